@@ -0,0 +1,164 @@
+use crate::error::AddressError;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE};
+use base64::Engine;
+use std::fmt;
+use std::str::FromStr;
+
+const BOUNCEABLE_TAG: u8 = 0x11;
+const NON_BOUNCEABLE_TAG: u8 = 0x51;
+const TESTNET_FLAG: u8 = 0x80;
+
+/// Standard internal address (`addr_std`) of a TON account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address {
+    workchain: i32,
+    hash_part: [u8; 32],
+}
+
+impl Address {
+    pub fn new(workchain: i32, hash_part: [u8; 32]) -> Self {
+        Self {
+            workchain,
+            hash_part,
+        }
+    }
+
+    pub fn workchain(&self) -> i32 {
+        self.workchain
+    }
+
+    pub fn hash_part(&self) -> &[u8; 32] {
+        &self.hash_part
+    }
+
+    /// Parse an address in raw `workchain:hex` form.
+    pub fn from_raw_string(address: &str) -> Result<Self, AddressError> {
+        let (workchain, hash) = address
+            .split_once(':')
+            .ok_or_else(|| AddressError::InvalidFormat(address.to_string()))?;
+        let workchain = workchain
+            .parse::<i32>()
+            .map_err(|_| AddressError::InvalidFormat(address.to_string()))?;
+        if hash.len() != 64 {
+            return Err(AddressError::InvalidFormat(address.to_string()));
+        }
+        let mut hash_part = [0u8; 32];
+        for (i, byte) in hash_part.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hash[i * 2..i * 2 + 2], 16)
+                .map_err(|_| AddressError::InvalidFormat(address.to_string()))?;
+        }
+        Ok(Self::new(workchain, hash_part))
+    }
+
+    /// Parse a user-friendly base64 or base64url address, returning it
+    /// together with its bounceable and testnet-only flags.
+    pub fn from_base64_with_flags(address: &str) -> Result<(Self, bool, bool), AddressError> {
+        if address.len() != 48 {
+            return Err(AddressError::InvalidFormat(address.to_string()));
+        }
+        let bytes = if address.contains(['-', '_']) {
+            URL_SAFE.decode(address)
+        } else {
+            STANDARD.decode(address)
+        }
+        .map_err(|_| AddressError::InvalidFormat(address.to_string()))?;
+        if bytes.len() != 36 {
+            return Err(AddressError::InvalidFormat(address.to_string()));
+        }
+
+        let checksum = u16::from_be_bytes([bytes[34], bytes[35]]);
+        if crc16(&bytes[..34]) != checksum {
+            return Err(AddressError::InvalidChecksum);
+        }
+
+        let tag = bytes[0];
+        let testnet = tag & TESTNET_FLAG != 0;
+        let bounceable = match tag & !TESTNET_FLAG {
+            BOUNCEABLE_TAG => true,
+            NON_BOUNCEABLE_TAG => false,
+            _ => return Err(AddressError::InvalidFormat(address.to_string())),
+        };
+
+        let workchain = bytes[1] as i8 as i32;
+        let mut hash_part = [0u8; 32];
+        hash_part.copy_from_slice(&bytes[2..34]);
+
+        Ok((Self::new(workchain, hash_part), bounceable, testnet))
+    }
+
+    pub fn from_base64(address: &str) -> Result<Self, AddressError> {
+        Self::from_base64_with_flags(address).map(|(address, _, _)| address)
+    }
+
+    /// Raw `workchain:hex` form.
+    pub fn to_raw_string(&self) -> String {
+        format!(
+            "{}:{}",
+            self.workchain,
+            crate::cell::hex_string(&self.hash_part)
+        )
+    }
+
+    /// User-friendly form with the given flags.
+    pub fn to_base64(&self, bounceable: bool, testnet: bool, url_safe: bool) -> String {
+        let mut bytes = [0u8; 36];
+        bytes[0] = if bounceable {
+            BOUNCEABLE_TAG
+        } else {
+            NON_BOUNCEABLE_TAG
+        };
+        if testnet {
+            bytes[0] |= TESTNET_FLAG;
+        }
+        bytes[1] = self.workchain as i8 as u8;
+        bytes[2..34].copy_from_slice(&self.hash_part);
+        let checksum = crc16(&bytes[..34]);
+        bytes[34..].copy_from_slice(&checksum.to_be_bytes());
+
+        if url_safe {
+            URL_SAFE.encode(bytes)
+        } else {
+            STANDARD.encode(bytes)
+        }
+    }
+
+    /// Bounceable url-safe mainnet form, e.g. `EQ...`.
+    pub fn to_base64_url(&self) -> String {
+        self.to_base64(true, false, true)
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    /// Parse an address in either raw or user-friendly form.
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        if address.contains(':') {
+            Self::from_raw_string(address)
+        } else {
+            Self::from_base64(address)
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_base64_url())
+    }
+}
+
+/// CRC-16/XMODEM used by user-friendly addresses.
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
@@ -0,0 +1,462 @@
+use super::{ArcCell, Cell, CellHash};
+use crate::error::CellError;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+const BOC_GENERIC_MAGIC: u32 = 0xb5ee9c72;
+const BOC_INDEXED_MAGIC: u32 = 0x68ff65f3;
+const BOC_INDEXED_CRC32_MAGIC: u32 = 0xacc3a728;
+/// Smallest serialized cell: the two descriptor bytes of an empty cell.
+const MIN_CELL_SIZE: usize = 2;
+
+struct RawCell {
+    data: Vec<u8>,
+    bit_len: usize,
+    references: Vec<usize>,
+    is_exotic: bool,
+}
+
+struct BocReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BocReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CellError> {
+        if self.pos + len > self.data.len() {
+            return Err(CellError::BocDeserialization(
+                "unexpected end of data".to_string(),
+            ));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_uint(&mut self, len: usize) -> Result<usize, CellError> {
+        Ok(self
+            .take(len)?
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize))
+    }
+}
+
+impl Cell {
+    /// Deserialize a bag of cells and return all of its roots.
+    pub fn from_boc_roots(boc: &[u8]) -> Result<Vec<ArcCell>, CellError> {
+        let mut reader = BocReader { data: boc, pos: 0 };
+
+        let magic = reader.read_uint(4)? as u32;
+        let (has_index, has_crc32c, size_bytes) = match magic {
+            BOC_GENERIC_MAGIC => {
+                let flags = reader.read_uint(1)?;
+                (flags & 0x80 != 0, flags & 0x40 != 0, flags & 0x07)
+            }
+            BOC_INDEXED_MAGIC => (true, false, reader.read_uint(1)?),
+            BOC_INDEXED_CRC32_MAGIC => (true, true, reader.read_uint(1)?),
+            other => {
+                return Err(CellError::BocDeserialization(format!(
+                    "unknown magic {:08x}",
+                    other
+                )))
+            }
+        };
+        if size_bytes == 0 || size_bytes > 4 {
+            return Err(CellError::BocDeserialization(format!(
+                "invalid reference size {}",
+                size_bytes
+            )));
+        }
+
+        let offset_bytes = reader.read_uint(1)?;
+        if offset_bytes == 0 || offset_bytes > 8 {
+            return Err(CellError::BocDeserialization(format!(
+                "invalid offset size {}",
+                offset_bytes
+            )));
+        }
+        let cells_count = reader.read_uint(size_bytes)?;
+        let roots_count = reader.read_uint(size_bytes)?;
+        let _absent_count = reader.read_uint(size_bytes)?;
+        let total_cells_size = reader.read_uint(offset_bytes)?;
+
+        // Counts come from untrusted input: bound them by the bytes left before allocating
+        let remaining = boc.len() - reader.pos;
+        if cells_count > remaining / MIN_CELL_SIZE || roots_count > cells_count {
+            return Err(CellError::BocDeserialization(format!(
+                "{} cells and {} roots do not fit into {} bytes",
+                cells_count, roots_count, remaining
+            )));
+        }
+        let root_list_size = if magic == BOC_GENERIC_MAGIC {
+            roots_count * size_bytes
+        } else {
+            0
+        };
+        let index_size = if has_index {
+            cells_count * offset_bytes
+        } else {
+            0
+        };
+        if root_list_size + index_size > remaining
+            || total_cells_size > remaining - root_list_size - index_size
+        {
+            return Err(CellError::BocDeserialization(
+                "header sizes exceed the data".to_string(),
+            ));
+        }
+
+        let root_indexes = if magic == BOC_GENERIC_MAGIC {
+            (0..roots_count)
+                .map(|_| reader.read_uint(size_bytes))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            (0..roots_count).collect()
+        };
+        reader.take(index_size)?;
+
+        let cells_start = reader.pos;
+        let mut raw_cells = Vec::with_capacity(cells_count);
+        for _ in 0..cells_count {
+            raw_cells.push(Self::read_raw_cell(&mut reader, size_bytes)?);
+        }
+        if reader.pos - cells_start != total_cells_size {
+            return Err(CellError::BocDeserialization(
+                "cells size does not match header".to_string(),
+            ));
+        }
+
+        if has_crc32c {
+            let expected = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+            if crc32c(&boc[..reader.pos - 4]) != expected {
+                return Err(CellError::BocDeserialization(
+                    "crc32c checksum mismatch".to_string(),
+                ));
+            }
+        }
+
+        let mut cells: Vec<Option<ArcCell>> = vec![None; cells_count];
+        for index in (0..cells_count).rev() {
+            let raw = &raw_cells[index];
+            let mut references = Vec::with_capacity(raw.references.len());
+            for &reference in &raw.references {
+                if reference <= index || reference >= cells_count {
+                    return Err(CellError::BocDeserialization(format!(
+                        "cell {} has invalid reference {}",
+                        index, reference
+                    )));
+                }
+                references.push(cells[reference].clone().unwrap());
+            }
+            let cell = Cell::new(raw.data.clone(), raw.bit_len, references, raw.is_exotic)?;
+            cells[index] = Some(Arc::new(cell));
+        }
+
+        root_indexes
+            .into_iter()
+            .map(|index| {
+                cells.get(index).cloned().flatten().ok_or_else(|| {
+                    CellError::BocDeserialization(format!("invalid root index {}", index))
+                })
+            })
+            .collect()
+    }
+
+    fn read_raw_cell(reader: &mut BocReader, size_bytes: usize) -> Result<RawCell, CellError> {
+        let d1 = reader.read_uint(1)? as u8;
+        let d2 = reader.read_uint(1)? as u8;
+
+        let refs_count = (d1 & 0x07) as usize;
+        let is_exotic = d1 & 0x08 != 0;
+        let with_hashes = d1 & 0x10 != 0;
+        let level_mask = super::LevelMask::new(d1 >> 5);
+        if refs_count > super::MAX_CELL_REFS {
+            return Err(CellError::BocDeserialization(
+                "absent cells are not supported".to_string(),
+            ));
+        }
+
+        if with_hashes {
+            reader.take(level_mask.hash_count() * (32 + 2))?;
+        }
+
+        let data_len = d2.div_ceil(2) as usize;
+        let mut data = reader.take(data_len)?.to_vec();
+        let bit_len = if d2.is_multiple_of(2) {
+            data_len * 8
+        } else {
+            let last = data[data_len - 1];
+            if last == 0 {
+                return Err(CellError::BocDeserialization(
+                    "missing completion tag".to_string(),
+                ));
+            }
+            let trailing = last.trailing_zeros() as usize;
+            data[data_len - 1] &= !(1 << trailing);
+            data_len * 8 - trailing - 1
+        };
+
+        let references = (0..refs_count)
+            .map(|_| reader.read_uint(size_bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RawCell {
+            data,
+            bit_len,
+            references,
+            is_exotic,
+        })
+    }
+
+    /// Deserialize a bag of cells with a single root.
+    pub fn from_boc(boc: &[u8]) -> Result<Cell, CellError> {
+        let mut roots = Self::from_boc_roots(boc)?;
+        if roots.len() != 1 {
+            return Err(CellError::BocDeserialization(format!(
+                "expected 1 root, found {}",
+                roots.len()
+            )));
+        }
+        let root = roots.remove(0);
+        Ok(Arc::try_unwrap(root).unwrap_or_else(|root| (*root).clone()))
+    }
+
+    pub fn from_boc_b64(boc: &str) -> Result<Cell, CellError> {
+        let bytes = STANDARD
+            .decode(boc)
+            .map_err(|e| CellError::BocDeserialization(e.to_string()))?;
+        Self::from_boc(&bytes)
+    }
+
+    pub fn from_boc_hex(boc: &str) -> Result<Cell, CellError> {
+        let boc = boc.trim();
+        if !boc.len().is_multiple_of(2) {
+            return Err(CellError::BocDeserialization(
+                "odd length hex string".to_string(),
+            ));
+        }
+        let bytes = (0..boc.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&boc[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| CellError::BocDeserialization(e.to_string()))?;
+        Self::from_boc(&bytes)
+    }
+
+    /// Serialize this cell as a single-root bag of cells.
+    pub fn to_boc(&self, has_crc32c: bool) -> Result<Vec<u8>, CellError> {
+        serialize_boc(&[Arc::new(self.clone())], has_crc32c)
+    }
+
+    /// Serialize this cell as a base64-encoded bag of cells with a crc32c checksum.
+    pub fn to_boc_b64(&self) -> Result<String, CellError> {
+        Ok(STANDARD.encode(self.to_boc(true)?))
+    }
+}
+
+/// Serialize a bag of cells containing the given roots.
+pub fn serialize_boc(roots: &[ArcCell], has_crc32c: bool) -> Result<Vec<u8>, CellError> {
+    // Reverse post-order of a depth-first walk puts every cell before the cells it references.
+    let mut visited: HashSet<CellHash> = HashSet::new();
+    let mut post_order: Vec<ArcCell> = Vec::new();
+    for root in roots {
+        visit(root, &mut visited, &mut post_order);
+    }
+    post_order.reverse();
+    let ordered = post_order;
+
+    let indexes: HashMap<CellHash, usize> = ordered
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| (cell.repr_hash(), i))
+        .collect();
+
+    let cells_count = ordered.len();
+    let size_bytes = byte_len(cells_count as u64).max(1);
+
+    let mut cells_data = Vec::new();
+    for cell in &ordered {
+        cells_data.extend_from_slice(&cell.descriptors(cell.level_mask()));
+        cells_data.extend_from_slice(&cell.padded_data());
+        for reference in cell.references() {
+            let index = indexes[&reference.repr_hash()];
+            cells_data.extend_from_slice(&index.to_be_bytes()[8 - size_bytes..]);
+        }
+    }
+    let offset_bytes = byte_len(cells_data.len() as u64).max(1);
+
+    let mut boc = Vec::with_capacity(cells_data.len() + 32);
+    boc.extend_from_slice(&BOC_GENERIC_MAGIC.to_be_bytes());
+    let mut flags = size_bytes as u8;
+    if has_crc32c {
+        flags |= 0x40;
+    }
+    boc.push(flags);
+    boc.push(offset_bytes as u8);
+    boc.extend_from_slice(&cells_count.to_be_bytes()[8 - size_bytes..]);
+    boc.extend_from_slice(&roots.len().to_be_bytes()[8 - size_bytes..]);
+    boc.extend_from_slice(&0usize.to_be_bytes()[8 - size_bytes..]);
+    boc.extend_from_slice(&cells_data.len().to_be_bytes()[8 - offset_bytes..]);
+    for root in roots {
+        let index = indexes[&root.repr_hash()];
+        boc.extend_from_slice(&index.to_be_bytes()[8 - size_bytes..]);
+    }
+    boc.extend_from_slice(&cells_data);
+
+    if has_crc32c {
+        let checksum = crc32c(&boc);
+        boc.extend_from_slice(&checksum.to_le_bytes());
+    }
+
+    Ok(boc)
+}
+
+fn visit(cell: &ArcCell, visited: &mut HashSet<CellHash>, post_order: &mut Vec<ArcCell>) {
+    if !visited.insert(cell.repr_hash()) {
+        return;
+    }
+    for reference in cell.references().iter().rev() {
+        visit(reference, visited, post_order);
+    }
+    post_order.push(cell.clone());
+}
+
+fn byte_len(value: u64) -> usize {
+    (64 - value.leading_zeros() as usize).div_ceil(8)
+}
+
+pub(crate) fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82f63b78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{hex_string, CellBuilder};
+
+    /// Wallet v3R2 code, a single cell.
+    const WALLET_V3R2_CODE: &str = "te6cckEBAQEAcQAA3v8AIN0gggFMl7ohggEznLqxn3Gw7UTQ0x/THzHXC//jBOCk8mCDCNcYINMf0x/TH/gjE7vyY+1E0NMf0x/T/9FRMrryoVFEuvKiBPkBVBBV+RDyo/gAkyDXSpbTB9QC+wDo0QGkyMsfyx/L/8ntVBC9ba0=";
+
+    fn hash_hex(cell: &Cell) -> String {
+        hex_string(&cell.repr_hash())
+    }
+
+    #[test]
+    fn empty_cell_hash() {
+        assert_eq!(
+            hash_hex(&Cell::empty()),
+            "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7"
+        );
+    }
+
+    #[test]
+    fn wallet_code_hashes() {
+        let v3 = Cell::from_boc_b64(WALLET_V3R2_CODE).unwrap();
+        assert_eq!(
+            hash_hex(&v3),
+            "84dafa449f98a6987789ba232358072bc0f76dc4524002a5d0918b9a75d2d599"
+        );
+        assert_eq!(
+            hash_hex(&crate::wallet::WalletV4R2::code()),
+            "feb5ff6820e2ff0d9483e7e0d62c817d846789fb4ae580c878866d959dabd5c0"
+        );
+        assert_eq!(
+            hash_hex(&crate::wallet::WalletV5R1::code()),
+            "20834b7b72b112147e1b2fb457b84e74d1a30f04f737d4f62a668e9552d2b72f"
+        );
+    }
+
+    #[test]
+    fn round_trip_single_cell() {
+        let cell = Cell::from_boc_b64(WALLET_V3R2_CODE).unwrap();
+        assert_eq!(cell.to_boc_b64().unwrap(), WALLET_V3R2_CODE);
+    }
+
+    #[test]
+    fn round_trip_tree() {
+        let code = crate::wallet::WalletV4R2::code();
+        for has_crc32c in [false, true] {
+            let boc = code.to_boc(has_crc32c).unwrap();
+            let parsed = Cell::from_boc(&boc).unwrap();
+            assert_eq!(parsed.repr_hash(), code.repr_hash());
+            assert_eq!(parsed.repr_depth(), code.repr_depth());
+            assert_eq!(parsed.to_boc(has_crc32c).unwrap(), boc);
+        }
+    }
+
+    #[test]
+    fn round_trip_shared_cells_and_roots() {
+        let mut leaf = CellBuilder::new();
+        leaf.store_u32(0xdeadbeef).unwrap();
+        let leaf = leaf.build().unwrap().to_arc();
+        let mut root = CellBuilder::new();
+        root.store_uint(5, 0b10110)
+            .unwrap()
+            .store_reference(leaf.clone())
+            .unwrap()
+            .store_reference(leaf.clone())
+            .unwrap();
+        let root = root.build().unwrap().to_arc();
+
+        let boc = serialize_boc(&[root.clone(), leaf.clone()], false).unwrap();
+        let roots = Cell::from_boc_roots(&boc).unwrap();
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].repr_hash(), root.repr_hash());
+        assert_eq!(roots[1].repr_hash(), leaf.repr_hash());
+        assert_eq!(roots[0].bit_len(), 5);
+        // The shared leaf is stored once
+        assert_eq!(boc[6], 2);
+    }
+
+    #[test]
+    fn rejects_bad_checksum() {
+        let mut boc = Cell::from_boc_b64(WALLET_V3R2_CODE)
+            .unwrap()
+            .to_boc(true)
+            .unwrap();
+        let last = boc.len() - 1;
+        boc[last] ^= 1;
+        assert!(Cell::from_boc(&boc).is_err());
+    }
+
+    #[test]
+    fn rejects_huge_counts() {
+        // 4-byte sizes claiming 2^32 - 1 cells and roots in a few bytes
+        let mut boc = BOC_GENERIC_MAGIC.to_be_bytes().to_vec();
+        boc.extend_from_slice(&[0x04, 0x01]);
+        boc.extend_from_slice(&[0xff; 4]);
+        boc.extend_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+        boc.extend_from_slice(&[0x00; 4]);
+        boc.extend_from_slice(&[0x02, 0x00, 0x00]);
+        assert!(Cell::from_boc_roots(&boc).is_err());
+
+        let mut boc = BOC_GENERIC_MAGIC.to_be_bytes().to_vec();
+        boc.extend_from_slice(&[0x04, 0x01]);
+        boc.extend_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+        boc.extend_from_slice(&[0xff; 4]);
+        boc.extend_from_slice(&[0x00; 4]);
+        boc.extend_from_slice(&[0x02, 0x00, 0x00]);
+        assert!(Cell::from_boc_roots(&boc).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_boc() {
+        let boc = crate::wallet::WalletV4R2::code().to_boc(false).unwrap();
+        for len in 0..boc.len() {
+            assert!(Cell::from_boc_roots(&boc[..len]).is_err());
+        }
+    }
+}
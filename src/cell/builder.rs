@@ -0,0 +1,285 @@
+use super::{ArcCell, Cell, CellSlice, MAX_CELL_BITS, MAX_CELL_REFS};
+use crate::address::Address;
+use crate::error::CellError;
+use num_bigint::{BigInt, BigUint, Sign};
+
+/// Incrementally writes bits and references into a new ordinary cell.
+#[derive(Debug, Clone, Default)]
+pub struct CellBuilder {
+    data: Vec<u8>,
+    bit_len: usize,
+    references: Vec<ArcCell>,
+    is_exotic: bool,
+}
+
+impl CellBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the resulting cell as exotic; its type is read from the first data byte.
+    pub fn set_exotic(&mut self, is_exotic: bool) -> &mut Self {
+        self.is_exotic = is_exotic;
+        self
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn remaining_bits(&self) -> usize {
+        MAX_CELL_BITS - self.bit_len
+    }
+
+    pub fn remaining_refs(&self) -> usize {
+        MAX_CELL_REFS - self.references.len()
+    }
+
+    pub fn store_bit(&mut self, bit: bool) -> Result<&mut Self, CellError> {
+        if self.bit_len >= MAX_CELL_BITS {
+            return Err(CellError::CellOverflow);
+        }
+        if self.bit_len.is_multiple_of(8) {
+            self.data.push(0);
+        }
+        if bit {
+            let last = self.data.len() - 1;
+            self.data[last] |= 1 << (7 - self.bit_len % 8);
+        }
+        self.bit_len += 1;
+        Ok(self)
+    }
+
+    /// Store the first `bit_len` bits of `data`.
+    pub fn store_bits(&mut self, data: &[u8], bit_len: usize) -> Result<&mut Self, CellError> {
+        if data.len() * 8 < bit_len {
+            return Err(CellError::CellUnderflow);
+        }
+        if bit_len > self.remaining_bits() {
+            return Err(CellError::CellOverflow);
+        }
+        if self.bit_len.is_multiple_of(8) {
+            let full_bytes = bit_len / 8;
+            self.data.extend_from_slice(&data[..full_bytes]);
+            self.bit_len += full_bytes * 8;
+            for i in full_bytes * 8..bit_len {
+                self.store_bit(data[i / 8] & (1 << (7 - i % 8)) != 0)?;
+            }
+        } else {
+            for i in 0..bit_len {
+                self.store_bit(data[i / 8] & (1 << (7 - i % 8)) != 0)?;
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn store_bytes(&mut self, data: &[u8]) -> Result<&mut Self, CellError> {
+        self.store_bits(data, data.len() * 8)
+    }
+
+    pub fn store_zeros(&mut self, bit_len: usize) -> Result<&mut Self, CellError> {
+        if bit_len > self.remaining_bits() {
+            return Err(CellError::CellOverflow);
+        }
+        for _ in 0..bit_len {
+            self.store_bit(false)?;
+        }
+        Ok(self)
+    }
+
+    /// Store an unsigned integer using `bit_len` bits (up to 64).
+    pub fn store_uint(&mut self, bit_len: usize, value: u64) -> Result<&mut Self, CellError> {
+        if bit_len > 64 || (bit_len < 64 && value >> bit_len != 0) {
+            return Err(CellError::InvalidCell(format!(
+                "value {} does not fit into {} bits",
+                value, bit_len
+            )));
+        }
+        if bit_len > self.remaining_bits() {
+            return Err(CellError::CellOverflow);
+        }
+        for i in (0..bit_len).rev() {
+            self.store_bit((value >> i) & 1 != 0)?;
+        }
+        Ok(self)
+    }
+
+    /// Store a signed integer in two's complement using `bit_len` bits (up to 64).
+    pub fn store_int(&mut self, bit_len: usize, value: i64) -> Result<&mut Self, CellError> {
+        if bit_len == 0 || bit_len > 64 {
+            return Err(CellError::InvalidCell(format!(
+                "can not store signed integer in {} bits",
+                bit_len
+            )));
+        }
+        if bit_len < 64 {
+            let bound = 1i64 << (bit_len - 1);
+            if value < -bound || value >= bound {
+                return Err(CellError::InvalidCell(format!(
+                    "value {} does not fit into {} bits",
+                    value, bit_len
+                )));
+            }
+        }
+        let mask = if bit_len == 64 {
+            u64::MAX
+        } else {
+            (1u64 << bit_len) - 1
+        };
+        self.store_uint(bit_len, value as u64 & mask)
+    }
+
+    pub fn store_u8(&mut self, value: u8) -> Result<&mut Self, CellError> {
+        self.store_uint(8, value as u64)
+    }
+
+    pub fn store_u32(&mut self, value: u32) -> Result<&mut Self, CellError> {
+        self.store_uint(32, value as u64)
+    }
+
+    pub fn store_u64(&mut self, value: u64) -> Result<&mut Self, CellError> {
+        self.store_uint(64, value)
+    }
+
+    pub fn store_i8(&mut self, value: i8) -> Result<&mut Self, CellError> {
+        self.store_int(8, value as i64)
+    }
+
+    pub fn store_i32(&mut self, value: i32) -> Result<&mut Self, CellError> {
+        self.store_int(32, value as i64)
+    }
+
+    /// Store an arbitrary size unsigned integer using `bit_len` bits.
+    pub fn store_biguint(
+        &mut self,
+        bit_len: usize,
+        value: &BigUint,
+    ) -> Result<&mut Self, CellError> {
+        if value.bits() as usize > bit_len {
+            return Err(CellError::InvalidCell(format!(
+                "value {} does not fit into {} bits",
+                value, bit_len
+            )));
+        }
+        if bit_len > self.remaining_bits() {
+            return Err(CellError::CellOverflow);
+        }
+        for i in (0..bit_len as u64).rev() {
+            self.store_bit(value.bit(i))?;
+        }
+        Ok(self)
+    }
+
+    /// Store an arbitrary size signed integer in two's complement using `bit_len` bits.
+    pub fn store_bigint(&mut self, bit_len: usize, value: &BigInt) -> Result<&mut Self, CellError> {
+        if bit_len == 0 {
+            return Err(CellError::InvalidCell(
+                "can not store signed integer in 0 bits".to_string(),
+            ));
+        }
+        let modulus = BigInt::from(1) << bit_len;
+        let bound = BigInt::from(1) << (bit_len - 1);
+        if *value < -bound.clone() || *value >= bound {
+            return Err(CellError::InvalidCell(format!(
+                "value {} does not fit into {} bits",
+                value, bit_len
+            )));
+        }
+        let unsigned = if value.sign() == Sign::Minus {
+            value + modulus
+        } else {
+            value.clone()
+        };
+        let (_, magnitude) = unsigned.into_parts();
+        self.store_biguint(bit_len, &magnitude)
+    }
+
+    /// Store an amount of nanotons as `VarUInteger 16`.
+    pub fn store_coins(&mut self, amount: u128) -> Result<&mut Self, CellError> {
+        let byte_len = (128 - amount.leading_zeros() as usize).div_ceil(8);
+        if byte_len > 15 {
+            return Err(CellError::InvalidCell(format!(
+                "amount {} does not fit into VarUInteger 16",
+                amount
+            )));
+        }
+        self.store_uint(4, byte_len as u64)?;
+        let bytes = amount.to_be_bytes();
+        self.store_bytes(&bytes[16 - byte_len..])
+    }
+
+    /// Store `MsgAddressInt` as `addr_std` without anycast.
+    pub fn store_address(&mut self, address: &Address) -> Result<&mut Self, CellError> {
+        let workchain = i8::try_from(address.workchain()).map_err(|_| {
+            CellError::InvalidCell(format!(
+                "workchain {} does not fit into addr_std",
+                address.workchain()
+            ))
+        })?;
+        self.store_uint(2, 0b10)?;
+        self.store_bit(false)?;
+        self.store_i8(workchain)?;
+        self.store_bytes(address.hash_part())
+    }
+
+    /// Store `addr_none`.
+    pub fn store_address_none(&mut self) -> Result<&mut Self, CellError> {
+        self.store_uint(2, 0b00)
+    }
+
+    /// Store `MsgAddress` as either `addr_std` or `addr_none`.
+    pub fn store_optional_address(
+        &mut self,
+        address: Option<&Address>,
+    ) -> Result<&mut Self, CellError> {
+        match address {
+            Some(address) => self.store_address(address),
+            None => self.store_address_none(),
+        }
+    }
+
+    /// Append the remaining bits and references of a slice.
+    pub fn store_slice(&mut self, slice: &CellSlice) -> Result<&mut Self, CellError> {
+        let mut slice = slice.clone();
+        let bit_len = slice.remaining_bits();
+        let bits = slice.load_bits(bit_len)?;
+        self.store_bits(&bits, bit_len)?;
+        while slice.remaining_refs() > 0 {
+            self.store_reference(slice.load_reference()?)?;
+        }
+        Ok(self)
+    }
+
+    /// Append all bits and references of a cell.
+    pub fn store_cell(&mut self, cell: &ArcCell) -> Result<&mut Self, CellError> {
+        self.store_slice(&cell.parser())
+    }
+
+    pub fn store_reference(&mut self, cell: ArcCell) -> Result<&mut Self, CellError> {
+        if self.references.len() >= MAX_CELL_REFS {
+            return Err(CellError::CellOverflow);
+        }
+        self.references.push(cell);
+        Ok(self)
+    }
+
+    /// Store `Maybe ^Cell`: a presence bit followed by an optional reference.
+    pub fn store_maybe_reference(&mut self, cell: Option<ArcCell>) -> Result<&mut Self, CellError> {
+        match cell {
+            Some(cell) => {
+                self.store_bit(true)?;
+                self.store_reference(cell)
+            }
+            None => self.store_bit(false),
+        }
+    }
+
+    pub fn build(&self) -> Result<Cell, CellError> {
+        Cell::new(
+            self.data.clone(),
+            self.bit_len,
+            self.references.clone(),
+            self.is_exotic,
+        )
+    }
+}
@@ -0,0 +1,209 @@
+use super::{ArcCell, Cell, CellBuilder, CellSlice};
+use crate::error::CellError;
+use num_bigint::BigUint;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+impl CellSlice {
+    /// Load `HashmapE n X`: a presence bit followed by an optional reference to the root.
+    ///
+    /// Values are returned as slices positioned right after the leaf label.
+    pub fn load_dict(
+        &mut self,
+        key_bits: usize,
+    ) -> Result<BTreeMap<BigUint, CellSlice>, CellError> {
+        let mut entries = BTreeMap::new();
+        if let Some(root) = self.load_maybe_reference()? {
+            parse_edge(root.parser(), key_bits, &mut Vec::new(), &mut entries)?;
+        }
+        Ok(entries)
+    }
+
+    /// Load a non-empty `Hashmap n X` stored inline, consuming the rest of the slice.
+    pub fn load_hashmap(
+        &mut self,
+        key_bits: usize,
+    ) -> Result<BTreeMap<BigUint, CellSlice>, CellError> {
+        let remaining_bits = self.remaining_bits();
+        let remaining_refs = self.remaining_refs();
+        let root = self.load_slice(remaining_bits, remaining_refs)?;
+
+        let mut entries = BTreeMap::new();
+        parse_edge(root, key_bits, &mut Vec::new(), &mut entries)?;
+        Ok(entries)
+    }
+}
+
+fn parse_edge(
+    mut slice: CellSlice,
+    key_bits: usize,
+    prefix: &mut Vec<bool>,
+    entries: &mut BTreeMap<BigUint, CellSlice>,
+) -> Result<(), CellError> {
+    let label = load_label(&mut slice, key_bits)?;
+    let prefix_len = prefix.len();
+    prefix.extend_from_slice(&label);
+    let remaining = key_bits - label.len();
+
+    if remaining == 0 {
+        entries.insert(bits_to_key(prefix), slice);
+    } else {
+        let left = slice.load_reference()?;
+        let right = slice.load_reference()?;
+        for (bit, child) in [(false, left), (true, right)] {
+            prefix.push(bit);
+            parse_edge(child.parser(), remaining - 1, prefix, entries)?;
+            prefix.pop();
+        }
+    }
+
+    prefix.truncate(prefix_len);
+    Ok(())
+}
+
+/// Load `HmLabel ~n m`.
+pub(crate) fn load_label(slice: &mut CellSlice, max_len: usize) -> Result<Vec<bool>, CellError> {
+    let len_bits = usize::BITS as usize - max_len.leading_zeros() as usize;
+
+    let label = if !slice.load_bit()? {
+        // hml_short$0
+        let mut len = 0;
+        while slice.load_bit()? {
+            len += 1;
+        }
+        (0..len)
+            .map(|_| slice.load_bit())
+            .collect::<Result<Vec<_>, _>>()?
+    } else if !slice.load_bit()? {
+        // hml_long$10
+        let len = slice.load_uint(len_bits)? as usize;
+        (0..len)
+            .map(|_| slice.load_bit())
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        // hml_same$11
+        let bit = slice.load_bit()?;
+        let len = slice.load_uint(len_bits)? as usize;
+        vec![bit; len]
+    };
+
+    if label.len() > max_len {
+        return Err(CellError::InvalidCell(format!(
+            "dictionary label of {} bits exceeds {} bits",
+            label.len(),
+            max_len
+        )));
+    }
+    Ok(label)
+}
+
+fn bits_to_key(bits: &[bool]) -> BigUint {
+    bits.iter().fold(BigUint::default(), |key, bit| {
+        (key << 1u8) + BigUint::from(*bit as u8)
+    })
+}
+
+impl CellBuilder {
+    /// Store `HashmapE n X` where each value cell is inlined into its leaf.
+    pub fn store_dict(
+        &mut self,
+        key_bits: usize,
+        entries: &BTreeMap<BigUint, ArcCell>,
+    ) -> Result<&mut Self, CellError> {
+        if entries.is_empty() {
+            return self.store_bit(false);
+        }
+        let root = build_hashmap(key_bits, entries)?;
+        self.store_maybe_reference(Some(Arc::new(root)))
+    }
+}
+
+/// Build the root cell of a non-empty `Hashmap n X` where each value cell is inlined into its leaf.
+pub fn build_hashmap(
+    key_bits: usize,
+    entries: &BTreeMap<BigUint, ArcCell>,
+) -> Result<Cell, CellError> {
+    if entries.is_empty() {
+        return Err(CellError::InvalidCell(
+            "can not build an empty Hashmap".to_string(),
+        ));
+    }
+    let entries = entries
+        .iter()
+        .map(|(key, value)| {
+            if key.bits() as usize > key_bits {
+                return Err(CellError::InvalidCell(format!(
+                    "dictionary key {} does not fit into {} bits",
+                    key, key_bits
+                )));
+            }
+            let bits = (0..key_bits as u64).rev().map(|i| key.bit(i)).collect();
+            Ok((bits, value.clone()))
+        })
+        .collect::<Result<Vec<(Vec<bool>, ArcCell)>, CellError>>()?;
+
+    build_edge(&entries, key_bits)
+}
+
+fn build_edge(entries: &[(Vec<bool>, ArcCell)], key_bits: usize) -> Result<Cell, CellError> {
+    let mut builder = CellBuilder::new();
+
+    if entries.len() == 1 {
+        let (key, value) = &entries[0];
+        store_label(&mut builder, key, key_bits)?;
+        builder.store_cell(value)?;
+        return builder.build();
+    }
+
+    let first = &entries[0].0;
+    let last = &entries[entries.len() - 1].0;
+    let prefix_len = first
+        .iter()
+        .zip(last.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    store_label(&mut builder, &first[..prefix_len], key_bits)?;
+
+    let split = entries.partition_point(|(key, _)| !key[prefix_len]);
+    for side in [&entries[..split], &entries[split..]] {
+        let side = side
+            .iter()
+            .map(|(key, value)| (key[prefix_len + 1..].to_vec(), value.clone()))
+            .collect::<Vec<_>>();
+        let child = build_edge(&side, key_bits - prefix_len - 1)?;
+        builder.store_reference(Arc::new(child))?;
+    }
+
+    builder.build()
+}
+
+/// Store `HmLabel ~n m` using the shortest encoding.
+fn store_label(builder: &mut CellBuilder, label: &[bool], max_len: usize) -> Result<(), CellError> {
+    let len_bits = usize::BITS as usize - max_len.leading_zeros() as usize;
+    let short_len = 2 * label.len() + 2;
+    let long_len = 2 + len_bits + label.len();
+    let same_len = 3 + len_bits;
+    let is_same = !label.is_empty() && label.iter().all(|bit| *bit == label[0]);
+
+    if is_same && same_len < short_len.min(long_len) {
+        builder.store_uint(2, 0b11)?;
+        builder.store_bit(label[0])?;
+        builder.store_uint(len_bits, label.len() as u64)?;
+    } else if long_len < short_len {
+        builder.store_uint(2, 0b10)?;
+        builder.store_uint(len_bits, label.len() as u64)?;
+        for bit in label {
+            builder.store_bit(*bit)?;
+        }
+    } else {
+        builder.store_bit(false)?;
+        for _ in 0..label.len() {
+            builder.store_bit(true)?;
+        }
+        builder.store_bit(false)?;
+        for bit in label {
+            builder.store_bit(*bit)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,405 @@
+mod boc;
+mod builder;
+mod dict;
+mod slice;
+
+pub use self::boc::serialize_boc;
+pub use self::builder::CellBuilder;
+pub use self::dict::build_hashmap;
+pub(crate) use self::dict::load_label;
+pub use self::slice::CellSlice;
+
+use crate::error::CellError;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::Arc;
+
+pub type ArcCell = Arc<Cell>;
+pub type CellHash = [u8; 32];
+
+pub const MAX_CELL_BITS: usize = 1023;
+pub const MAX_CELL_REFS: usize = 4;
+pub const MAX_LEVEL: u8 = 3;
+
+/// Type of a cell as defined by the first byte of an exotic cell's data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CellType {
+    Ordinary,
+    PrunedBranch,
+    Library,
+    MerkleProof,
+    MerkleUpdate,
+}
+
+/// Level mask of a cell, i.e. the set of Merkle levels its hashes depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LevelMask(u8);
+
+impl LevelMask {
+    pub fn new(mask: u8) -> Self {
+        Self(mask & 0b111)
+    }
+
+    pub fn mask(&self) -> u8 {
+        self.0
+    }
+
+    /// Highest level present in the mask.
+    pub fn level(&self) -> u8 {
+        8 - self.0.leading_zeros() as u8
+    }
+
+    /// Index of the hash corresponding to this mask.
+    pub fn hash_index(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Number of hashes a cell with this mask has.
+    pub fn hash_count(&self) -> usize {
+        self.hash_index() + 1
+    }
+
+    /// Mask truncated to the given level.
+    pub fn apply(&self, level: u8) -> Self {
+        Self(self.0 & ((1u8 << level) - 1))
+    }
+
+    pub fn is_significant(&self, level: u8) -> bool {
+        level == 0 || (self.0 >> (level - 1)) & 1 != 0
+    }
+
+    pub fn shift_right(&self) -> Self {
+        Self(self.0 >> 1)
+    }
+}
+
+/// A TVM cell: up to 1023 bits of data and up to 4 references to other cells.
+///
+/// Hashes and depths of every significant level are computed on construction,
+/// so a `Cell` is immutable once built.
+#[derive(Clone)]
+pub struct Cell {
+    data: Vec<u8>,
+    bit_len: usize,
+    references: Vec<ArcCell>,
+    cell_type: CellType,
+    level_mask: LevelMask,
+    hashes: Vec<CellHash>,
+    depths: Vec<u16>,
+}
+
+impl Cell {
+    /// Create an ordinary or exotic cell. For exotic cells the type is taken from the first data byte.
+    pub fn new(
+        data: Vec<u8>,
+        bit_len: usize,
+        references: Vec<ArcCell>,
+        is_exotic: bool,
+    ) -> Result<Self, CellError> {
+        if bit_len > MAX_CELL_BITS {
+            return Err(CellError::CellOverflow);
+        }
+        if references.len() > MAX_CELL_REFS {
+            return Err(CellError::CellOverflow);
+        }
+        if data.len() * 8 < bit_len {
+            return Err(CellError::InvalidCell(format!(
+                "{} bytes can not hold {} bits",
+                data.len(),
+                bit_len
+            )));
+        }
+
+        let mut data = data;
+        data.truncate(bit_len.div_ceil(8));
+        if !bit_len.is_multiple_of(8) {
+            let last = data.len() - 1;
+            data[last] &= 0xffu8 << (8 - bit_len % 8);
+        }
+
+        let cell_type = if is_exotic {
+            Self::exotic_type(&data, bit_len)?
+        } else {
+            CellType::Ordinary
+        };
+        let level_mask = Self::compute_level_mask(cell_type, &data, &references)?;
+
+        let mut cell = Self {
+            data,
+            bit_len,
+            references,
+            cell_type,
+            level_mask,
+            hashes: Vec::new(),
+            depths: Vec::new(),
+        };
+        cell.compute_hashes();
+
+        Ok(cell)
+    }
+
+    /// An ordinary cell without data and references.
+    pub fn empty() -> Self {
+        Self::new(Vec::new(), 0, Vec::new(), false).expect("empty cell is always valid")
+    }
+
+    fn exotic_type(data: &[u8], bit_len: usize) -> Result<CellType, CellError> {
+        if bit_len < 8 {
+            return Err(CellError::InvalidCell(
+                "exotic cell must have at least 8 bits".to_string(),
+            ));
+        }
+        match data[0] {
+            1 => Ok(CellType::PrunedBranch),
+            2 => Ok(CellType::Library),
+            3 => Ok(CellType::MerkleProof),
+            4 => Ok(CellType::MerkleUpdate),
+            other => Err(CellError::InvalidCell(format!(
+                "unknown exotic cell type {}",
+                other
+            ))),
+        }
+    }
+
+    fn compute_level_mask(
+        cell_type: CellType,
+        data: &[u8],
+        references: &[ArcCell],
+    ) -> Result<LevelMask, CellError> {
+        let children_mask = references
+            .iter()
+            .fold(0u8, |mask, r| mask | r.level_mask.mask());
+
+        match cell_type {
+            CellType::Ordinary => Ok(LevelMask::new(children_mask)),
+            CellType::PrunedBranch => {
+                if data.len() < 2 {
+                    return Err(CellError::InvalidCell(
+                        "pruned branch is too short".to_string(),
+                    ));
+                }
+                let mask = LevelMask::new(data[1]);
+                let expected_len = 2 + mask.hash_index() * (32 + 2);
+                if mask.mask() == 0 || data.len() != expected_len || !references.is_empty() {
+                    return Err(CellError::InvalidCell(
+                        "malformed pruned branch".to_string(),
+                    ));
+                }
+                Ok(mask)
+            }
+            CellType::Library => {
+                if data.len() != 1 + 32 || !references.is_empty() {
+                    return Err(CellError::InvalidCell("malformed library cell".to_string()));
+                }
+                Ok(LevelMask::default())
+            }
+            CellType::MerkleProof => {
+                if data.len() != 1 + 32 + 2 || references.len() != 1 {
+                    return Err(CellError::InvalidCell("malformed merkle proof".to_string()));
+                }
+                Ok(LevelMask::new(children_mask).shift_right())
+            }
+            CellType::MerkleUpdate => {
+                if data.len() != 1 + 2 * (32 + 2) || references.len() != 2 {
+                    return Err(CellError::InvalidCell(
+                        "malformed merkle update".to_string(),
+                    ));
+                }
+                Ok(LevelMask::new(children_mask).shift_right())
+            }
+        }
+    }
+
+    fn compute_hashes(&mut self) {
+        let is_pruned = self.cell_type == CellType::PrunedBranch;
+        let is_merkle = matches!(
+            self.cell_type,
+            CellType::MerkleProof | CellType::MerkleUpdate
+        );
+        let total_hash_count = self.level_mask.hash_count();
+        let hash_count = if is_pruned { 1 } else { total_hash_count };
+        let hash_offset = total_hash_count - hash_count;
+
+        let mut hashes: Vec<CellHash> = Vec::with_capacity(hash_count);
+        let mut depths: Vec<u16> = Vec::with_capacity(hash_count);
+
+        let mut hash_i = 0;
+        for level in 0..=self.level_mask.level() {
+            if !self.level_mask.is_significant(level) {
+                continue;
+            }
+            if hash_i < hash_offset {
+                hash_i += 1;
+                continue;
+            }
+
+            let mut hasher = Sha256::new();
+            hasher.update(self.descriptors(self.level_mask.apply(level)));
+            if hash_i == hash_offset {
+                hasher.update(self.padded_data());
+            } else {
+                hasher.update(hashes[hash_i - hash_offset - 1]);
+            }
+
+            let child_level = if is_merkle { level + 1 } else { level };
+            let mut depth = 0;
+            for reference in &self.references {
+                let child_depth = reference.depth(child_level);
+                depth = depth.max(child_depth + 1);
+                hasher.update(child_depth.to_be_bytes());
+            }
+            for reference in &self.references {
+                hasher.update(reference.hash(child_level));
+            }
+
+            hashes.push(hasher.finalize().into());
+            depths.push(depth);
+            hash_i += 1;
+        }
+
+        self.hashes = hashes;
+        self.depths = depths;
+    }
+
+    /// The two descriptor bytes `d1` and `d2` of the standard cell representation.
+    pub(crate) fn descriptors(&self, level_mask: LevelMask) -> [u8; 2] {
+        let d1 = self.references.len() as u8
+            + if self.is_exotic() { 8 } else { 0 }
+            + 32 * level_mask.mask();
+        let d2 = (self.bit_len / 8 + self.bit_len.div_ceil(8)) as u8;
+        [d1, d2]
+    }
+
+    /// Data bytes with the completion tag appended when the bit length is not byte aligned.
+    pub(crate) fn padded_data(&self) -> Vec<u8> {
+        let mut data = self.data.clone();
+        if !self.bit_len.is_multiple_of(8) {
+            let last = data.len() - 1;
+            data[last] |= 1 << (7 - self.bit_len % 8);
+        }
+        data
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn references(&self) -> &[ArcCell] {
+        &self.references
+    }
+
+    pub fn reference(&self, index: usize) -> Result<&ArcCell, CellError> {
+        self.references.get(index).ok_or(CellError::CellUnderflow)
+    }
+
+    pub fn cell_type(&self) -> CellType {
+        self.cell_type
+    }
+
+    pub fn is_exotic(&self) -> bool {
+        self.cell_type != CellType::Ordinary
+    }
+
+    pub fn level_mask(&self) -> LevelMask {
+        self.level_mask
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level_mask.level()
+    }
+
+    /// Hash of the cell at the given level.
+    pub fn hash(&self, level: u8) -> CellHash {
+        let hash_index = self.level_mask.apply(level).hash_index();
+        if self.cell_type == CellType::PrunedBranch {
+            let this_index = self.level_mask.hash_index();
+            if hash_index != this_index {
+                let offset = 2 + hash_index * 32;
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(&self.data[offset..offset + 32]);
+                return hash;
+            }
+            return self.hashes[0];
+        }
+        self.hashes[hash_index]
+    }
+
+    /// Depth of the cell at the given level.
+    pub fn depth(&self, level: u8) -> u16 {
+        let hash_index = self.level_mask.apply(level).hash_index();
+        if self.cell_type == CellType::PrunedBranch {
+            let this_index = self.level_mask.hash_index();
+            if hash_index != this_index {
+                let offset = 2 + this_index * 32 + hash_index * 2;
+                return u16::from_be_bytes([self.data[offset], self.data[offset + 1]]);
+            }
+            return self.depths[0];
+        }
+        self.depths[hash_index]
+    }
+
+    /// Representation hash of the cell, the one used to identify contracts, messages and blocks.
+    pub fn repr_hash(&self) -> CellHash {
+        self.hash(MAX_LEVEL)
+    }
+
+    pub fn repr_depth(&self) -> u16 {
+        self.depth(MAX_LEVEL)
+    }
+
+    /// Start reading the cell from the beginning.
+    pub fn parser(self: &Arc<Self>) -> CellSlice {
+        CellSlice::new(self.clone())
+    }
+
+    pub fn to_arc(self) -> ArcCell {
+        Arc::new(self)
+    }
+}
+
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        self.repr_hash() == other.repr_hash()
+    }
+}
+
+impl Eq for Cell {}
+
+impl std::hash::Hash for Cell {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.repr_hash().hash(state);
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl fmt::Debug for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cell{{")?;
+        if self.is_exotic() {
+            write!(f, "type: {:?}, ", self.cell_type)?;
+        }
+        write!(
+            f,
+            "bits: {}, data: {}",
+            self.bit_len,
+            hex_string(&self.data)
+        )?;
+        if !self.references.is_empty() {
+            write!(f, ", refs: {:?}", self.references)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+pub(crate) fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,299 @@
+use super::{ArcCell, Cell};
+use crate::address::Address;
+use crate::error::CellError;
+use num_bigint::{BigInt, BigUint};
+use std::sync::Arc;
+
+/// Read cursor over the bits and references of a cell.
+#[derive(Debug, Clone)]
+pub struct CellSlice {
+    cell: ArcCell,
+    bit_pos: usize,
+    bit_end: usize,
+    ref_pos: usize,
+    ref_end: usize,
+}
+
+impl CellSlice {
+    pub fn new(cell: ArcCell) -> Self {
+        let bit_end = cell.bit_len();
+        let ref_end = cell.references().len();
+        Self {
+            cell,
+            bit_pos: 0,
+            bit_end,
+            ref_pos: 0,
+            ref_end,
+        }
+    }
+
+    pub fn cell(&self) -> &ArcCell {
+        &self.cell
+    }
+
+    pub fn remaining_bits(&self) -> usize {
+        self.bit_end - self.bit_pos
+    }
+
+    pub fn remaining_refs(&self) -> usize {
+        self.ref_end - self.ref_pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining_bits() == 0 && self.remaining_refs() == 0
+    }
+
+    fn bit_at(&self, pos: usize) -> bool {
+        self.cell.data()[pos / 8] & (1 << (7 - pos % 8)) != 0
+    }
+
+    pub fn preload_bit(&self) -> Result<bool, CellError> {
+        if self.remaining_bits() == 0 {
+            return Err(CellError::CellUnderflow);
+        }
+        Ok(self.bit_at(self.bit_pos))
+    }
+
+    pub fn load_bit(&mut self) -> Result<bool, CellError> {
+        let bit = self.preload_bit()?;
+        self.bit_pos += 1;
+        Ok(bit)
+    }
+
+    pub fn skip_bits(&mut self, bit_len: usize) -> Result<&mut Self, CellError> {
+        if bit_len > self.remaining_bits() {
+            return Err(CellError::CellUnderflow);
+        }
+        self.bit_pos += bit_len;
+        Ok(self)
+    }
+
+    /// Load `bit_len` bits, left aligned into bytes.
+    pub fn load_bits(&mut self, bit_len: usize) -> Result<Vec<u8>, CellError> {
+        if bit_len > self.remaining_bits() {
+            return Err(CellError::CellUnderflow);
+        }
+        let mut bytes = vec![0u8; bit_len.div_ceil(8)];
+        if self.bit_pos.is_multiple_of(8) {
+            let start = self.bit_pos / 8;
+            let len = bytes.len();
+            bytes.copy_from_slice(&self.cell.data()[start..start + len]);
+            if !bit_len.is_multiple_of(8) {
+                bytes[len - 1] &= 0xffu8 << (8 - bit_len % 8);
+            }
+        } else {
+            for i in 0..bit_len {
+                if self.bit_at(self.bit_pos + i) {
+                    bytes[i / 8] |= 1 << (7 - i % 8);
+                }
+            }
+        }
+        self.bit_pos += bit_len;
+        Ok(bytes)
+    }
+
+    pub fn load_bytes(&mut self, len: usize) -> Result<Vec<u8>, CellError> {
+        self.load_bits(len * 8)
+    }
+
+    pub fn load_hash(&mut self) -> Result<[u8; 32], CellError> {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&self.load_bytes(32)?);
+        Ok(hash)
+    }
+
+    pub fn preload_uint(&self, bit_len: usize) -> Result<u64, CellError> {
+        self.clone().load_uint(bit_len)
+    }
+
+    /// Load an unsigned integer of `bit_len` bits (up to 64).
+    pub fn load_uint(&mut self, bit_len: usize) -> Result<u64, CellError> {
+        if bit_len > 64 {
+            return Err(CellError::InvalidCell(format!(
+                "can not load {} bits into u64",
+                bit_len
+            )));
+        }
+        if bit_len > self.remaining_bits() {
+            return Err(CellError::CellUnderflow);
+        }
+        let mut value = 0u64;
+        for _ in 0..bit_len {
+            value = (value << 1) | self.load_bit()? as u64;
+        }
+        Ok(value)
+    }
+
+    /// Load a signed two's complement integer of `bit_len` bits (up to 64).
+    pub fn load_int(&mut self, bit_len: usize) -> Result<i64, CellError> {
+        if bit_len == 0 {
+            return Ok(0);
+        }
+        let value = self.load_uint(bit_len)?;
+        if bit_len == 64 {
+            return Ok(value as i64);
+        }
+        let sign_bit = 1u64 << (bit_len - 1);
+        if value & sign_bit != 0 {
+            Ok((value | !(sign_bit | (sign_bit - 1))) as i64)
+        } else {
+            Ok(value as i64)
+        }
+    }
+
+    pub fn load_u8(&mut self) -> Result<u8, CellError> {
+        Ok(self.load_uint(8)? as u8)
+    }
+
+    pub fn load_u16(&mut self) -> Result<u16, CellError> {
+        Ok(self.load_uint(16)? as u16)
+    }
+
+    pub fn load_u32(&mut self) -> Result<u32, CellError> {
+        Ok(self.load_uint(32)? as u32)
+    }
+
+    pub fn load_u64(&mut self) -> Result<u64, CellError> {
+        self.load_uint(64)
+    }
+
+    pub fn load_i8(&mut self) -> Result<i8, CellError> {
+        Ok(self.load_int(8)? as i8)
+    }
+
+    pub fn load_i32(&mut self) -> Result<i32, CellError> {
+        Ok(self.load_int(32)? as i32)
+    }
+
+    pub fn load_biguint(&mut self, bit_len: usize) -> Result<BigUint, CellError> {
+        let bytes = self.load_bits(bit_len)?;
+        let value = BigUint::from_bytes_be(&bytes);
+        let padding = bytes.len() * 8 - bit_len;
+        Ok(value >> padding)
+    }
+
+    pub fn load_bigint(&mut self, bit_len: usize) -> Result<BigInt, CellError> {
+        if bit_len == 0 {
+            return Ok(BigInt::from(0));
+        }
+        let value = BigInt::from(self.load_biguint(bit_len)?);
+        if value.bit(bit_len as u64 - 1) {
+            Ok(value - (BigInt::from(1) << bit_len))
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Load an amount of nanotons stored as `VarUInteger 16`.
+    pub fn load_coins(&mut self) -> Result<u128, CellError> {
+        let byte_len = self.load_uint(4)? as usize;
+        let bytes = self.load_bytes(byte_len)?;
+        Ok(bytes.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128))
+    }
+
+    /// Load `MsgAddress`, returning `None` for `addr_none`. External addresses are rejected.
+    pub fn load_optional_address(&mut self) -> Result<Option<Address>, CellError> {
+        match self.load_uint(2)? {
+            0b00 => Ok(None),
+            0b01 => Err(CellError::InvalidCell(
+                "external addresses are not supported".to_string(),
+            )),
+            0b10 => {
+                self.skip_anycast()?;
+                let workchain = self.load_i8()? as i32;
+                let hash_part = self.load_hash()?;
+                Ok(Some(Address::new(workchain, hash_part)))
+            }
+            _ => {
+                self.skip_anycast()?;
+                let address_len = self.load_uint(9)? as usize;
+                let workchain = self.load_i32()?;
+                if address_len != 256 {
+                    return Err(CellError::InvalidCell(format!(
+                        "unsupported address length {}",
+                        address_len
+                    )));
+                }
+                let hash_part = self.load_hash()?;
+                Ok(Some(Address::new(workchain, hash_part)))
+            }
+        }
+    }
+
+    /// Load `MsgAddressInt`.
+    pub fn load_address(&mut self) -> Result<Address, CellError> {
+        self.load_optional_address()?
+            .ok_or_else(|| CellError::InvalidCell("expected address, found addr_none".to_string()))
+    }
+
+    fn skip_anycast(&mut self) -> Result<(), CellError> {
+        if self.load_bit()? {
+            let depth = self.load_uint(5)? as usize;
+            self.skip_bits(depth)?;
+        }
+        Ok(())
+    }
+
+    pub fn load_reference(&mut self) -> Result<ArcCell, CellError> {
+        if self.remaining_refs() == 0 {
+            return Err(CellError::CellUnderflow);
+        }
+        let cell = self.cell.references()[self.ref_pos].clone();
+        self.ref_pos += 1;
+        Ok(cell)
+    }
+
+    /// Load `Maybe ^Cell`.
+    pub fn load_maybe_reference(&mut self) -> Result<Option<ArcCell>, CellError> {
+        if self.load_bit()? {
+            Ok(Some(self.load_reference()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Split off the next `bit_len` bits and `refs` references as a separate slice.
+    pub fn load_slice(&mut self, bit_len: usize, refs: usize) -> Result<CellSlice, CellError> {
+        if bit_len > self.remaining_bits() || refs > self.remaining_refs() {
+            return Err(CellError::CellUnderflow);
+        }
+        let slice = Self {
+            cell: self.cell.clone(),
+            bit_pos: self.bit_pos,
+            bit_end: self.bit_pos + bit_len,
+            ref_pos: self.ref_pos,
+            ref_end: self.ref_pos + refs,
+        };
+        self.bit_pos += bit_len;
+        self.ref_pos += refs;
+        Ok(slice)
+    }
+
+    /// Copy the remaining bits and references into a new cell.
+    pub fn to_cell(&self) -> Result<ArcCell, CellError> {
+        if self.bit_pos == 0
+            && self.ref_pos == 0
+            && self.bit_end == self.cell.bit_len()
+            && self.ref_end == self.cell.references().len()
+        {
+            return Ok(self.cell.clone());
+        }
+        let mut slice = self.clone();
+        let bit_len = slice.remaining_bits();
+        let data = slice.load_bits(bit_len)?;
+        let references = self.cell.references()[self.ref_pos..self.ref_end].to_vec();
+        Ok(Arc::new(Cell::new(data, bit_len, references, false)?))
+    }
+
+    pub fn ensure_empty(&self) -> Result<(), CellError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(CellError::InvalidCell(format!(
+                "{} bits and {} references left unread",
+                self.remaining_bits(),
+                self.remaining_refs()
+            )))
+        }
+    }
+}
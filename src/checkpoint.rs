@@ -0,0 +1,470 @@
+use crate::address::Address;
+use crate::error::CheckpointError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Position in the history of an account: the last transaction processed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TransactionCursor {
+    pub lt: u64,
+    pub hash: String,
+}
+
+/// Storage for the progress of long-running streams, so that they resume after a restart.
+///
+/// Block streams record the last masterchain seqno processed under a stream name, account
+/// streams the last transaction processed per account. Implement it to keep checkpoints
+/// next to the data an indexer writes, e.g. in the same database transaction.
+///
+/// The methods return `impl Future`, so the trait is not dyn compatible: take a store as a
+/// generic `S: CheckpointStore` rather than a `&dyn CheckpointStore`. To pick a store at
+/// runtime, wrap the candidates in an enum implementing the trait by delegation.
+pub trait CheckpointStore: Send + Sync {
+    /// Last masterchain seqno processed by the stream called `stream`.
+    fn masterchain_seqno(
+        &self,
+        stream: &str,
+    ) -> impl Future<Output = Result<Option<u32>, CheckpointError>> + Send;
+
+    fn set_masterchain_seqno(
+        &self,
+        stream: &str,
+        seqno: u32,
+    ) -> impl Future<Output = Result<(), CheckpointError>> + Send;
+
+    /// Last transaction of `address` processed.
+    fn account_cursor(
+        &self,
+        address: &Address,
+    ) -> impl Future<Output = Result<Option<TransactionCursor>, CheckpointError>> + Send;
+
+    fn set_account_cursor(
+        &self,
+        address: &Address,
+        cursor: &TransactionCursor,
+    ) -> impl Future<Output = Result<(), CheckpointError>> + Send;
+}
+
+/// Checkpoints of every stream, as kept by the in-memory and JSON file stores.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Checkpoints {
+    masterchain: BTreeMap<String, u32>,
+    /// Cursors by raw address.
+    accounts: BTreeMap<String, TransactionCursor>,
+}
+
+/// Store keeping checkpoints in memory only, e.g. for tests or short-lived processes.
+#[derive(Debug, Default)]
+pub struct MemoryCheckpointStore {
+    checkpoints: Mutex<Checkpoints>,
+}
+
+impl MemoryCheckpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CheckpointStore for MemoryCheckpointStore {
+    async fn masterchain_seqno(&self, stream: &str) -> Result<Option<u32>, CheckpointError> {
+        Ok(self
+            .checkpoints
+            .lock()
+            .unwrap()
+            .masterchain
+            .get(stream)
+            .copied())
+    }
+
+    async fn set_masterchain_seqno(&self, stream: &str, seqno: u32) -> Result<(), CheckpointError> {
+        self.checkpoints
+            .lock()
+            .unwrap()
+            .masterchain
+            .insert(stream.to_string(), seqno);
+        Ok(())
+    }
+
+    async fn account_cursor(
+        &self,
+        address: &Address,
+    ) -> Result<Option<TransactionCursor>, CheckpointError> {
+        let checkpoints = self.checkpoints.lock().unwrap();
+        Ok(checkpoints.accounts.get(&address.to_raw_string()).cloned())
+    }
+
+    async fn set_account_cursor(
+        &self,
+        address: &Address,
+        cursor: &TransactionCursor,
+    ) -> Result<(), CheckpointError> {
+        self.checkpoints
+            .lock()
+            .unwrap()
+            .accounts
+            .insert(address.to_raw_string(), cursor.clone());
+        Ok(())
+    }
+}
+
+/// Store keeping checkpoints in a JSON file.
+///
+/// The whole file is rewritten on every update through a temporary file and a rename, so
+/// it is never left half written. Suited to a moderate number of accounts.
+///
+/// Writes run on the blocking thread pool of the Tokio runtime, which the store must be
+/// used from. Updates hold a lock for the duration of the write, so that concurrent updates
+/// reach the file in the order they were applied. An update whose future is dropped may
+/// still be written.
+#[derive(Debug)]
+pub struct JsonFileCheckpointStore {
+    path: PathBuf,
+    checkpoints: Arc<Mutex<Checkpoints>>,
+}
+
+impl JsonFileCheckpointStore {
+    /// Open the store at `path`, starting empty when the file does not exist yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CheckpointError> {
+        let path = path.as_ref().to_path_buf();
+        let checkpoints = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|err| CheckpointError::Format(err.to_string()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Checkpoints::default(),
+            Err(err) => return Err(CheckpointError::Io(err.to_string())),
+        };
+        Ok(Self {
+            path,
+            checkpoints: Arc::new(Mutex::new(checkpoints)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Apply `change` and write the result, returning once the file is replaced.
+    async fn update(
+        &self,
+        change: impl FnOnce(&mut Checkpoints) + Send + 'static,
+    ) -> Result<(), CheckpointError> {
+        let path = self.path.clone();
+        let checkpoints = self.checkpoints.clone();
+        run_blocking(move || {
+            let mut checkpoints = checkpoints.lock().unwrap();
+            let mut updated = checkpoints.clone();
+            change(&mut updated);
+
+            let content = serde_json::to_vec_pretty(&updated)
+                .map_err(|err| CheckpointError::Format(err.to_string()))?;
+            let mut temporary = path.clone().into_os_string();
+            temporary.push(".tmp");
+            fs::write(&temporary, content)
+                .and_then(|_| fs::rename(&temporary, &path))
+                .map_err(|err| CheckpointError::Io(err.to_string()))?;
+
+            *checkpoints = updated;
+            Ok(())
+        })
+        .await
+    }
+}
+
+impl CheckpointStore for JsonFileCheckpointStore {
+    async fn masterchain_seqno(&self, stream: &str) -> Result<Option<u32>, CheckpointError> {
+        Ok(self
+            .checkpoints
+            .lock()
+            .unwrap()
+            .masterchain
+            .get(stream)
+            .copied())
+    }
+
+    async fn set_masterchain_seqno(&self, stream: &str, seqno: u32) -> Result<(), CheckpointError> {
+        let stream = stream.to_string();
+        self.update(move |checkpoints| {
+            checkpoints.masterchain.insert(stream, seqno);
+        })
+        .await
+    }
+
+    async fn account_cursor(
+        &self,
+        address: &Address,
+    ) -> Result<Option<TransactionCursor>, CheckpointError> {
+        let checkpoints = self.checkpoints.lock().unwrap();
+        Ok(checkpoints.accounts.get(&address.to_raw_string()).cloned())
+    }
+
+    async fn set_account_cursor(
+        &self,
+        address: &Address,
+        cursor: &TransactionCursor,
+    ) -> Result<(), CheckpointError> {
+        let address = address.to_raw_string();
+        let cursor = cursor.clone();
+        self.update(move |checkpoints| {
+            checkpoints.accounts.insert(address, cursor);
+        })
+        .await
+    }
+}
+
+/// Store keeping checkpoints in an SQLite database. Requires the `sqlite` feature.
+///
+/// Queries run on the blocking thread pool of the Tokio runtime, which the store must be
+/// used from.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteCheckpointStore {
+    connection: Arc<Mutex<rusqlite::Connection>>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteCheckpointStore {
+    /// Open or create the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CheckpointError> {
+        let connection = rusqlite::Connection::open(path).map_err(database_error)?;
+        Self::from_connection(connection)
+    }
+
+    /// Use an open connection, creating the checkpoint tables if needed.
+    pub fn from_connection(connection: rusqlite::Connection) -> Result<Self, CheckpointError> {
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS masterchain_checkpoints (
+                    stream TEXT PRIMARY KEY,
+                    seqno INTEGER NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS account_checkpoints (
+                    address TEXT PRIMARY KEY,
+                    lt INTEGER NOT NULL,
+                    hash TEXT NOT NULL
+                );",
+            )
+            .map_err(database_error)?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Run `query` on the connection without blocking the runtime.
+    async fn query<T: Send + 'static>(
+        &self,
+        query: impl FnOnce(&rusqlite::Connection) -> rusqlite::Result<T> + Send + 'static,
+    ) -> Result<T, CheckpointError> {
+        let connection = self.connection.clone();
+        run_blocking(move || query(&connection.lock().unwrap()).map_err(database_error)).await
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl CheckpointStore for SqliteCheckpointStore {
+    async fn masterchain_seqno(&self, stream: &str) -> Result<Option<u32>, CheckpointError> {
+        use rusqlite::OptionalExtension;
+
+        let stream = stream.to_string();
+        self.query(move |connection| {
+            connection
+                .query_row(
+                    "SELECT seqno FROM masterchain_checkpoints WHERE stream = ?1",
+                    [stream],
+                    |row| row.get(0),
+                )
+                .optional()
+        })
+        .await
+    }
+
+    async fn set_masterchain_seqno(&self, stream: &str, seqno: u32) -> Result<(), CheckpointError> {
+        let stream = stream.to_string();
+        self.query(move |connection| {
+            connection.execute(
+                "INSERT INTO masterchain_checkpoints (stream, seqno) VALUES (?1, ?2)
+                 ON CONFLICT (stream) DO UPDATE SET seqno = excluded.seqno",
+                rusqlite::params![stream, seqno],
+            )
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn account_cursor(
+        &self,
+        address: &Address,
+    ) -> Result<Option<TransactionCursor>, CheckpointError> {
+        use rusqlite::OptionalExtension;
+
+        let address = address.to_raw_string();
+        let row = self
+            .query(move |connection| {
+                connection
+                    .query_row(
+                        "SELECT lt, hash FROM account_checkpoints WHERE address = ?1",
+                        [address],
+                        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+                    )
+                    .optional()
+            })
+            .await?;
+        row.map(|(lt, hash)| {
+            let lt = u64::try_from(lt)
+                .map_err(|_| CheckpointError::Format(format!("negative lt {}", lt)))?;
+            Ok(TransactionCursor { lt, hash })
+        })
+        .transpose()
+    }
+
+    async fn set_account_cursor(
+        &self,
+        address: &Address,
+        cursor: &TransactionCursor,
+    ) -> Result<(), CheckpointError> {
+        let lt = i64::try_from(cursor.lt)
+            .map_err(|_| CheckpointError::Format(format!("lt {} is too large", cursor.lt)))?;
+        let address = address.to_raw_string();
+        let hash = cursor.hash.clone();
+        self.query(move |connection| {
+            connection.execute(
+                "INSERT INTO account_checkpoints (address, lt, hash) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (address) DO UPDATE SET lt = excluded.lt, hash = excluded.hash",
+                rusqlite::params![address, lt, hash],
+            )
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+/// Run the blocking I/O of a store on the blocking thread pool.
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, CheckpointError> + Send + 'static,
+) -> Result<T, CheckpointError> {
+    match tokio::task::spawn_blocking(task).await {
+        Ok(result) => result,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        Err(err) => Err(CheckpointError::Io(err.to_string())),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn database_error(err: rusqlite::Error) -> CheckpointError {
+    CheckpointError::Database(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address() -> Address {
+        Address::new(0, [0x5a; 32])
+    }
+
+    fn cursor() -> TransactionCursor {
+        TransactionCursor {
+            lt: 47_584_224_000_001,
+            hash: "8sOCHXuiGgEFJhDiHPvYUUCzMvUIlzmbfKUdZHtaQ9M=".to_string(),
+        }
+    }
+
+    fn temporary_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "toncenter-checkpoints-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    async fn check_store(store: &impl CheckpointStore) {
+        assert_eq!(store.masterchain_seqno("blocks").await.unwrap(), None);
+        assert_eq!(store.account_cursor(&address()).await.unwrap(), None);
+
+        store.set_masterchain_seqno("blocks", 41).await.unwrap();
+        store.set_masterchain_seqno("blocks", 42).await.unwrap();
+        store
+            .set_account_cursor(&address(), &cursor())
+            .await
+            .unwrap();
+
+        assert_eq!(store.masterchain_seqno("blocks").await.unwrap(), Some(42));
+        assert_eq!(store.masterchain_seqno("other").await.unwrap(), None);
+        assert_eq!(
+            store.account_cursor(&address()).await.unwrap(),
+            Some(cursor())
+        );
+        assert_eq!(
+            store
+                .account_cursor(&Address::new(-1, [0x5a; 32]))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn memory_store() {
+        check_store(&MemoryCheckpointStore::new()).await;
+    }
+
+    #[tokio::test]
+    async fn json_store_survives_reopening() {
+        let path = temporary_path("reopen");
+        check_store(&JsonFileCheckpointStore::open(&path).unwrap()).await;
+
+        let store = JsonFileCheckpointStore::open(&path).unwrap();
+        assert_eq!(store.masterchain_seqno("blocks").await.unwrap(), Some(42));
+        assert_eq!(
+            store.account_cursor(&address()).await.unwrap(),
+            Some(cursor())
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn json_store_writes_concurrent_updates() {
+        let path = temporary_path("concurrent");
+        let store = JsonFileCheckpointStore::open(&path).unwrap();
+        let streams: Vec<String> = (0..16).map(|i| format!("stream-{}", i)).collect();
+        futures::future::try_join_all(
+            streams
+                .iter()
+                .enumerate()
+                .map(|(seqno, stream)| store.set_masterchain_seqno(stream, seqno as u32)),
+        )
+        .await
+        .unwrap();
+
+        let reopened = JsonFileCheckpointStore::open(&path).unwrap();
+        for (seqno, stream) in streams.iter().enumerate() {
+            assert_eq!(
+                reopened.masterchain_seqno(stream).await.unwrap(),
+                Some(seqno as u32)
+            );
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn json_store_rejects_invalid_file() {
+        let path = temporary_path("invalid");
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            JsonFileCheckpointStore::open(&path),
+            Err(CheckpointError::Format(_))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn sqlite_store() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        check_store(&SqliteCheckpointStore::from_connection(connection).unwrap()).await;
+    }
+}
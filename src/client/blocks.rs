@@ -0,0 +1,759 @@
+use super::v2::{parse_lt, retry_rate_limited, ApiClientV2};
+use crate::address::Address;
+use crate::checkpoint::CheckpointStore;
+use crate::error::{ToncenterError, VerificationError};
+use crate::models::{BlocksShortTxId, RawTransaction, TonBlockIdExt};
+use crate::shard::ShardId;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Masterchain block together with the shard blocks it committed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasterchainBlock {
+    pub id: TonBlockIdExt,
+    /// Shard blocks created since the previous masterchain block, ordered by seqno so that
+    /// every block comes after its predecessors.
+    pub shard_blocks: Vec<TonBlockIdExt>,
+}
+
+impl ApiClientV2 {
+    /// Follow the masterchain, yielding every block from `start_seqno` on without gaps.
+    ///
+    /// The latest masterchain info is polled every `poll_interval` once the stream catches up.
+    /// Each block is expanded into the shard blocks it committed: `get_shards` only returns
+    /// the current shard tips, so their `prev_blocks` are walked back with `get_block_header`
+    /// until the tips of the previous masterchain block, across splits and merges. Rate
+    /// limited requests are retried with an increasing delay. The stream ends after yielding
+    /// an error; resume it from the seqno of the last block processed plus one.
+    ///
+    /// # Parameters
+    ///
+    /// * `start_seqno` - First masterchain block to yield (optional). The latest block when omitted.
+    /// * `poll_interval` - Delay between checks for new masterchain blocks.
+    pub fn block_stream(
+        &self,
+        start_seqno: Option<u32>,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<MasterchainBlock, ToncenterError>> + Send + '_ {
+        let follower = BlockFollower::new(self, start_seqno, poll_interval);
+        stream::try_unfold(follower, |mut follower| async move {
+            let block = follower.next().await?;
+            Ok(Some((block, follower)))
+        })
+    }
+
+    /// Follow the masterchain like [`Self::block_stream`], resuming from the seqno `store`
+    /// holds for `stream_id`.
+    ///
+    /// The stream starts after the stored seqno, or at the latest block when there is none.
+    /// A block is recorded as processed when the next one is requested, so after a crash
+    /// the block being handled is yielded again: delivery is at least once.
+    ///
+    /// # Parameters
+    ///
+    /// * `store` - Where the last processed seqno is read and written.
+    /// * `stream_id` - Name of the checkpoint, to run several independent consumers.
+    /// * `poll_interval` - Delay between checks for new masterchain blocks.
+    pub fn block_stream_with_checkpoints<'a, S: CheckpointStore>(
+        &'a self,
+        store: &'a S,
+        stream_id: &str,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<MasterchainBlock, ToncenterError>> + Send + 'a {
+        let state = CheckpointedFollower {
+            follower: None,
+            stream_id: stream_id.to_string(),
+            processed: None,
+        };
+        stream::try_unfold(state, move |mut state| async move {
+            if let Some(seqno) = state.processed.take() {
+                store.set_masterchain_seqno(&state.stream_id, seqno).await?;
+            }
+            let follower = match &mut state.follower {
+                Some(follower) => follower,
+                None => {
+                    let start_seqno = store
+                        .masterchain_seqno(&state.stream_id)
+                        .await?
+                        .map(|seqno| seqno + 1);
+                    state
+                        .follower
+                        .insert(BlockFollower::new(self, start_seqno, poll_interval))
+                }
+            };
+            let block = follower.next().await?;
+            state.processed = Some(block.id.seqno);
+            Ok(Some((block, state)))
+        })
+    }
+
+    /// Get every transaction of a block, following pages until `incomplete` is unset.
+    ///
+    /// Transactions are returned in the order liteservers list them: by account id, then by
+    /// logical time within each account. Pages are concatenated in that order and the
+    /// boundary transaction repeated by a page is dropped.
+    ///
+    /// # Parameters
+    ///
+    /// * `block` - Block to list transactions of.
+    pub async fn get_all_block_transactions(
+        &self,
+        block: &TonBlockIdExt,
+    ) -> Result<Vec<BlocksShortTxId>, ToncenterError> {
+        const PAGE_SIZE: u32 = 256;
+
+        let mut transactions: Vec<BlocksShortTxId> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut after: Option<(u64, String)> = None;
+        loop {
+            let page = retry_rate_limited(|| {
+                self.get_block_transactions(
+                    block.workchain,
+                    &block.shard,
+                    block.seqno,
+                    Some(&block.root_hash),
+                    Some(&block.file_hash),
+                    after.as_ref().map(|(lt, _)| *lt),
+                    after.as_ref().map(|(_, account)| account.as_str()),
+                    Some(PAGE_SIZE),
+                )
+            })
+            .await?;
+
+            let before = transactions.len();
+            for transaction in page.transactions {
+                if seen.insert(transaction.hash.clone()) {
+                    transactions.push(transaction);
+                }
+            }
+            match transactions.last() {
+                Some(last) if page.incomplete && transactions.len() > before => {
+                    after = Some((parse_lt(&last.lt)?, account_id(&last.account)));
+                }
+                _ => return Ok(transactions),
+            }
+        }
+    }
+
+    /// Get every transaction of a block with full data, fetching up to `concurrency`
+    /// transactions at once.
+    ///
+    /// The order is the one of [`Self::get_all_block_transactions`] whatever the concurrency.
+    ///
+    /// # Parameters
+    ///
+    /// * `block` - Block to list transactions of.
+    /// * `concurrency` - Maximum number of transactions fetched in parallel.
+    pub async fn get_all_block_raw_transactions(
+        &self,
+        block: &TonBlockIdExt,
+        concurrency: usize,
+    ) -> Result<Vec<RawTransaction>, ToncenterError> {
+        let short_ids = self.get_all_block_transactions(block).await?;
+        stream::iter(short_ids)
+            .map(|short_id| async move { self.get_raw_transaction(&short_id).await })
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await
+    }
+
+    async fn get_raw_transaction(
+        &self,
+        short_id: &BlocksShortTxId,
+    ) -> Result<RawTransaction, ToncenterError> {
+        let lt = parse_lt(&short_id.lt)?;
+        let transactions = retry_rate_limited(|| {
+            self.get_transactions(
+                &short_id.account,
+                Some(1),
+                Some(lt),
+                Some(&short_id.hash),
+                None,
+                None,
+            )
+        })
+        .await?;
+        transactions
+            .into_iter()
+            .find(|transaction| transaction.transaction_id.hash == short_id.hash)
+            .ok_or_else(|| ToncenterError::TransactionNotFound {
+                account: short_id.account.clone(),
+                hash: short_id.hash.clone(),
+            })
+    }
+}
+
+/// State of [`ApiClientV2::block_stream`].
+struct BlockFollower<'a> {
+    client: &'a ApiClientV2,
+    next_seqno: Option<u32>,
+    /// Latest masterchain seqno known to exist.
+    last_seqno: u32,
+    /// Shard tips of the masterchain block before `next_seqno`.
+    prev_tips: Option<Vec<TonBlockIdExt>>,
+    poll_interval: Duration,
+}
+
+impl<'a> BlockFollower<'a> {
+    fn new(client: &'a ApiClientV2, start_seqno: Option<u32>, poll_interval: Duration) -> Self {
+        Self {
+            client,
+            next_seqno: start_seqno,
+            last_seqno: 0,
+            prev_tips: None,
+            poll_interval,
+        }
+    }
+
+    async fn next(&mut self) -> Result<MasterchainBlock, ToncenterError> {
+        let seqno = match self.next_seqno {
+            Some(seqno) => seqno,
+            None => self.latest_seqno().await?,
+        };
+        while seqno > self.last_seqno {
+            if self.latest_seqno().await? < seqno {
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        }
+
+        let prev_tips = match self.prev_tips.take() {
+            Some(tips) => tips,
+            None => self.shard_tips(seqno.saturating_sub(1)).await?,
+        };
+        let masterchain = ShardId::root(-1).shard_string();
+        let id = retry_rate_limited(|| {
+            self.client
+                .lookup_block(-1, &masterchain, Some(seqno), None, None)
+        })
+        .await?;
+        let tips = self.shard_tips(seqno).await?;
+        let shard_blocks = self.new_shard_blocks(&tips, &prev_tips).await?;
+
+        self.prev_tips = Some(tips);
+        self.next_seqno = Some(seqno + 1);
+        Ok(MasterchainBlock { id, shard_blocks })
+    }
+
+    async fn latest_seqno(&mut self) -> Result<u32, ToncenterError> {
+        let info = retry_rate_limited(|| self.client.get_masterchain_info()).await?;
+        self.last_seqno = info.last.seqno;
+        Ok(self.last_seqno)
+    }
+
+    async fn shard_tips(&self, seqno: u32) -> Result<Vec<TonBlockIdExt>, ToncenterError> {
+        Ok(retry_rate_limited(|| self.client.get_shards(seqno))
+            .await?
+            .shards)
+    }
+
+    /// Blocks reachable from `tips` that are not already committed by `prev_tips`.
+    ///
+    /// Seqnos grow along every chain of shard blocks, including splits and merges, so a block
+    /// is old exactly when an overlapping previous tip has the same or a higher seqno.
+    async fn new_shard_blocks(
+        &self,
+        tips: &[TonBlockIdExt],
+        prev_tips: &[TonBlockIdExt],
+    ) -> Result<Vec<TonBlockIdExt>, ToncenterError> {
+        let prev_tips = prev_tips
+            .iter()
+            .map(|tip| Ok((shard_id(tip)?, tip.seqno)))
+            .collect::<Result<Vec<_>, ToncenterError>>()?;
+
+        let mut blocks = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = tips.to_vec();
+        while let Some(block) = pending.pop() {
+            let shard = shard_id(&block)?;
+            if prev_tips
+                .iter()
+                .any(|(tip, seqno)| tip.intersects(&shard) && *seqno >= block.seqno)
+            {
+                continue;
+            }
+            // Both halves of a split lead back to the same parent block
+            if !visited.insert((shard, block.seqno)) {
+                continue;
+            }
+
+            let header = retry_rate_limited(|| {
+                self.client.get_block_header(
+                    block.workchain,
+                    &block.shard,
+                    block.seqno,
+                    Some(&block.root_hash),
+                    Some(&block.file_hash),
+                )
+            })
+            .await?;
+            pending.extend(header.prev_blocks);
+            blocks.push(block);
+        }
+
+        blocks.sort_by_key(|block| (block.workchain, block.seqno));
+        Ok(blocks)
+    }
+}
+
+/// State of [`ApiClientV2::block_stream_with_checkpoints`].
+struct CheckpointedFollower<'a> {
+    /// Created once the start seqno is read from the store.
+    follower: Option<BlockFollower<'a>>,
+    stream_id: String,
+    /// Seqno of the block last yielded, stored when the next block is requested.
+    processed: Option<u32>,
+}
+
+fn shard_id(block: &TonBlockIdExt) -> Result<ShardId, ToncenterError> {
+    Ok(block.shard_id().map_err(VerificationError::from)?)
+}
+
+/// Account id as expected by `after_hash`: base64 of the account hash. Accounts are listed as
+/// raw addresses.
+fn account_id(account: &str) -> String {
+    match Address::from_raw_string(account) {
+        Ok(address) => STANDARD.encode(address.hash_part()),
+        Err(_) => account.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::MemoryCheckpointStore;
+    use crate::client::mock::{mock_client, Reply, Request};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    fn block() -> TonBlockIdExt {
+        TonBlockIdExt {
+            workchain: 0,
+            shard: "-9223372036854775808".to_string(),
+            seqno: 46_000_000,
+            root_hash: STANDARD.encode([1u8; 32]),
+            file_hash: STANDARD.encode([2u8; 32]),
+            extra: None,
+        }
+    }
+
+    /// Transactions of the block in liteserver order: by account, then by lt.
+    fn block_transactions() -> Vec<BlocksShortTxId> {
+        [
+            (1, 100),
+            (1, 101),
+            (2, 100),
+            (3, 102),
+            (3, 103),
+            (3, 104),
+            (4, 100),
+        ]
+        .into_iter()
+        .map(|(account, lt): (u8, u64)| BlocksShortTxId {
+            mode: 135,
+            account: Address::new(0, [account; 32]).to_raw_string(),
+            lt: lt.to_string(),
+            hash: STANDARD.encode([account, lt as u8]),
+        })
+        .collect()
+    }
+
+    /// Page of `getBlockTransactions` as liteservers return it: `size` transactions starting
+    /// at the `after` boundary, which is repeated.
+    fn page(
+        transactions: &[BlocksShortTxId],
+        params: &HashMap<String, String>,
+        size: usize,
+    ) -> Value {
+        let start = match (params.get("after_lt"), params.get("after_hash")) {
+            (Some(lt), Some(account)) => transactions
+                .iter()
+                .position(|tx| &tx.lt == lt && &account_id(&tx.account) == account)
+                .unwrap(),
+            _ => 0,
+        };
+        let end = transactions.len().min(start + size);
+        json!({
+            "@type": "blocks.transactions",
+            "id": block(),
+            "req_count": size,
+            "incomplete": end < transactions.len(),
+            "transactions": transactions[start..end],
+        })
+    }
+
+    fn raw_transaction(short_id: &BlocksShortTxId) -> Value {
+        json!({
+            "@type": "raw.transaction",
+            "address": { "@type": "accountAddress", "account_address": short_id.account },
+            "utime": 1_717_600_385,
+            "data": "",
+            "transaction_id": {
+                "@type": "internal.transactionId",
+                "lt": short_id.lt,
+                "hash": short_id.hash
+            },
+            "fee": "0",
+            "storage_fee": "0",
+            "other_fee": "0",
+            "in_msg": null,
+            "out_msgs": []
+        })
+    }
+
+    #[tokio::test]
+    async fn concatenates_pages_without_boundary_repeats() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let client = mock_client(Arc::new(move |request: &Request| {
+            assert_eq!(request.method, "getBlockTransactions");
+            counter.fetch_add(1, Ordering::SeqCst);
+            page(&block_transactions(), &request.params, 3).into()
+        }))
+        .await;
+
+        let transactions = client.get_all_block_transactions(&block()).await.unwrap();
+        assert_eq!(transactions, block_transactions());
+        // 3 new transactions on the first page, then 2 plus the repeated boundary
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn stops_paging_on_complete_page() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let client = mock_client(Arc::new(move |request: &Request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut page = page(&block_transactions(), &request.params, 3);
+            page["incomplete"] = json!(false);
+            page.into()
+        }))
+        .await;
+
+        let transactions = client.get_all_block_transactions(&block()).await.unwrap();
+        assert_eq!(transactions, block_transactions()[..3]);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn raw_transactions_keep_block_order() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let completed = Arc::new(Mutex::new(Vec::new()));
+        let (requests, completions) = (requested.clone(), completed.clone());
+        let client = mock_client(Arc::new(move |request: &Request| {
+            let params = &request.params;
+            match request.method.as_str() {
+                "getBlockTransactions" => page(&block_transactions(), params, 3).into(),
+                "getTransactions" => {
+                    let transactions = block_transactions();
+                    let short_id = transactions
+                        .iter()
+                        .find(|tx| Some(&tx.hash) == params.get("hash"))
+                        .unwrap();
+                    requests.lock().unwrap().push(short_id.hash.clone());
+                    // Answer later transactions first so that completion order differs
+                    let index = transactions.iter().position(|tx| tx == short_id).unwrap();
+                    let completions = completions.clone();
+                    let hash = short_id.hash.clone();
+                    Reply::from(json!([raw_transaction(short_id)]))
+                        .after(Duration::from_millis(20 * (7 - index as u64)))
+                        .on_sent(move || completions.lock().unwrap().push(hash))
+                }
+                other => panic!("unexpected method {}", other),
+            }
+        }))
+        .await;
+
+        let expected = block_transactions()
+            .iter()
+            .map(|tx| (tx.account.clone(), tx.hash.clone()))
+            .collect::<Vec<_>>();
+        for concurrency in [0, 1, 2, 4, 16] {
+            requested.lock().unwrap().clear();
+            completed.lock().unwrap().clear();
+            let transactions = client
+                .get_all_block_raw_transactions(&block(), concurrency)
+                .await
+                .unwrap();
+            let ids = transactions
+                .into_iter()
+                .map(|tx| (tx.address.account_address, tx.transaction_id.hash))
+                .collect::<Vec<_>>();
+            assert_eq!(ids, expected, "concurrency {}", concurrency);
+
+            // Sequential requests complete in order, concurrent ones out of order
+            let requested = requested.lock().unwrap().clone();
+            let completed = completed.lock().unwrap().clone();
+            assert_eq!(requested.len(), expected.len());
+            if concurrency > 1 {
+                assert_ne!(completed, requested, "concurrency {}", concurrency);
+            } else {
+                assert_eq!(completed, requested, "concurrency {}", concurrency);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_raw_transaction() {
+        let client = mock_client(Arc::new(|request: &Request| {
+            match request.method.as_str() {
+                "getBlockTransactions" => {
+                    page(&block_transactions()[..1], &request.params, 3).into()
+                }
+                _ => json!([]).into(),
+            }
+        }))
+        .await;
+
+        let short_id = &block_transactions()[0];
+        match client.get_all_block_raw_transactions(&block(), 4).await {
+            Err(ToncenterError::TransactionNotFound { account, hash }) => {
+                assert_eq!(account, short_id.account);
+                assert_eq!(hash, short_id.hash);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    fn block_id(shard: ShardId, seqno: u32) -> TonBlockIdExt {
+        let mut hash = [0u8; 32];
+        hash[..8].copy_from_slice(&shard.prefix().to_be_bytes());
+        hash[8..12].copy_from_slice(&seqno.to_be_bytes());
+        let root_hash = STANDARD.encode(hash);
+        hash[31] = 1;
+        TonBlockIdExt {
+            workchain: shard.workchain(),
+            shard: shard.shard_string(),
+            seqno,
+            root_hash,
+            file_hash: STANDARD.encode(hash),
+            extra: None,
+        }
+    }
+
+    fn masterchain_block(seqno: u32) -> TonBlockIdExt {
+        block_id(ShardId::root(-1), seqno)
+    }
+
+    /// Basechain history committed by masterchain blocks 99 to 104.
+    struct Chain {
+        /// Shard tips after each masterchain block.
+        tips: HashMap<u32, Vec<TonBlockIdExt>>,
+        /// Predecessors of each shard block, by shard and seqno.
+        prev_blocks: HashMap<(String, u32), Vec<TonBlockIdExt>>,
+    }
+
+    impl Chain {
+        fn new() -> Self {
+            let root = ShardId::root(0);
+            let (left, right) = root.children().unwrap();
+            let mut prev_blocks = HashMap::new();
+            let mut block = |shard: ShardId, seqno: u32, prev: &[&TonBlockIdExt]| {
+                let id = block_id(shard, seqno);
+                let prev = prev.iter().map(|&prev| prev.clone()).collect();
+                prev_blocks.insert((id.shard.clone(), seqno), prev);
+                id
+            };
+
+            let root_9 = block(root, 9, &[]);
+            let root_10 = block(root, 10, &[&root_9]);
+            // Several blocks between two masterchain blocks
+            let root_11 = block(root, 11, &[&root_10]);
+            let root_12 = block(root, 12, &[&root_11]);
+            let root_13 = block(root, 13, &[&root_12]);
+            // Split, the left half moving on twice
+            let left_14 = block(left, 14, &[&root_13]);
+            let right_14 = block(right, 14, &[&root_13]);
+            let left_15 = block(left, 15, &[&left_14]);
+            // Only the right half moves on
+            let right_15 = block(right, 15, &[&right_14]);
+            // Merge
+            let root_16 = block(root, 16, &[&left_15, &right_15]);
+
+            Self {
+                tips: HashMap::from([
+                    (99, vec![root_9]),
+                    (100, vec![root_10]),
+                    (101, vec![root_13]),
+                    (102, vec![left_15.clone(), right_14]),
+                    (103, vec![left_15, right_15]),
+                    (104, vec![root_16]),
+                ]),
+                prev_blocks,
+            }
+        }
+
+        fn header(&self, params: &HashMap<String, String>) -> Value {
+            let shard = ShardId::parse(0, &params["shard"]).unwrap();
+            let seqno = params["seqno"].parse().unwrap();
+            let id = block_id(shard, seqno);
+            assert_eq!(params["root_hash"], id.root_hash);
+            assert_eq!(params["file_hash"], id.file_hash);
+            let prev_blocks = &self.prev_blocks[&(id.shard.clone(), seqno)];
+            json!({
+                "@type": "blocks.header",
+                "id": id,
+                "global_id": -239,
+                "version": 0,
+                "flags": 1,
+                "after_merge": prev_blocks.len() == 2,
+                "after_split": prev_blocks.iter().any(|prev| prev.shard != params["shard"]),
+                "before_split": false,
+                "want_merge": false,
+                "want_split": false,
+                "validator_list_hash_short": 0,
+                "catchain_seqno": 0,
+                "min_ref_mc_seqno": 0,
+                "is_key_block": false,
+                "prev_key_block_seqno": 0,
+                "start_lt": "0",
+                "end_lt": "0",
+                "gen_utime": 1_717_600_385,
+                "prev_blocks": prev_blocks
+            })
+        }
+    }
+
+    /// Client over [`Chain`] whose latest masterchain block moves from 102 to 104, one
+    /// block per `getMasterchainInfo` call. Returns the `getBlockHeader` requests.
+    async fn chain_client() -> (ApiClientV2, Arc<Mutex<Vec<(String, u32)>>>) {
+        let chain = Chain::new();
+        let last = AtomicU32::new(102);
+        let headers = Arc::new(Mutex::new(Vec::new()));
+        let requested = headers.clone();
+        let client = mock_client(Arc::new(move |request: &Request| {
+            let params = &request.params;
+            match request.method.as_str() {
+                "getMasterchainInfo" => {
+                    let seqno = last.fetch_add(1, Ordering::SeqCst).min(104);
+                    json!({
+                        "@type": "blocks.masterchainInfo",
+                        "last": masterchain_block(seqno),
+                        "state_root_hash": STANDARD.encode([3u8; 32]),
+                        "init": masterchain_block(0),
+                        "@extra": "1717600394.5:0:0.1"
+                    })
+                }
+                "lookupBlock" => {
+                    assert_eq!(params["workchain"], "-1");
+                    json!(masterchain_block(params["seqno"].parse().unwrap()))
+                }
+                "shards" => {
+                    let seqno = params["seqno"].parse().unwrap();
+                    json!({ "@type": "blocks.shards", "shards": chain.tips[&seqno] })
+                }
+                "getBlockHeader" => {
+                    requested
+                        .lock()
+                        .unwrap()
+                        .push((params["shard"].clone(), params["seqno"].parse().unwrap()));
+                    chain.header(params)
+                }
+                other => panic!("unexpected method {}", other),
+            }
+            .into()
+        }))
+        .await;
+        (client, headers)
+    }
+
+    /// Shard and seqno of the shard blocks, checking they are ordered by seqno.
+    fn shard_blocks(block: &MasterchainBlock) -> Vec<(String, u32)> {
+        assert!(block
+            .shard_blocks
+            .windows(2)
+            .all(|pair| pair[0].seqno <= pair[1].seqno));
+        let mut blocks: Vec<_> = block
+            .shard_blocks
+            .iter()
+            .map(|block| (block.shard.clone(), block.seqno))
+            .collect();
+        blocks.sort();
+        blocks
+    }
+
+    #[tokio::test]
+    async fn block_stream_expands_shard_blocks() {
+        let (client, headers) = chain_client().await;
+        let root = ShardId::root(0);
+        let (left, right) = root.children().unwrap();
+        let id = |shard: ShardId, seqno: u32| (shard.shard_string(), seqno);
+
+        let blocks: Vec<MasterchainBlock> = client
+            .block_stream(Some(100), Duration::from_millis(10))
+            .take(5)
+            .try_collect()
+            .await
+            .unwrap();
+        let seqnos: Vec<u32> = blocks.iter().map(|block| block.id.seqno).collect();
+        assert_eq!(seqnos, [100, 101, 102, 103, 104]);
+        assert_eq!(blocks[0].id, masterchain_block(100));
+
+        let mut expected = vec![
+            vec![id(root, 10)],
+            vec![id(root, 11), id(root, 12), id(root, 13)],
+            vec![id(left, 14), id(left, 15), id(right, 14)],
+            vec![id(right, 15)],
+            vec![id(root, 16)],
+        ];
+        for expected in &mut expected {
+            expected.sort();
+        }
+        let actual: Vec<_> = blocks.iter().map(shard_blocks).collect();
+        assert_eq!(actual, expected);
+
+        // Headers are only fetched for new blocks, once each
+        let mut headers = headers.lock().unwrap().clone();
+        headers.sort();
+        let mut yielded: Vec<_> = expected.concat();
+        yielded.sort();
+        assert_eq!(headers, yielded);
+    }
+
+    #[tokio::test]
+    async fn block_stream_starts_at_latest_block() {
+        let (client, _) = chain_client().await;
+        let blocks: Vec<MasterchainBlock> = client
+            .block_stream(None, Duration::from_millis(10))
+            .take(3)
+            .try_collect()
+            .await
+            .unwrap();
+        let seqnos: Vec<u32> = blocks.iter().map(|block| block.id.seqno).collect();
+        assert_eq!(seqnos, [102, 103, 104]);
+        assert_eq!(blocks[1].shard_blocks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn block_stream_resumes_from_checkpoint() {
+        let (client, _) = chain_client().await;
+        let store = MemoryCheckpointStore::new();
+        store.set_masterchain_seqno("indexer", 100).await.unwrap();
+
+        let seqnos = |blocks: Vec<MasterchainBlock>| -> Vec<u32> {
+            blocks.iter().map(|block| block.id.seqno).collect()
+        };
+        let blocks = client
+            .block_stream_with_checkpoints(&store, "indexer", Duration::from_millis(10))
+            .take(2)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(seqnos(blocks), [101, 102]);
+        // The last block yielded is not recorded until the next one is requested
+        assert_eq!(store.masterchain_seqno("indexer").await.unwrap(), Some(101));
+
+        let blocks = client
+            .block_stream_with_checkpoints(&store, "indexer", Duration::from_millis(10))
+            .take(3)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(seqnos(blocks), [102, 103, 104]);
+        assert_eq!(store.masterchain_seqno("other").await.unwrap(), None);
+    }
+}
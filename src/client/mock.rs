@@ -0,0 +1,201 @@
+//! Local HTTP server answering toncenter requests, for tests of the client helpers.
+
+use super::{ApiClientV2, Network};
+use crate::models::SmcRunResult;
+use crate::tvm::StackEntry;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Request received by the mock server.
+pub(crate) struct Request {
+    /// API method, e.g. `getTransactions`.
+    pub method: String,
+    pub params: HashMap<String, String>,
+    /// JSON body of POST requests, `Null` for GET requests.
+    pub body: Value,
+}
+
+/// Answer of the mock server: a result or a toncenter error, sent after `delay`.
+pub(crate) struct Reply {
+    result: Result<Value, (u32, String)>,
+    delay: Duration,
+    sent: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl Reply {
+    /// Toncenter error with `code` and `message`.
+    pub fn error(code: u32, message: &str) -> Self {
+        Self {
+            result: Err((code, message.to_string())),
+            delay: Duration::ZERO,
+            sent: None,
+        }
+    }
+
+    /// Send the reply only after `delay`, without blocking the server.
+    pub fn after(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Call `sent` once the delay is over, right before the reply is written.
+    pub fn on_sent(mut self, sent: impl FnOnce() + Send + 'static) -> Self {
+        self.sent = Some(Box::new(sent));
+        self
+    }
+}
+
+impl From<Value> for Reply {
+    fn from(result: Value) -> Self {
+        Self {
+            result: Ok(result),
+            delay: Duration::ZERO,
+            sent: None,
+        }
+    }
+}
+
+pub(crate) type Handler = dyn Fn(&Request) -> Reply + Send + Sync;
+
+/// Serve toncenter responses from `handler`. Returns a client talking to the server.
+pub(crate) async fn mock_client(handler: Arc<Handler>) -> ApiClientV2 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                let header_end = loop {
+                    if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                };
+                let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+                let content_length = head
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .map(|(_, value)| value.trim().parse::<usize>().unwrap())
+                    .unwrap_or(0);
+                while request.len() < header_end + content_length {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+
+                let target = head.split_whitespace().nth(1).unwrap();
+                let url = url::Url::parse(&format!("http://localhost{}", target)).unwrap();
+                let request = Request {
+                    method: url.path().trim_start_matches('/').to_string(),
+                    params: url.query_pairs().into_owned().collect(),
+                    body: serde_json::from_slice(&request[header_end..]).unwrap_or(Value::Null),
+                };
+
+                let reply = handler(&request);
+                tokio::time::sleep(reply.delay).await;
+                if let Some(sent) = reply.sent {
+                    sent();
+                }
+                let body = match reply.result {
+                    Ok(result) => json!({ "ok": true, "result": result }),
+                    Err((code, error)) => json!({ "ok": false, "error": error, "code": code }),
+                }
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    ApiClientV2::new(Network::Custom(url), None)
+}
+
+/// `raw.transaction` of `account` at `lt` with the given inbound message, or none.
+pub(crate) fn raw_transaction(account: &str, lt: u64, in_msg: Value) -> Value {
+    json!({
+        "@type": "raw.transaction",
+        "address": { "@type": "accountAddress", "account_address": account },
+        "utime": 1_717_600_385,
+        "data": "",
+        "transaction_id": {
+            "@type": "internal.transactionId",
+            "lt": lt.to_string(),
+            "hash": transaction_hash(lt)
+        },
+        "fee": "0",
+        "storage_fee": "0",
+        "other_fee": "0",
+        "in_msg": in_msg,
+        "out_msgs": []
+    })
+}
+
+/// Made-up hash of the transaction at `lt`, as used by [`raw_transaction`].
+pub(crate) fn transaction_hash(lt: u64) -> String {
+    STANDARD.encode(lt.to_be_bytes().repeat(4))
+}
+
+/// Answer `getTransactions` from `history`, newest first, the way toncenter pages it:
+/// from the transaction given by `lt` and `hash` inclusive, at most `limit` of them,
+/// and only newer than `to_lt`.
+pub(crate) fn transactions_page(history: &[Value], params: &HashMap<String, String>) -> Value {
+    let lt_of = |transaction: &Value| -> u64 {
+        transaction["transaction_id"]["lt"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+    let start = match params.get("lt") {
+        Some(lt) => {
+            let lt = lt.parse().unwrap();
+            let start = history
+                .iter()
+                .position(|transaction| lt_of(transaction) == lt)
+                .unwrap();
+            assert_eq!(params["hash"], transaction_hash(lt));
+            start
+        }
+        None => 0,
+    };
+    let limit = params
+        .get("limit")
+        .map_or(10, |limit| limit.parse().unwrap());
+    let to_lt = params
+        .get("to_lt")
+        .map_or(0, |to_lt| to_lt.parse().unwrap());
+    Value::Array(
+        history[start..]
+            .iter()
+            .filter(|transaction| lt_of(transaction) > to_lt)
+            .take(limit)
+            .cloned()
+            .collect(),
+    )
+}
+
+/// `runGetMethod` result with exit code `0` and the given stack.
+pub(crate) fn get_method_result(stack: Vec<StackEntry>) -> Value {
+    serde_json::to_value(SmcRunResult {
+        gas_used: 1_000,
+        stack,
+        exit_code: 0,
+        extra: None,
+        raw_stack: Vec::new(),
+    })
+    .unwrap()
+}
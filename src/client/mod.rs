@@ -1,8 +1,12 @@
 pub(crate) mod base;
+pub(crate) mod blocks;
+#[cfg(test)]
+pub(crate) mod mock;
 pub(crate) mod v2;
 pub(crate) mod v3;
 
 pub use self::base::ApiKey;
 pub use self::base::Network;
+pub use self::blocks::MasterchainBlock;
 pub use self::v2::ApiClientV2;
 pub use self::v3::ApiClientV3;
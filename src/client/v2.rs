@@ -1,12 +1,24 @@
 use super::base::Network;
+use crate::address::Address;
+use crate::cell::{hex_string, ArcCell, Cell, CellHash};
+use crate::checkpoint::{CheckpointStore, TransactionCursor};
 use crate::client::base::{ApiKey, BaseApiClient};
-use crate::error::ToncenterError;
+use crate::config::{BlockchainConfig, ConfigParam};
+use crate::error::{InvalidInput, ProcessingError, ToncenterError, VerificationError};
+use crate::message::{ExternalMessage, StateInit};
 use crate::models::{
     BlocksHeader, BlocksMasterchainInfo, BlocksShardBlockProof, BlocksShards, BlocksTransactions,
     ConfigInfo, ConsensusBlock, DetectAddressResult, FullAccountState, JsonRpcRequest,
     MasterchainBlockSignatures, QueryFees, RawExtMessageInfo, RawFullAccountState, RawTransaction,
     SmcRunResult, Success, TokenData, TonBlockIdExt, WalletInformation,
 };
+use crate::proof::{verify_account_state, AccountProofSource, ShardAccount};
+use crate::shard::ShardId;
+use crate::tvm::{FromStack, StackEntry};
+use futures::stream::{self, Stream, TryStreamExt};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub struct ApiClientV2 {
     base_client: BaseApiClient,
@@ -236,6 +248,47 @@ impl ApiClientV2 {
             .await
     }
 
+    /// Get masterchain block signatures and verify them against the validator set.
+    ///
+    /// The response must be for `block`: a server returning valid signatures of another
+    /// block is rejected, and the signed root and file hashes are the ones of `block`.
+    /// The validator set (config param 34) is taken from the previous masterchain block.
+    /// Note that it is fetched from the same API, so this only proves that the block was
+    /// signed by the validators the API reports; pin a trusted key block for full trustlessness.
+    ///
+    /// # Parameters
+    ///
+    /// * `block` - Id of the masterchain block, e.g. from `getMasterchainInfo` or `lookupBlock`.
+    pub async fn get_masterchain_block_signatures_verified(
+        &self,
+        block: &TonBlockIdExt,
+    ) -> Result<MasterchainBlockSignatures, ToncenterError> {
+        if block.workchain != -1 {
+            return Err(VerificationError::Malformed(format!(
+                "block of workchain {} is not a masterchain block",
+                block.workchain
+            ))
+            .into());
+        }
+        let signatures = self.get_masterchain_block_signatures(block.seqno).await?;
+        let validator_set = match self
+            .get_config_param_decoded(34, Some(block.seqno.saturating_sub(1)))
+            .await?
+        {
+            ConfigParam::CurrentValidatorSet(validator_set) => validator_set,
+            other => {
+                return Err(VerificationError::Malformed(format!(
+                    "expected validator set, got {:?}",
+                    other
+                ))
+                .into())
+            }
+        };
+
+        signatures.verify(block, &validator_set)?;
+        Ok(signatures)
+    }
+
     /// Get shard block proof.
     ///
     /// # Parameters
@@ -268,6 +321,86 @@ impl ApiClientV2 {
             .await
     }
 
+    /// Get shard block proof and chain it to a trusted masterchain block.
+    ///
+    /// Returns the id of the proven shard block.
+    ///
+    /// # Parameters
+    ///
+    /// * `workchain` - Block workchain id.
+    /// * `shard` - Block shard id in signed decimal form.
+    /// * `seqno` - Block seqno.
+    /// * `trusted` - Masterchain block the caller already trusts, e.g. one with verified signatures.
+    pub async fn get_shard_block_proof_verified(
+        &self,
+        workchain: i32,
+        shard: &str,
+        seqno: u32,
+        trusted: &TonBlockIdExt,
+    ) -> Result<TonBlockIdExt, ToncenterError> {
+        let proof = self
+            .get_shard_block_proof(workchain, shard, seqno, Some(trusted.seqno))
+            .await?;
+        let proven = proof.verify(trusted)?;
+
+        let requested = ShardId::parse_signed(workchain, shard)?;
+        if proven.shard_id().map_err(VerificationError::from)? != requested || proven.seqno != seqno
+        {
+            return Err(VerificationError::Malformed(format!(
+                "proof is for block {} of shard {}:{}",
+                proven.seqno, proven.workchain, proven.shard
+            ))
+            .into());
+        }
+        Ok(proven)
+    }
+
+    /// Get the state of an account proven against a trusted masterchain block.
+    ///
+    /// The shard block holding the account is taken from `getShards` and chained to
+    /// `trusted` with [`Self::get_shard_block_proof_verified`]. The account proofs come from
+    /// `source`, since toncenter does not serve them. Returns `None` if the proof shows that
+    /// the account does not exist.
+    ///
+    /// # Parameters
+    ///
+    /// * `source` - Source of account state proofs, e.g. a liteserver connection.
+    /// * `address` - Account address.
+    /// * `trusted` - Masterchain block the caller already trusts, e.g. one with verified signatures.
+    pub async fn get_account_state_verified<S: AccountProofSource>(
+        &self,
+        source: &S,
+        address: &Address,
+        trusted: &TonBlockIdExt,
+    ) -> Result<Option<ShardAccount>, ToncenterError> {
+        let block = if address.workchain() == -1 {
+            trusted.clone()
+        } else {
+            let top = self
+                .get_shards(trusted.seqno)
+                .await?
+                .shards
+                .into_iter()
+                .find(|block| block.shard_id().is_ok_and(|shard| shard.contains(address)))
+                .ok_or_else(|| {
+                    VerificationError::Malformed(format!(
+                        "no shard of block {} holds {}",
+                        trusted.seqno, address
+                    ))
+                })?;
+            self.get_shard_block_proof_verified(top.workchain, &top.shard, top.seqno, trusted)
+                .await?
+        };
+
+        let proof = source.account_proof(&block, address).await?;
+        Ok(verify_account_state(
+            &block,
+            &proof.block_proof,
+            &proof.state_proof,
+            address,
+        )?)
+    }
+
     /// Get consensus block and its update timestamp.
     pub async fn get_consensus_block(&self) -> Result<ConsensusBlock, ToncenterError> {
         self.base_client
@@ -342,6 +475,7 @@ impl ApiClientV2 {
     /// * `after_lt` - Logical time of transaction after which to start (optional).
     /// * `after_hash` - Hash of transaction after which to start (optional).
     /// * `count` - Maximum number of transactions to return (optional, default is 40).
+    #[allow(clippy::too_many_arguments)]
     pub async fn get_block_transactions(
         &self,
         workchain: i32,
@@ -515,18 +649,71 @@ impl ApiClientV2 {
             .await
     }
 
+    /// Get config parameter by id and decode it.
+    ///
+    /// Parameters without a typed representation are returned as `ConfigParam::Other`.
+    ///
+    /// # Parameters
+    ///
+    /// * `config_id` - Configuration id.
+    /// * `seqno` - Masterchain seqno (optional). If not specified, latest blockchain state will be used.
+    pub async fn get_config_param_decoded(
+        &self,
+        config_id: u32,
+        seqno: Option<u32>,
+    ) -> Result<ConfigParam, ToncenterError> {
+        let config_info = self.get_config_param(config_id, seqno).await?;
+        let cell = config_info.config.to_cell()?;
+
+        Ok(ConfigParam::decode(config_id, &cell)?)
+    }
+
+    /// Get all config parameters.
+    ///
+    /// # Parameters
+    ///
+    /// * `seqno` - Masterchain seqno (optional). If not specified, latest blockchain state will be used.
+    pub async fn get_config_all(&self, seqno: Option<u32>) -> Result<ConfigInfo, ToncenterError> {
+        let mut params: Vec<(&str, String)> = vec![];
+
+        if let Some(seqno) = seqno {
+            params.push(("seqno", seqno.to_string()));
+        }
+
+        let params: Vec<(&str, &str)> = params.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+        self.base_client
+            .get(&self.base_url, "getConfigAll", &params)
+            .await
+    }
+
+    /// Get all config parameters and decode them.
+    ///
+    /// # Parameters
+    ///
+    /// * `seqno` - Masterchain seqno (optional). If not specified, latest blockchain state will be used.
+    pub async fn get_full_config(
+        &self,
+        seqno: Option<u32>,
+    ) -> Result<BlockchainConfig, ToncenterError> {
+        let config_info = self.get_config_all(seqno).await?;
+        let cell = config_info.config.to_cell()?;
+
+        Ok(BlockchainConfig::from_cell(&cell)?)
+    }
+
     /// Run get method on smart contract.
     ///
     /// # Parameters
     ///
     /// * `address` - Address of the smart contract.
     /// * `method` - Method name to run.
-    /// * `params` - Parameters for the method.
+    /// * `stack` - Input stack for the method.
     pub async fn run_get_method(
         &self,
         address: &str,
         method: &str,
-        stack: &[&str],
+        stack: &[StackEntry],
     ) -> Result<SmcRunResult, ToncenterError> {
         let request_body = serde_json::json!({
             "address": address,
@@ -539,6 +726,32 @@ impl ApiClientV2 {
             .await
     }
 
+    /// Run get method on smart contract and convert the resulting stack into `T`.
+    ///
+    /// A non-zero exit code is returned as `ToncenterError::GetMethodFailed`.
+    ///
+    /// # Parameters
+    ///
+    /// * `address` - Address of the smart contract.
+    /// * `method` - Method name to run.
+    /// * `stack` - Input stack for the method.
+    pub async fn run_get_method_typed<T: FromStack>(
+        &self,
+        address: &str,
+        method: &str,
+        stack: &[StackEntry],
+    ) -> Result<T, ToncenterError> {
+        let result = self.run_get_method(address, method, stack).await?;
+
+        if result.exit_code != 0 {
+            return Err(ToncenterError::GetMethodFailed {
+                exit_code: result.exit_code,
+            });
+        }
+
+        Ok(T::from_stack(&result.stack)?)
+    }
+
     /// Send serialized BOC file: fully packed and serialized external message to blockchain.
     ///
     /// # Parameters
@@ -571,6 +784,148 @@ impl ApiClientV2 {
             .await
     }
 
+    /// Send an external message and wait for the transaction that processes it.
+    ///
+    /// Transactions of the destination account are polled every `poll_interval` and matched by
+    /// the normalized message hash. Once the local clock passes `valid_until` the account is
+    /// checked one last time and `ToncenterError::MessageExpired` is returned.
+    ///
+    /// # Parameters
+    ///
+    /// * `message` - External message to send.
+    /// * `valid_until` - Unix time after which the destination rejects the message.
+    /// * `poll_interval` - Delay between checks of the destination transactions.
+    pub async fn send_and_wait(
+        &self,
+        message: &ExternalMessage,
+        valid_until: u32,
+        poll_interval: Duration,
+    ) -> Result<RawTransaction, ToncenterError> {
+        let hash = message.normalized_hash()?;
+        let address = message.destination;
+        let mut checked_lt = parse_lt(
+            &self
+                .get_address_information(&address.to_string())
+                .await?
+                .last_transaction_id
+                .lt,
+        )?;
+
+        self.send_boc(&message.to_boc_b64()?).await?;
+
+        loop {
+            let expired = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs())
+                > valid_until as u64;
+
+            let transactions = self.get_transactions_since(address, checked_lt).await?;
+            if let Some(newest) = transactions.first() {
+                checked_lt = parse_lt(&newest.transaction_id.lt)?;
+            }
+            if let Some(transaction) = transactions
+                .into_iter()
+                .find(|transaction| external_in_msg_hash(transaction) == Some(hash))
+            {
+                return Ok(transaction);
+            }
+
+            if expired {
+                return Err(ToncenterError::MessageExpired {
+                    hash: hex_string(&hash),
+                });
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Transactions of the account newer than `since_lt`, newest first.
+    async fn get_transactions_since(
+        &self,
+        address: Address,
+        since_lt: u64,
+    ) -> Result<Vec<RawTransaction>, ToncenterError> {
+        self.transaction_stream(address, Some(since_lt), 16)
+            .try_collect()
+            .await
+    }
+
+    /// Stream the transaction history of an account, newest first.
+    ///
+    /// Pages of `page_size` transactions are fetched on demand, continuing from the last
+    /// transaction of the previous page, until a transaction with `lt <= to_lt` or the first
+    /// transaction of the account is reached. When a liteserver lacks the requested history
+    /// the remaining pages are fetched with `archival=true`. Rate limited requests are retried
+    /// with an increasing delay. The stream ends after yielding an error.
+    ///
+    /// # Parameters
+    ///
+    /// * `address` - The target TON account.
+    /// * `to_lt` - Logical time to stop at, exclusive (optional). The whole history when omitted.
+    /// * `page_size` - Number of transactions per request.
+    pub fn transaction_stream<'a>(
+        &'a self,
+        address: Address,
+        to_lt: Option<u64>,
+        page_size: u32,
+    ) -> impl Stream<Item = Result<RawTransaction, ToncenterError>> + Send + 'a {
+        let pager = TransactionPager::new(self, &address, to_lt, page_size);
+        stream::try_unfold(pager, |mut pager| async move {
+            Ok(pager.next().await?.map(|transaction| (transaction, pager)))
+        })
+    }
+
+    /// Stream the transactions of an account processed since the cursor in `store`, newest first.
+    ///
+    /// Behaves like [`Self::transaction_stream`] with `to_lt` set to the stored cursor, or
+    /// over the whole history when `store` has none. Once the stream is exhausted the newest
+    /// transaction becomes the stored cursor, so the next stream only yields what came after.
+    /// A stream dropped or failed before its end leaves the cursor unchanged.
+    ///
+    /// # Parameters
+    ///
+    /// * `address` - The target TON account.
+    /// * `store` - Where the cursor of the account is read and written.
+    /// * `page_size` - Number of transactions per request.
+    pub fn transaction_stream_with_checkpoint<'a, S: CheckpointStore>(
+        &'a self,
+        address: Address,
+        store: &'a S,
+        page_size: u32,
+    ) -> impl Stream<Item = Result<RawTransaction, ToncenterError>> + Send + 'a {
+        let state: (Option<TransactionPager<'a>>, Option<TransactionCursor>) = (None, None);
+        stream::try_unfold(state, move |(pager, newest)| async move {
+            let mut pager = match pager {
+                Some(pager) => pager,
+                None => {
+                    let to_lt = store
+                        .account_cursor(&address)
+                        .await?
+                        .map(|cursor| cursor.lt);
+                    TransactionPager::new(self, &address, to_lt, page_size)
+                }
+            };
+            match pager.next().await? {
+                Some(transaction) => {
+                    let newest = match newest {
+                        Some(newest) => newest,
+                        None => TransactionCursor {
+                            lt: parse_lt(&transaction.transaction_id.lt)?,
+                            hash: transaction.transaction_id.hash.clone(),
+                        },
+                    };
+                    Ok(Some((transaction, (Some(pager), Some(newest)))))
+                }
+                None => {
+                    if let Some(newest) = newest {
+                        store.set_account_cursor(&address, &newest).await?;
+                    }
+                    Ok(None)
+                }
+            }
+        })
+    }
+
     /// This method takes address, body and init-params (if any), packs it to external message and sends to network.
     /// All params should be BOC-serialized.
     ///
@@ -653,6 +1008,54 @@ impl ApiClientV2 {
             .await
     }
 
+    /// Estimate fees of deploying a contract with an external message carrying `body`.
+    ///
+    /// Signature checks are skipped, so `body` may be signed with a dummy key. The API only
+    /// takes code and data: a `StateInit` with a split depth, tick-tock flags or libraries is
+    /// rejected, as neither the estimate nor the address would match the actual deploy.
+    ///
+    /// # Parameters
+    ///
+    /// * `state_init` - Initial state of the contract.
+    /// * `workchain` - Workchain the contract is deployed to.
+    /// * `body` - Optional body of the deploy message.
+    pub async fn estimate_deploy_fee(
+        &self,
+        state_init: &StateInit,
+        workchain: i32,
+        body: Option<&ArcCell>,
+    ) -> Result<QueryFees, ToncenterError> {
+        if state_init.split_depth.is_some()
+            || state_init.special.is_some()
+            || !state_init.libraries.is_empty()
+        {
+            return Err(ToncenterError::InvalidInput(InvalidInput::StateInit(
+                "fees can only be estimated for code and data".to_string(),
+            )));
+        }
+        let address = state_init.address(workchain)?.to_string();
+        let body = body.map(|body| body.to_boc_b64()).transpose()?;
+        let code = state_init
+            .code
+            .as_ref()
+            .map(|code| code.to_boc_b64())
+            .transpose()?;
+        let data = state_init
+            .data
+            .as_ref()
+            .map(|data| data.to_boc_b64())
+            .transpose()?;
+
+        self.estimate_fee(
+            &address,
+            body.as_deref(),
+            code.as_deref(),
+            data.as_deref(),
+            Some(true),
+        )
+        .await
+    }
+
     /// Generic JSON-RPC method to interact with Toncenter API.
     ///
     /// # Parameters
@@ -678,3 +1081,509 @@ impl ApiClientV2 {
             .await
     }
 }
+
+pub(crate) fn parse_lt(lt: &str) -> Result<u64, ToncenterError> {
+    lt.parse().map_err(|_| {
+        ToncenterError::ProcessingError(ProcessingError::Deserialization(serde::de::Error::custom(
+            format!("invalid logical time {}", lt),
+        )))
+    })
+}
+
+/// Normalized hash of the inbound external message of a transaction.
+fn external_in_msg_hash(transaction: &RawTransaction) -> Option<CellHash> {
+    let in_msg = transaction.in_msg.as_ref()?;
+    if in_msg
+        .source
+        .as_deref()
+        .is_some_and(|source| !source.is_empty())
+    {
+        return None;
+    }
+    let message = ExternalMessage {
+        destination: in_msg.destination.parse().ok()?,
+        state_init: None,
+        body: Cell::from_boc_b64(in_msg.msg_data.body.as_deref()?)
+            .ok()?
+            .to_arc(),
+    };
+    message.normalized_hash().ok()
+}
+
+/// State of [`ApiClientV2::transaction_stream`].
+struct TransactionPager<'a> {
+    client: &'a ApiClientV2,
+    address: String,
+    to_lt: u64,
+    page_size: u32,
+    /// Last transaction yielded, where the next page starts.
+    cursor: Option<(u64, String)>,
+    archival: bool,
+    buffer: VecDeque<RawTransaction>,
+    done: bool,
+}
+
+impl<'a> TransactionPager<'a> {
+    fn new(client: &'a ApiClientV2, address: &Address, to_lt: Option<u64>, page_size: u32) -> Self {
+        Self {
+            client,
+            address: address.to_string(),
+            to_lt: to_lt.unwrap_or(0),
+            page_size: page_size.max(1),
+            cursor: None,
+            archival: false,
+            buffer: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    async fn next(&mut self) -> Result<Option<RawTransaction>, ToncenterError> {
+        while self.buffer.is_empty() && !self.done {
+            self.fetch_page().await?;
+        }
+        Ok(self.buffer.pop_front())
+    }
+
+    async fn fetch_page(&mut self) -> Result<(), ToncenterError> {
+        let page = self.request_page().await?;
+        let full_page = page.len() == self.page_size as usize;
+
+        for transaction in page {
+            let lt = parse_lt(&transaction.transaction_id.lt)?;
+            if lt <= self.to_lt {
+                self.done = true;
+                break;
+            }
+            // Pages start with the cursor transaction itself
+            if self
+                .cursor
+                .as_ref()
+                .is_some_and(|(cursor_lt, _)| lt >= *cursor_lt)
+            {
+                continue;
+            }
+            self.cursor = Some((lt, transaction.transaction_id.hash.clone()));
+            self.buffer.push_back(transaction);
+        }
+
+        if !full_page || self.buffer.is_empty() {
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    async fn request_page(&mut self) -> Result<Vec<RawTransaction>, ToncenterError> {
+        loop {
+            let result = retry_rate_limited(|| {
+                self.client.get_transactions(
+                    &self.address,
+                    Some(self.page_size),
+                    self.cursor.as_ref().map(|(lt, _)| *lt),
+                    self.cursor.as_ref().map(|(_, hash)| hash.as_str()),
+                    (self.to_lt > 0).then_some(self.to_lt),
+                    self.archival.then_some(true),
+                )
+            })
+            .await;
+            match result {
+                Err(ToncenterError::HttpServerError { message, .. })
+                    if !self.archival && lacks_history(&message) =>
+                {
+                    self.archival = true;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Whether a liteserver failed because it does not store the requested blocks, e.g.
+/// `LITE_SERVER_UNKNOWN: cannot compute block with specified transaction: lt not in db`.
+fn lacks_history(message: &str) -> bool {
+    message.contains("not in db")
+}
+
+/// Run `request`, retrying it with an increasing delay while it is rate limited.
+pub(super) async fn retry_rate_limited<T, F, Fut>(mut request: F) -> Result<T, ToncenterError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ToncenterError>>,
+{
+    const RETRIES: u32 = 5;
+
+    let mut delay = Duration::from_secs(1);
+    for _ in 0..RETRIES {
+        match request().await {
+            Err(ToncenterError::RateLimitExceeded) => {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    request().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mock::{
+        mock_client, raw_transaction, transaction_hash, transactions_page, Reply, Request,
+    };
+    use crate::message::{comment_body, TickTock};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    const WALLET: &str = "EQCNyniXLqtKjisdPyp-Q8HFY-aW9lu5xa4IRWhM7hODhE64";
+
+    fn now() -> u32 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32
+    }
+
+    fn external(comment: &str) -> ExternalMessage {
+        ExternalMessage {
+            destination: WALLET.parse().unwrap(),
+            state_init: None,
+            body: comment_body(comment).unwrap(),
+        }
+    }
+
+    /// Transaction processing `message`, as toncenter reports it: without the state init.
+    fn processing(lt: u64, message: &ExternalMessage) -> Value {
+        raw_transaction(
+            WALLET,
+            lt,
+            json!({
+                "@type": "raw.message",
+                "source": "",
+                "destination": WALLET,
+                "value": "0",
+                "fwd_fee": "0",
+                "ihr_fee": "0",
+                "created_lt": "0",
+                "body_hash": "",
+                "msg_data": {
+                    "@type": "msg.dataRaw",
+                    "body": message.body.to_boc_b64().unwrap(),
+                    "init_state": ""
+                }
+            }),
+        )
+    }
+
+    /// Wallet whose history gains the transactions of `arrivals` one per poll, newest first.
+    async fn wallet_client(
+        mut arrivals: Vec<Value>,
+        sent: ExternalMessage,
+    ) -> (ApiClientV2, Arc<Mutex<Vec<String>>>) {
+        let history = Mutex::new(vec![raw_transaction(WALLET, 100, Value::Null)]);
+        let polls = Arc::new(Mutex::new(Vec::new()));
+        let to_lts = polls.clone();
+        arrivals.reverse();
+        let arrivals = Mutex::new(arrivals);
+        let client = mock_client(Arc::new(move |request: &Request| {
+            match request.method.as_str() {
+                "getAddressInformation" => json!({
+                    "@type": "raw.fullAccountState",
+                    "balance": "1000000000",
+                    "code": "",
+                    "data": "",
+                    "last_transaction_id": {
+                        "@type": "internal.transactionId",
+                        "lt": "100",
+                        "hash": transaction_hash(100)
+                    },
+                    "block_id": {
+                        "@type": "ton.blockIdExt",
+                        "workchain": -1,
+                        "shard": "-9223372036854775808",
+                        "seqno": 38743891,
+                        "root_hash": "oZHCqWIAGxfYUwTB0KvOkkQ8wDMO9Jp9TM6Ts0MCOhk=",
+                        "file_hash": "J/gzBy0DdOT2z9EQF6b2OV9c4kgNRg73azGYJYv1mg0="
+                    },
+                    "frozen_hash": "",
+                    "sync_utime": 1717600394,
+                    "@extra": "1717600394.5:0:0.1",
+                    "state": "active"
+                }),
+                "sendBoc" => {
+                    assert_eq!(request.body["boc"], sent.to_boc_b64().unwrap());
+                    json!({ "@type": "ok", "@extra": "1717600394.5:0:0.1" })
+                }
+                "getTransactions" => {
+                    to_lts.lock().unwrap().push(request.params["to_lt"].clone());
+                    let mut history = history.lock().unwrap();
+                    if let Some(arrival) = arrivals.lock().unwrap().pop() {
+                        history.insert(0, arrival);
+                    }
+                    transactions_page(&history, &request.params)
+                }
+                other => panic!("unexpected method {}", other),
+            }
+            .into()
+        }))
+        .await;
+        (client, polls)
+    }
+
+    #[tokio::test]
+    async fn send_and_wait_finds_message_on_later_poll() {
+        let mut message = external("pay");
+        message.state_init = Some(comment_body("init").unwrap());
+        let (client, polls) = wallet_client(
+            vec![
+                processing(200, &external("another")),
+                processing(300, &message),
+            ],
+            message.clone(),
+        )
+        .await;
+
+        let transaction = client
+            .send_and_wait(&message, now() + 600, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(transaction.transaction_id.lt, "300");
+        // Every poll only asks for transactions newer than the previous one
+        assert_eq!(*polls.lock().unwrap(), ["100", "200"]);
+    }
+
+    #[tokio::test]
+    async fn send_and_wait_expires() {
+        let message = external("pay");
+        let (client, polls) =
+            wallet_client(vec![processing(200, &external("another"))], message.clone()).await;
+
+        let result = client
+            .send_and_wait(&message, now() - 1, Duration::from_millis(10))
+            .await;
+        let ToncenterError::MessageExpired { hash } = result.unwrap_err() else {
+            panic!("expected an expired message");
+        };
+        assert_eq!(hash, hex_string(&message.normalized_hash().unwrap()));
+        // The account is still checked once after the deadline
+        assert_eq!(polls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn send_and_wait_checks_once_after_deadline() {
+        let message = external("pay");
+        let (client, _) = wallet_client(vec![processing(200, &message)], message.clone()).await;
+
+        let transaction = client
+            .send_and_wait(&message, now() - 1, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(transaction.transaction_id.lt, "200");
+    }
+
+    /// History of `WALLET` with transactions at 1000, 900, ..., 100, newest first.
+    fn wallet_history() -> Vec<Value> {
+        (1..=10)
+            .rev()
+            .map(|i| raw_transaction(WALLET, i * 100, Value::Null))
+            .collect()
+    }
+
+    async fn stream_lts(
+        client: &ApiClientV2,
+        to_lt: Option<u64>,
+        page_size: u32,
+    ) -> Result<Vec<u64>, ToncenterError> {
+        client
+            .transaction_stream(WALLET.parse().unwrap(), to_lt, page_size)
+            .map_ok(|transaction| parse_lt(&transaction.transaction_id.lt).unwrap())
+            .try_collect()
+            .await
+    }
+
+    /// Client over [`wallet_history`] recording the parameters of every `getTransactions` call.
+    async fn history_client(
+        reply: impl Fn(&HashMap<String, String>) -> Reply + Send + Sync + 'static,
+    ) -> (ApiClientV2, Arc<Mutex<Vec<HashMap<String, String>>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let client = mock_client(Arc::new(move |request: &Request| {
+            assert_eq!(request.method, "getTransactions");
+            recorded.lock().unwrap().push(request.params.clone());
+            reply(&request.params)
+        }))
+        .await;
+        (client, requests)
+    }
+
+    fn lts_requested(requests: &Mutex<Vec<HashMap<String, String>>>) -> Vec<Option<String>> {
+        let requests = requests.lock().unwrap();
+        requests
+            .iter()
+            .map(|params| params.get("lt").cloned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn transaction_stream_pages_through_history() {
+        let history = wallet_history();
+        let (client, requests) =
+            history_client(move |params| transactions_page(&history, params).into()).await;
+
+        let lts = stream_lts(&client, None, 4).await.unwrap();
+        assert_eq!(lts, [1000, 900, 800, 700, 600, 500, 400, 300, 200, 100]);
+        // Each page starts at the last transaction of the previous one, which is skipped
+        assert_eq!(
+            lts_requested(&requests),
+            [
+                None,
+                Some("700".into()),
+                Some("400".into()),
+                Some("100".into())
+            ]
+        );
+        assert!(requests
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|params| params["limit"] == "4" && !params.contains_key("to_lt")));
+    }
+
+    #[tokio::test]
+    async fn transaction_stream_stops_at_to_lt() {
+        let history = wallet_history();
+        let (client, requests) =
+            history_client(move |params| transactions_page(&history, params).into()).await;
+        assert_eq!(
+            stream_lts(&client, Some(450), 3).await.unwrap(),
+            [1000, 900, 800, 700, 600, 500]
+        );
+        assert!(requests
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|params| params["to_lt"] == "450"));
+
+        // Servers returning older transactions anyway are cut off at `to_lt`
+        let history = wallet_history();
+        let (client, requests) = history_client(move |params| {
+            let mut params = params.clone();
+            params.remove("to_lt");
+            transactions_page(&history, &params).into()
+        })
+        .await;
+        assert_eq!(
+            stream_lts(&client, Some(700), 4).await.unwrap(),
+            [1000, 900, 800]
+        );
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn transaction_stream_falls_back_to_archival() {
+        let history = wallet_history();
+        let (client, requests) = history_client(move |params| {
+            let archival = params
+                .get("archival")
+                .is_some_and(|archival| archival == "true");
+            let lt = params.get("lt").map_or(u64::MAX, |lt| lt.parse().unwrap());
+            if lt < 500 && !archival {
+                Reply::error(
+                    500,
+                    "LITE_SERVER_UNKNOWN: cannot compute block with specified transaction: \
+                     lt not in db",
+                )
+            } else {
+                transactions_page(&history, params).into()
+            }
+        })
+        .await;
+
+        let lts = stream_lts(&client, None, 4).await.unwrap();
+        assert_eq!(lts, [1000, 900, 800, 700, 600, 500, 400, 300, 200, 100]);
+        let requests = requests.lock().unwrap();
+        let archival: Vec<_> = requests
+            .iter()
+            .skip(1)
+            .map(|params| (params["lt"].as_str(), params.contains_key("archival")))
+            .collect();
+        // Once switched, the remaining pages stay on archival liteservers
+        assert_eq!(
+            archival,
+            [("700", false), ("400", false), ("400", true), ("100", true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction_stream_returns_other_server_errors() {
+        let (client, requests) =
+            history_client(|_| Reply::error(500, "LITE_SERVER_NETWORK: timeout")).await;
+
+        let result = stream_lts(&client, None, 4).await;
+        assert!(matches!(
+            result,
+            Err(ToncenterError::HttpServerError { code: 500, .. })
+        ));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert!(!requests.lock().unwrap()[0].contains_key("archival"));
+    }
+
+    #[tokio::test]
+    async fn estimate_deploy_fee_sends_code_and_data() {
+        let code = comment_body("code").unwrap();
+        let data = comment_body("data").unwrap();
+        let state_init = StateInit::new(code.clone(), data.clone());
+        let body = comment_body("deploy").unwrap();
+        let expected = json!({
+            "address": state_init.address(-1).unwrap().to_string(),
+            "body": body.to_boc_b64().unwrap(),
+            "init_code": code.to_boc_b64().unwrap(),
+            "init_data": data.to_boc_b64().unwrap(),
+            "ignore_chksig": true
+        });
+        let client = mock_client(Arc::new(move |request: &Request| {
+            assert_eq!(request.method, "estimateFee");
+            assert_eq!(request.body, expected);
+            let fees = json!({
+                "@type": "fees",
+                "in_fwd_fee": 1_000,
+                "storage_fee": 0,
+                "gas_fee": 2_000,
+                "fwd_fee": 0
+            });
+            json!({
+                "@type": "query.fees",
+                "source_fees": fees,
+                "destination_fees": [],
+                "@extra": "1717600394.5:0:0.1"
+            })
+            .into()
+        }))
+        .await;
+
+        let fees = client
+            .estimate_deploy_fee(&state_init, -1, Some(&body))
+            .await
+            .unwrap();
+        assert_eq!(fees.source_fees.gas_fee, 2_000);
+    }
+
+    #[tokio::test]
+    async fn estimate_deploy_fee_rejects_unsupported_fields() {
+        let client = mock_client(Arc::new(|_: &Request| panic!("nothing should be sent"))).await;
+        let state_init = StateInit::new(comment_body("code").unwrap(), Cell::empty().to_arc());
+        for state_init in [
+            state_init.clone().with_split_depth(4),
+            state_init.clone().with_special(TickTock {
+                tick: true,
+                tock: false,
+            }),
+            state_init.with_library(comment_body("library").unwrap(), true),
+        ] {
+            assert!(matches!(
+                client.estimate_deploy_fee(&state_init, 0, None).await,
+                Err(ToncenterError::InvalidInput(InvalidInput::StateInit(_)))
+            ));
+        }
+    }
+}
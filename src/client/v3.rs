@@ -1,13 +1,15 @@
 use super::base::Network;
-use crate::client::base::{ApiKey, BaseApiClient};
+use crate::client::base::ApiKey;
 
 pub struct ApiClientV3 {
+    /*
     base_client: BaseApiClient,
     base_url: String,
+    */
 }
 
 impl ApiClientV3 {
-    pub fn new(network: Network, api_key: Option<ApiKey>) -> Self {
+    pub fn new(_network: Network, _api_key: Option<ApiKey>) -> Self {
         /*
         let base_url = match network {
             Network::Mainnet => "https://toncenter.com/api/v3/",
@@ -0,0 +1,88 @@
+use std::fmt;
+use std::str::FromStr;
+
+const NANOTONS_PER_TON: u128 = 1_000_000_000;
+
+/// Amount of nanotons, serialized on-chain as `VarUInteger 16`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Coins(u128);
+
+impl Coins {
+    pub const ZERO: Coins = Coins(0);
+
+    pub fn from_nanotons(nanotons: u128) -> Self {
+        Self(nanotons)
+    }
+
+    pub fn from_ton(ton: u64) -> Self {
+        Self(ton as u128 * NANOTONS_PER_TON)
+    }
+
+    pub fn nanotons(&self) -> u128 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Coins) -> Option<Coins> {
+        self.0.checked_add(other.0).map(Coins)
+    }
+
+    pub fn checked_sub(self, other: Coins) -> Option<Coins> {
+        self.0.checked_sub(other.0).map(Coins)
+    }
+}
+
+impl From<u128> for Coins {
+    fn from(nanotons: u128) -> Self {
+        Self(nanotons)
+    }
+}
+
+impl From<u64> for Coins {
+    fn from(nanotons: u64) -> Self {
+        Self(nanotons as u128)
+    }
+}
+
+impl FromStr for Coins {
+    type Err = String;
+
+    /// Parse a decimal amount of TON, e.g. `"1.5"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+        if integer.is_empty() && fraction.is_empty() || fraction.len() > 9 {
+            return Err(format!("invalid TON amount: {}", s));
+        }
+        let parse = |digits: &str| -> Result<u128, String> {
+            if digits.is_empty() {
+                return Ok(0);
+            }
+            if !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(format!("invalid TON amount: {}", s));
+            }
+            digits
+                .parse::<u128>()
+                .map_err(|_| format!("invalid TON amount: {}", s))
+        };
+        let integer = parse(integer)?;
+        let fraction = parse(fraction)? * 10u128.pow(9 - fraction.len() as u32);
+        integer
+            .checked_mul(NANOTONS_PER_TON)
+            .and_then(|n| n.checked_add(fraction))
+            .map(Coins)
+            .ok_or_else(|| format!("TON amount is too large: {}", s))
+    }
+}
+
+impl fmt::Display for Coins {
+    /// Formats the amount in TON without trailing zeros, e.g. `1.5`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let integer = self.0 / NANOTONS_PER_TON;
+        let fraction = self.0 % NANOTONS_PER_TON;
+        if fraction == 0 {
+            write!(f, "{}", integer)
+        } else {
+            let fraction = format!("{:09}", fraction);
+            write!(f, "{}.{}", integer, fraction.trim_end_matches('0'))
+        }
+    }
+}
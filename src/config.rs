@@ -0,0 +1,748 @@
+use crate::address::Address;
+use crate::cell::{ArcCell, CellSlice};
+use crate::coins::Coins;
+use crate::error::CellError;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::collections::BTreeMap;
+
+const MASTERCHAIN: i32 = -1;
+
+/// Workchain description from config param 12.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkchainDescr {
+    pub enabled_since: u32,
+    pub actual_min_split: u8,
+    pub min_split: u8,
+    pub max_split: u8,
+    pub basic: bool,
+    pub active: bool,
+    pub accept_msgs: bool,
+    pub zerostate_root_hash: [u8; 32],
+    pub zerostate_file_hash: [u8; 32],
+    pub version: u32,
+    pub format: WorkchainFormat,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkchainFormat {
+    Basic {
+        vm_version: i32,
+        vm_mode: u64,
+    },
+    Extended {
+        min_addr_len: u16,
+        max_addr_len: u16,
+        addr_len_step: u16,
+        workchain_type_id: u32,
+    },
+}
+
+/// Validator election timings from config param 15, in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElectionTiming {
+    pub validators_elected_for: u32,
+    pub elections_start_before: u32,
+    pub elections_end_before: u32,
+    pub stake_held_for: u32,
+}
+
+/// Validator stake limits from config param 17.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StakeLimits {
+    pub min_stake: Coins,
+    pub max_stake: Coins,
+    pub min_total_stake: Coins,
+    /// Fixed point with 16 fractional bits.
+    pub max_stake_factor: u32,
+}
+
+/// Storage prices from config param 18, per second in 1/65536 nanoton units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoragePrices {
+    pub utime_since: u32,
+    pub bit_price_ps: u64,
+    pub cell_price_ps: u64,
+    pub mc_bit_price_ps: u64,
+    pub mc_cell_price_ps: u64,
+}
+
+/// Gas limits and prices from config params 20 (masterchain) and 21 (basechain).
+///
+/// `gas_price` is in 1/65536 nanoton units per gas unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasLimitsPrices {
+    pub flat_gas_limit: u64,
+    pub flat_gas_price: u64,
+    pub gas_price: u64,
+    pub gas_limit: u64,
+    pub special_gas_limit: u64,
+    pub gas_credit: u64,
+    pub block_gas_limit: u64,
+    pub freeze_due_limit: u64,
+    pub delete_due_limit: u64,
+}
+
+/// Message forwarding prices from config params 24 (masterchain) and 25 (basechain).
+///
+/// `bit_price` and `cell_price` are in 1/65536 nanoton units,
+/// `first_frac` and `next_frac` in 1/65536 fractions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MsgForwardPrices {
+    pub lump_price: u64,
+    pub bit_price: u64,
+    pub cell_price: u64,
+    pub ihr_price_factor: u32,
+    pub first_frac: u16,
+    pub next_frac: u16,
+}
+
+/// Validator set from config params 32 (previous), 34 (current) and 36 (next).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorSet {
+    pub utime_since: u32,
+    pub utime_until: u32,
+    pub total: u16,
+    pub main: u16,
+    pub total_weight: u64,
+    /// Validators ordered by their index in the set.
+    pub validators: Vec<ValidatorDescr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorDescr {
+    pub public_key: [u8; 32],
+    pub weight: u64,
+    pub adnl_addr: Option<[u8; 32]>,
+}
+
+/// A single decoded configuration parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigParam {
+    ConfigAddress(Address),
+    ElectorAddress(Address),
+    DnsRootAddress(Address),
+    Workchains(BTreeMap<i32, WorkchainDescr>),
+    ElectionTiming(ElectionTiming),
+    StakeLimits(StakeLimits),
+    StoragePrices(Vec<StoragePrices>),
+    MasterchainGasPrices(GasLimitsPrices),
+    GasPrices(GasLimitsPrices),
+    MasterchainMsgForwardPrices(MsgForwardPrices),
+    MsgForwardPrices(MsgForwardPrices),
+    PrevValidatorSet(ValidatorSet),
+    CurrentValidatorSet(ValidatorSet),
+    NextValidatorSet(ValidatorSet),
+    /// A parameter the crate does not decode.
+    Other {
+        id: u32,
+        cell: ArcCell,
+    },
+}
+
+impl ConfigParam {
+    /// Decode the value cell of config param `id`.
+    pub fn decode(id: u32, cell: &ArcCell) -> Result<Self, CellError> {
+        let mut slice = cell.parser();
+        Ok(match id {
+            0 => ConfigParam::ConfigAddress(load_masterchain_address(&mut slice)?),
+            1 => ConfigParam::ElectorAddress(load_masterchain_address(&mut slice)?),
+            4 => ConfigParam::DnsRootAddress(load_masterchain_address(&mut slice)?),
+            12 => ConfigParam::Workchains(load_workchains(&mut slice)?),
+            15 => ConfigParam::ElectionTiming(ElectionTiming::load(&mut slice)?),
+            17 => ConfigParam::StakeLimits(StakeLimits::load(&mut slice)?),
+            18 => ConfigParam::StoragePrices(load_storage_prices(&mut slice)?),
+            20 => ConfigParam::MasterchainGasPrices(GasLimitsPrices::load(&mut slice)?),
+            21 => ConfigParam::GasPrices(GasLimitsPrices::load(&mut slice)?),
+            24 => ConfigParam::MasterchainMsgForwardPrices(MsgForwardPrices::load(&mut slice)?),
+            25 => ConfigParam::MsgForwardPrices(MsgForwardPrices::load(&mut slice)?),
+            32 => ConfigParam::PrevValidatorSet(ValidatorSet::load(&mut slice)?),
+            34 => ConfigParam::CurrentValidatorSet(ValidatorSet::load(&mut slice)?),
+            36 => ConfigParam::NextValidatorSet(ValidatorSet::load(&mut slice)?),
+            id => ConfigParam::Other {
+                id,
+                cell: cell.clone(),
+            },
+        })
+    }
+}
+
+/// Decoded blockchain configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockchainConfig {
+    pub config_address: Address,
+    pub elector_address: Address,
+    pub workchains: BTreeMap<i32, WorkchainDescr>,
+    pub election_timing: ElectionTiming,
+    pub stake_limits: StakeLimits,
+    pub storage_prices: Vec<StoragePrices>,
+    pub masterchain_gas_prices: GasLimitsPrices,
+    pub gas_prices: GasLimitsPrices,
+    pub masterchain_msg_forward_prices: MsgForwardPrices,
+    pub msg_forward_prices: MsgForwardPrices,
+    pub prev_validator_set: Option<ValidatorSet>,
+    pub current_validator_set: ValidatorSet,
+    pub next_validator_set: Option<ValidatorSet>,
+    /// Raw value cells of every parameter, including the ones not decoded above.
+    pub params: BTreeMap<u32, ArcCell>,
+}
+
+impl BlockchainConfig {
+    /// Decode the configuration from the root of its `Hashmap 32 ^Cell` dictionary.
+    pub fn from_cell(root: &ArcCell) -> Result<Self, CellError> {
+        let params = root
+            .parser()
+            .load_hashmap(32)?
+            .into_iter()
+            .map(|(id, mut value)| Ok((dict_key(&id)?, value.load_reference()?)))
+            .collect::<Result<BTreeMap<u32, ArcCell>, CellError>>()?;
+
+        let param = |id: u32| -> Result<ConfigParam, CellError> {
+            let cell = params
+                .get(&id)
+                .ok_or_else(|| CellError::InvalidCell(format!("config param {} is missing", id)))?;
+            ConfigParam::decode(id, cell)
+        };
+        let optional_param = |id: u32| -> Result<Option<ConfigParam>, CellError> {
+            params
+                .get(&id)
+                .map(|cell| ConfigParam::decode(id, cell))
+                .transpose()
+        };
+
+        macro_rules! expect_param {
+            ($value:expr, $variant:ident) => {
+                match $value {
+                    ConfigParam::$variant(value) => Ok(value),
+                    _ => Err(CellError::InvalidCell(format!(
+                        "config param is not decoded as {}",
+                        stringify!($variant)
+                    ))),
+                }
+            };
+        }
+
+        Ok(Self {
+            config_address: expect_param!(param(0)?, ConfigAddress)?,
+            elector_address: expect_param!(param(1)?, ElectorAddress)?,
+            workchains: expect_param!(param(12)?, Workchains)?,
+            election_timing: expect_param!(param(15)?, ElectionTiming)?,
+            stake_limits: expect_param!(param(17)?, StakeLimits)?,
+            storage_prices: expect_param!(param(18)?, StoragePrices)?,
+            masterchain_gas_prices: expect_param!(param(20)?, MasterchainGasPrices)?,
+            gas_prices: expect_param!(param(21)?, GasPrices)?,
+            masterchain_msg_forward_prices: expect_param!(param(24)?, MasterchainMsgForwardPrices)?,
+            msg_forward_prices: expect_param!(param(25)?, MsgForwardPrices)?,
+            prev_validator_set: optional_param(32)?
+                .map(|value| expect_param!(value, PrevValidatorSet))
+                .transpose()?,
+            current_validator_set: expect_param!(param(34)?, CurrentValidatorSet)?,
+            next_validator_set: optional_param(36)?
+                .map(|value| expect_param!(value, NextValidatorSet))
+                .transpose()?,
+            params,
+        })
+    }
+
+    /// Gas prices for the given workchain.
+    pub fn gas_prices_for(&self, workchain: i32) -> &GasLimitsPrices {
+        if workchain == MASTERCHAIN {
+            &self.masterchain_gas_prices
+        } else {
+            &self.gas_prices
+        }
+    }
+
+    /// Message forwarding prices for the given workchain.
+    pub fn msg_forward_prices_for(&self, workchain: i32) -> &MsgForwardPrices {
+        if workchain == MASTERCHAIN {
+            &self.masterchain_msg_forward_prices
+        } else {
+            &self.msg_forward_prices
+        }
+    }
+}
+
+fn expect_tag(slice: &mut CellSlice, bits: usize, expected: &[u64]) -> Result<u64, CellError> {
+    let tag = slice.load_uint(bits)?;
+    if !expected.contains(&tag) {
+        return Err(CellError::InvalidCell(format!("unexpected tag {:#x}", tag)));
+    }
+    Ok(tag)
+}
+
+/// Key of a dictionary with 32-bit keys.
+fn dict_key(key: &BigUint) -> Result<u32, CellError> {
+    key.to_u32()
+        .ok_or_else(|| CellError::InvalidCell(format!("dictionary key {} exceeds 32 bits", key)))
+}
+
+fn load_masterchain_address(slice: &mut CellSlice) -> Result<Address, CellError> {
+    Ok(Address::new(MASTERCHAIN, slice.load_hash()?))
+}
+
+fn load_workchains(slice: &mut CellSlice) -> Result<BTreeMap<i32, WorkchainDescr>, CellError> {
+    slice
+        .load_dict(32)?
+        .into_iter()
+        .map(|(id, mut value)| {
+            // Workchain ids are signed, keys are their two's complement
+            Ok((dict_key(&id)? as i32, WorkchainDescr::load(&mut value)?))
+        })
+        .collect()
+}
+
+fn load_storage_prices(slice: &mut CellSlice) -> Result<Vec<StoragePrices>, CellError> {
+    slice
+        .load_hashmap(32)?
+        .into_values()
+        .map(|mut value| StoragePrices::load(&mut value))
+        .collect()
+}
+
+impl WorkchainDescr {
+    fn load(slice: &mut CellSlice) -> Result<Self, CellError> {
+        expect_tag(slice, 8, &[0xa6, 0xa7])?;
+        let enabled_since = slice.load_u32()?;
+        let actual_min_split = slice.load_u8()?;
+        let min_split = slice.load_u8()?;
+        let max_split = slice.load_u8()?;
+        let basic = slice.load_bit()?;
+        let active = slice.load_bit()?;
+        let accept_msgs = slice.load_bit()?;
+        slice.skip_bits(13)?;
+        let zerostate_root_hash = slice.load_hash()?;
+        let zerostate_file_hash = slice.load_hash()?;
+        let version = slice.load_u32()?;
+        let format = if basic {
+            expect_tag(slice, 4, &[0x1])?;
+            WorkchainFormat::Basic {
+                vm_version: slice.load_i32()?,
+                vm_mode: slice.load_u64()?,
+            }
+        } else {
+            expect_tag(slice, 4, &[0x0])?;
+            WorkchainFormat::Extended {
+                min_addr_len: slice.load_uint(12)? as u16,
+                max_addr_len: slice.load_uint(12)? as u16,
+                addr_len_step: slice.load_uint(12)? as u16,
+                workchain_type_id: slice.load_u32()?,
+            }
+        };
+
+        Ok(Self {
+            enabled_since,
+            actual_min_split,
+            min_split,
+            max_split,
+            basic,
+            active,
+            accept_msgs,
+            zerostate_root_hash,
+            zerostate_file_hash,
+            version,
+            format,
+        })
+    }
+}
+
+impl ElectionTiming {
+    fn load(slice: &mut CellSlice) -> Result<Self, CellError> {
+        Ok(Self {
+            validators_elected_for: slice.load_u32()?,
+            elections_start_before: slice.load_u32()?,
+            elections_end_before: slice.load_u32()?,
+            stake_held_for: slice.load_u32()?,
+        })
+    }
+}
+
+impl StakeLimits {
+    fn load(slice: &mut CellSlice) -> Result<Self, CellError> {
+        Ok(Self {
+            min_stake: Coins::from_nanotons(slice.load_coins()?),
+            max_stake: Coins::from_nanotons(slice.load_coins()?),
+            min_total_stake: Coins::from_nanotons(slice.load_coins()?),
+            max_stake_factor: slice.load_u32()?,
+        })
+    }
+}
+
+impl StoragePrices {
+    fn load(slice: &mut CellSlice) -> Result<Self, CellError> {
+        expect_tag(slice, 8, &[0xcc])?;
+        Ok(Self {
+            utime_since: slice.load_u32()?,
+            bit_price_ps: slice.load_u64()?,
+            cell_price_ps: slice.load_u64()?,
+            mc_bit_price_ps: slice.load_u64()?,
+            mc_cell_price_ps: slice.load_u64()?,
+        })
+    }
+}
+
+impl GasLimitsPrices {
+    fn load(slice: &mut CellSlice) -> Result<Self, CellError> {
+        match expect_tag(slice, 8, &[0xd1, 0xdd, 0xde])? {
+            0xd1 => {
+                let flat_gas_limit = slice.load_u64()?;
+                let flat_gas_price = slice.load_u64()?;
+                let prices = Self::load(slice)?;
+                Ok(Self {
+                    flat_gas_limit,
+                    flat_gas_price,
+                    ..prices
+                })
+            }
+            0xdd => {
+                let gas_price = slice.load_u64()?;
+                let gas_limit = slice.load_u64()?;
+                Ok(Self {
+                    flat_gas_limit: 0,
+                    flat_gas_price: 0,
+                    gas_price,
+                    gas_limit,
+                    special_gas_limit: gas_limit,
+                    gas_credit: slice.load_u64()?,
+                    block_gas_limit: slice.load_u64()?,
+                    freeze_due_limit: slice.load_u64()?,
+                    delete_due_limit: slice.load_u64()?,
+                })
+            }
+            _ => Ok(Self {
+                flat_gas_limit: 0,
+                flat_gas_price: 0,
+                gas_price: slice.load_u64()?,
+                gas_limit: slice.load_u64()?,
+                special_gas_limit: slice.load_u64()?,
+                gas_credit: slice.load_u64()?,
+                block_gas_limit: slice.load_u64()?,
+                freeze_due_limit: slice.load_u64()?,
+                delete_due_limit: slice.load_u64()?,
+            }),
+        }
+    }
+}
+
+impl MsgForwardPrices {
+    fn load(slice: &mut CellSlice) -> Result<Self, CellError> {
+        expect_tag(slice, 8, &[0xea])?;
+        Ok(Self {
+            lump_price: slice.load_u64()?,
+            bit_price: slice.load_u64()?,
+            cell_price: slice.load_u64()?,
+            ihr_price_factor: slice.load_u32()?,
+            first_frac: slice.load_u16()?,
+            next_frac: slice.load_u16()?,
+        })
+    }
+}
+
+impl ValidatorSet {
+    fn load(slice: &mut CellSlice) -> Result<Self, CellError> {
+        let tag = expect_tag(slice, 8, &[0x11, 0x12])?;
+        let utime_since = slice.load_u32()?;
+        let utime_until = slice.load_u32()?;
+        let total = slice.load_u16()?;
+        let main = slice.load_u16()?;
+
+        let (total_weight, list) = if tag == 0x11 {
+            (None, slice.load_hashmap(16)?)
+        } else {
+            (Some(slice.load_u64()?), slice.load_dict(16)?)
+        };
+        let validators = list
+            .into_values()
+            .map(|mut value| ValidatorDescr::load(&mut value))
+            .collect::<Result<Vec<_>, _>>()?;
+        let total_weight =
+            total_weight.unwrap_or_else(|| validators.iter().map(|v| v.weight).sum());
+
+        Ok(Self {
+            utime_since,
+            utime_until,
+            total,
+            main,
+            total_weight,
+            validators,
+        })
+    }
+}
+
+impl ValidatorDescr {
+    fn load(slice: &mut CellSlice) -> Result<Self, CellError> {
+        let tag = expect_tag(slice, 8, &[0x53, 0x73])?;
+        expect_tag(slice, 32, &[0x8e81278a])?;
+        let public_key = slice.load_hash()?;
+        let weight = slice.load_u64()?;
+        let adnl_addr = if tag == 0x73 {
+            Some(slice.load_hash()?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            public_key,
+            weight,
+            adnl_addr,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::cell::{build_hashmap, CellBuilder};
+    use std::sync::Arc;
+
+    /// Build a cell by running `store` on a fresh builder.
+    fn cell(store: impl FnOnce(&mut CellBuilder) -> Result<(), CellError>) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        store(&mut builder).unwrap();
+        Arc::new(builder.build().unwrap())
+    }
+
+    fn hashmap(entries: impl IntoIterator<Item = (u32, ArcCell)>) -> BTreeMap<BigUint, ArcCell> {
+        entries
+            .into_iter()
+            .map(|(key, value)| (BigUint::from(key), value))
+            .collect()
+    }
+
+    fn gas_prices(flat_gas_price: u64, gas_price: u64, special_gas_limit: u64) -> ArcCell {
+        cell(|b| {
+            b.store_u8(0xd1)?
+                .store_u64(100)?
+                .store_u64(flat_gas_price)?;
+            b.store_u8(0xde)?
+                .store_u64(gas_price)?
+                .store_u64(1_000_000)?
+                .store_u64(special_gas_limit)?
+                .store_u64(10_000)?
+                .store_u64(10_000_000)?
+                .store_u64(100_000_000)?
+                .store_u64(1_000_000_000)?;
+            Ok(())
+        })
+    }
+
+    fn forward_prices(lump_price: u64, bit_price: u64, cell_price: u64) -> ArcCell {
+        cell(|b| {
+            b.store_u8(0xea)?
+                .store_u64(lump_price)?
+                .store_u64(bit_price)?
+                .store_u64(cell_price)?
+                .store_u32(98_304)?
+                .store_uint(16, 21_845)?
+                .store_uint(16, 21_845)?;
+            Ok(())
+        })
+    }
+
+    /// Config dictionary encoded as `ConfigParams` on chain. Gas, forwarding and storage
+    /// prices are the mainnet ones, addresses, timings and validators are made up.
+    pub(crate) fn synthetic_config_root() -> ArcCell {
+        let basechain = cell(|b| {
+            b.store_u8(0xa6)?
+                .store_u32(1_573_821_854)?
+                .store_u8(0)?
+                .store_u8(0)?
+                .store_u8(8)?
+                .store_bit(true)?
+                .store_bit(true)?
+                .store_bit(true)?
+                .store_zeros(13)?
+                .store_bytes(&[0x11; 32])?
+                .store_bytes(&[0x22; 32])?
+                .store_u32(0)?
+                .store_uint(4, 1)?
+                .store_i32(-1)?
+                .store_u64(0)?;
+            Ok(())
+        });
+        let storage_prices = cell(|b| {
+            b.store_u8(0xcc)?
+                .store_u32(0)?
+                .store_u64(1)?
+                .store_u64(500)?
+                .store_u64(1_000)?
+                .store_u64(500_000)?;
+            Ok(())
+        });
+        let validator = |public_key: u8, weight: u64, adnl_addr: Option<u8>| {
+            cell(|b| {
+                b.store_u8(if adnl_addr.is_some() { 0x73 } else { 0x53 })?
+                    .store_u32(0x8e81278a)?
+                    .store_bytes(&[public_key; 32])?
+                    .store_u64(weight)?;
+                if let Some(adnl_addr) = adnl_addr {
+                    b.store_bytes(&[adnl_addr; 32])?;
+                }
+                Ok(())
+            })
+        };
+
+        let params = [
+            (0, cell(|b| b.store_bytes(&[0x55; 32]).map(drop))),
+            (1, cell(|b| b.store_bytes(&[0x33; 32]).map(drop))),
+            (
+                12,
+                cell(|b| b.store_dict(32, &hashmap([(0, basechain)])).map(drop)),
+            ),
+            (
+                15,
+                cell(|b| {
+                    b.store_u32(65_536)?
+                        .store_u32(32_768)?
+                        .store_u32(8_192)?
+                        .store_u32(32_768)?;
+                    Ok(())
+                }),
+            ),
+            (
+                17,
+                cell(|b| {
+                    b.store_coins(300_000_000_000_000)?
+                        .store_coins(10_000_000_000_000_000)?
+                        .store_coins(75_000_000_000_000_000)?
+                        .store_u32(196_608)?;
+                    Ok(())
+                }),
+            ),
+            (
+                18,
+                Arc::new(build_hashmap(32, &hashmap([(0, storage_prices)])).unwrap()),
+            ),
+            (20, gas_prices(1_000_000, 655_360_000, 70_000_000)),
+            (21, gas_prices(40_000, 26_214_400, 1_000_000)),
+            (24, forward_prices(10_000_000, 655_360_000, 65_536_000_000)),
+            (25, forward_prices(400_000, 26_214_400, 2_621_440_000)),
+            (
+                32,
+                cell(|b| {
+                    // validators#11 without the total weight
+                    b.store_u8(0x11)?
+                        .store_u32(1_699_934_464)?
+                        .store_u32(1_700_000_000)?
+                        .store_uint(16, 2)?
+                        .store_uint(16, 2)?
+                        .store_cell(
+                            &build_hashmap(
+                                16,
+                                &hashmap([
+                                    (0, validator(0x44, 5, None)),
+                                    (1, validator(0x45, 6, None)),
+                                ]),
+                            )?
+                            .to_arc(),
+                        )?;
+                    Ok(())
+                }),
+            ),
+            (
+                34,
+                cell(|b| {
+                    b.store_u8(0x12)?
+                        .store_u32(1_700_000_000)?
+                        .store_u32(1_700_065_536)?
+                        .store_uint(16, 1)?
+                        .store_uint(16, 1)?
+                        .store_u64(17)?
+                        .store_dict(16, &hashmap([(0, validator(0x33, 17, None))]))?;
+                    Ok(())
+                }),
+            ),
+            (
+                36,
+                cell(|b| {
+                    b.store_u8(0x12)?
+                        .store_u32(1_700_065_536)?
+                        .store_u32(1_700_131_072)?
+                        .store_uint(16, 1)?
+                        .store_uint(16, 1)?
+                        .store_u64(23)?
+                        .store_dict(16, &hashmap([(0, validator(0x66, 23, Some(0x77)))]))?;
+                    Ok(())
+                }),
+            ),
+        ];
+        let entries = params
+            .into_iter()
+            .map(|(id, value)| (id, cell(|b| b.store_reference(value).map(drop))));
+        Arc::new(build_hashmap(32, &hashmap(entries)).unwrap())
+    }
+
+    #[test]
+    fn decodes_config() {
+        let config = BlockchainConfig::from_cell(&synthetic_config_root()).unwrap();
+
+        assert_eq!(config.config_address, Address::new(-1, [0x55; 32]));
+        assert_eq!(config.elector_address, Address::new(-1, [0x33; 32]));
+        let basechain = &config.workchains[&0];
+        assert_eq!(basechain.max_split, 8);
+        assert!(basechain.basic && basechain.active && basechain.accept_msgs);
+        assert_eq!(
+            basechain.format,
+            WorkchainFormat::Basic {
+                vm_version: -1,
+                vm_mode: 0
+            }
+        );
+        assert_eq!(config.election_timing.validators_elected_for, 65_536);
+        assert_eq!(config.stake_limits.max_stake_factor, 196_608);
+        assert_eq!(config.storage_prices[0].cell_price_ps, 500);
+
+        let gas = config.gas_prices_for(0);
+        assert_eq!((gas.flat_gas_limit, gas.flat_gas_price), (100, 40_000));
+        assert_eq!(gas.gas_price, 26_214_400);
+        assert_eq!(config.gas_prices_for(-1).special_gas_limit, 70_000_000);
+        assert_eq!(config.msg_forward_prices_for(0).lump_price, 400_000);
+        assert_eq!(config.msg_forward_prices_for(-1).cell_price, 65_536_000_000);
+
+        let validators = &config.current_validator_set;
+        assert_eq!(validators.total_weight, 17);
+        assert_eq!(validators.validators[0].public_key, [0x33; 32]);
+        assert_eq!(validators.validators[0].adnl_addr, None);
+
+        let prev = config.prev_validator_set.as_ref().unwrap();
+        assert_eq!((prev.total, prev.main), (2, 2));
+        assert_eq!(prev.total_weight, 11);
+        assert_eq!(prev.validators[1].public_key, [0x45; 32]);
+        let next = config.next_validator_set.as_ref().unwrap();
+        assert_eq!(next.utime_since, 1_700_065_536);
+        assert_eq!(next.validators[0].weight, 23);
+        assert_eq!(next.validators[0].adnl_addr, Some([0x77; 32]));
+        assert_eq!(config.params.len(), 13);
+    }
+
+    #[test]
+    fn rejects_malformed_config() {
+        let root = synthetic_config_root();
+        let mut params: BTreeMap<BigUint, ArcCell> = root
+            .parser()
+            .load_hashmap(32)
+            .unwrap()
+            .into_iter()
+            .map(|(id, value)| (id, value.to_cell().unwrap()))
+            .collect();
+
+        // Wrong tag in the basechain gas prices
+        let bad_gas = cell(|b| b.store_u8(0xff)?.store_u64(0).map(drop));
+        let mut with_bad_gas = params.clone();
+        with_bad_gas.insert(
+            BigUint::from(21u32),
+            cell(|b| b.store_reference(bad_gas).map(drop)),
+        );
+        let root = Arc::new(build_hashmap(32, &with_bad_gas).unwrap());
+        assert!(BlockchainConfig::from_cell(&root).is_err());
+
+        params.remove(&BigUint::from(34u32));
+        let root = Arc::new(build_hashmap(32, &params).unwrap());
+        assert_eq!(
+            BlockchainConfig::from_cell(&root),
+            Err(CellError::InvalidCell(
+                "config param 34 is missing".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,510 @@
+use crate::address::Address;
+use crate::cell::{ArcCell, CellBuilder};
+use crate::client::ApiClientV2;
+use crate::config::ConfigParam;
+use crate::error::{CellError, InvalidInput, ToncenterError};
+use crate::tvm::StackEntry;
+use num_bigint::{BigInt, Sign};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+const SMC_ADDRESS_TAG: u64 = 0x9fd3;
+const NEXT_RESOLVER_TAG: u64 = 0xba93;
+const ADNL_ADDRESS_TAG: u64 = 0xad01;
+const STORAGE_ADDRESS_TAG: u64 = 0x7473;
+
+/// Longest domain in its internal representation that fits into a slice.
+const MAX_DOMAIN_BYTES: usize = 127;
+
+/// Category of a DNS record, identified on chain by the SHA-256 of its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DnsCategory {
+    /// Wallet of the domain owner, for sending TON to the domain.
+    Wallet,
+    /// TON Site, either an ADNL address or a TON Storage bag.
+    Site,
+    /// TON Storage bag.
+    Storage,
+    /// Contract resolving the subdomains.
+    NextResolver,
+    /// Any other category by its id.
+    Other([u8; 32]),
+}
+
+impl DnsCategory {
+    pub fn id(&self) -> [u8; 32] {
+        let name = match self {
+            DnsCategory::Wallet => "wallet",
+            DnsCategory::Site => "site",
+            DnsCategory::Storage => "storage",
+            DnsCategory::NextResolver => "dns_next_resolver",
+            DnsCategory::Other(id) => return *id,
+        };
+        Sha256::digest(name.as_bytes()).into()
+    }
+}
+
+/// Value of a DNS record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DnsRecord {
+    /// `dns_smc_address#9fd3 smc_addr:MsgAddressInt flags:(## 8) cap_list:flags . 0?SmcCapList`.
+    Wallet(Address),
+    /// `dns_adnl_address#ad01 adnl_addr:bits256 flags:(## 8) proto_list:flags . 0?ProtoList`.
+    Adnl([u8; 32]),
+    /// `dns_storage_address#7473 bag_id:bits256`.
+    Storage([u8; 32]),
+    /// `dns_next_resolver#ba93 resolver:MsgAddressInt`.
+    NextResolver(Address),
+    /// A record of another type, left undecoded.
+    Other(ArcCell),
+}
+
+impl DnsRecord {
+    /// Decode a record by its tag. Capability and protocol lists are skipped.
+    pub fn from_cell(cell: &ArcCell) -> Result<Self, CellError> {
+        let mut slice = cell.parser();
+        if slice.remaining_bits() < 16 {
+            return Ok(DnsRecord::Other(cell.clone()));
+        }
+        Ok(match slice.load_uint(16)? {
+            SMC_ADDRESS_TAG => DnsRecord::Wallet(slice.load_address()?),
+            ADNL_ADDRESS_TAG => DnsRecord::Adnl(slice.load_hash()?),
+            STORAGE_ADDRESS_TAG => DnsRecord::Storage(slice.load_hash()?),
+            NEXT_RESOLVER_TAG => DnsRecord::NextResolver(slice.load_address()?),
+            _ => DnsRecord::Other(cell.clone()),
+        })
+    }
+}
+
+/// Resolver of TON DNS names such as `alice.ton` or `alice.t.me`.
+///
+/// Resolution starts at the root DNS contract and follows `dns_next_resolver` records
+/// with `dnsresolve` get-method calls until a contract resolves the whole name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DnsResolver {
+    root: Address,
+}
+
+impl DnsResolver {
+    pub fn new(root: Address) -> Self {
+        Self { root }
+    }
+
+    /// Resolver starting at the root DNS contract from config param 4.
+    pub async fn from_config(client: &ApiClientV2) -> Result<Self, ToncenterError> {
+        match client.get_config_param_decoded(4, None).await? {
+            ConfigParam::DnsRootAddress(root) => Ok(Self::new(root)),
+            other => Err(CellError::InvalidCell(format!(
+                "expected DNS root address, got {:?}",
+                other
+            ))
+            .into()),
+        }
+    }
+
+    pub fn root(&self) -> &Address {
+        &self.root
+    }
+
+    /// Resolve the record of `category` for `domain`, `None` if the domain or the record
+    /// does not exist.
+    pub async fn resolve(
+        &self,
+        client: &ApiClientV2,
+        domain: &str,
+        category: DnsCategory,
+    ) -> Result<Option<DnsRecord>, ToncenterError> {
+        let category_id = StackEntry::Int(BigInt::from_bytes_be(Sign::Plus, &category.id()));
+        let mut resolver = self.root;
+        let mut name = encode_domain(domain)?;
+        loop {
+            let mut builder = CellBuilder::new();
+            builder.store_bytes(&name)?;
+            let stack = [
+                StackEntry::Slice(Arc::new(builder.build()?)),
+                category_id.clone(),
+            ];
+            let (resolved_bits, record): (u64, StackEntry) = client
+                .run_get_method_typed(&resolver.to_string(), "dnsresolve", &stack)
+                .await?;
+            let record = record.as_cell().map(DnsRecord::from_cell).transpose()?;
+
+            let name_bits = name.len() as u64 * 8;
+            if resolved_bits == 0 {
+                return Ok(None);
+            }
+            if resolved_bits % 8 != 0 || resolved_bits > name_bits {
+                return Err(CellError::InvalidCell(format!(
+                    "{} resolved {} bits of a {} bit name",
+                    resolver, resolved_bits, name_bits
+                ))
+                .into());
+            }
+            if resolved_bits == name_bits {
+                return Ok(record);
+            }
+
+            // The contract resolved a prefix and points to the resolver of the rest.
+            match record {
+                Some(DnsRecord::NextResolver(next)) => {
+                    resolver = next;
+                    name.drain(..(resolved_bits / 8) as usize);
+                }
+                None => return Ok(None),
+                Some(_) => {
+                    return Err(CellError::InvalidCell(format!(
+                        "{} returned a partial resolution without a next resolver",
+                        resolver
+                    ))
+                    .into())
+                }
+            }
+        }
+    }
+
+    /// Resolve the wallet address `domain` points to.
+    pub async fn resolve_wallet(
+        &self,
+        client: &ApiClientV2,
+        domain: &str,
+    ) -> Result<Option<Address>, ToncenterError> {
+        match self.resolve(client, domain, DnsCategory::Wallet).await? {
+            Some(DnsRecord::Wallet(address)) => Ok(Some(address)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Internal representation of a domain: labels in reverse order, each followed by a zero
+/// byte, with a leading zero byte addressing the root. `alice.ton` becomes `\0ton\0alice\0`.
+fn encode_domain(domain: &str) -> Result<Vec<u8>, ToncenterError> {
+    let invalid = || ToncenterError::InvalidInput(InvalidInput::Domain(domain.to_string()));
+    let domain = domain.trim_end_matches('.').to_lowercase();
+    if domain.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut name = vec![0];
+    for label in domain.split('.').rev() {
+        if label.is_empty() || label.bytes().any(|byte| byte <= b' ') {
+            return Err(invalid());
+        }
+        name.extend_from_slice(label.as_bytes());
+        name.push(0);
+    }
+    if name.len() > MAX_DOMAIN_BYTES {
+        return Err(invalid());
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::hex_string;
+    use crate::client::mock::{get_method_result, mock_client, Request};
+    use crate::error::ProcessingError;
+    use serde_json::Value;
+
+    const ROOT: &str = "Ef_lZ1T4NCb2mwkme9h2rJfESCE0W34ma9lWp7-_uY3zXDvq";
+    const TON_RESOLVER: &str = "EQC3dNlesgVD8YbAazcauIrXBPfiVhMMr5YYk2in0Mtsz0Bz";
+    const OWNER: &str = "EQAvDfWFG0oYX19jwNDNBBL1rKNT9XfaGP9HyTb5nb2Eml6y";
+
+    fn address(address: &str) -> Address {
+        address.parse().unwrap()
+    }
+
+    fn cell(build: impl FnOnce(&mut CellBuilder)) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        build(&mut builder);
+        Arc::new(builder.build().unwrap())
+    }
+
+    fn wallet_record(owner: &str) -> ArcCell {
+        cell(|b| {
+            b.store_uint(16, SMC_ADDRESS_TAG)
+                .unwrap()
+                .store_address(&address(owner))
+                .unwrap()
+                .store_u8(0)
+                .unwrap();
+        })
+    }
+
+    fn next_resolver_record(resolver: &str) -> ArcCell {
+        cell(|b| {
+            b.store_uint(16, NEXT_RESOLVER_TAG)
+                .unwrap()
+                .store_address(&address(resolver))
+                .unwrap();
+        })
+    }
+
+    fn dnsresolve_result(resolved_bytes: u64, record: Option<ArcCell>) -> Value {
+        get_method_result(vec![
+            StackEntry::Int(BigInt::from(resolved_bytes * 8)),
+            record.map_or(StackEntry::Null, StackEntry::Cell),
+        ])
+    }
+
+    /// Name and category a `dnsresolve` call was made with.
+    fn dnsresolve_args(request: &Request) -> (Vec<u8>, String) {
+        assert_eq!(request.method, "runGetMethod");
+        assert_eq!(request.body["method"], "dnsresolve");
+        let stack = &request.body["stack"];
+        let name = match StackEntry::from_json(&stack[0]).unwrap() {
+            StackEntry::Slice(name) => name.parser().load_bytes(name.bit_len() / 8).unwrap(),
+            other => panic!("name passed as {:?}", other),
+        };
+        let category = match StackEntry::from_json(&stack[1]).unwrap() {
+            StackEntry::Int(category) => format!("{:064x}", category),
+            other => panic!("category passed as {:?}", other),
+        };
+        (name, category)
+    }
+
+    fn is_invalid_cell(result: Result<Option<DnsRecord>, ToncenterError>) -> bool {
+        matches!(
+            result,
+            Err(ToncenterError::ProcessingError(ProcessingError::Cell(
+                CellError::InvalidCell(_)
+            )))
+        )
+    }
+
+    #[test]
+    fn category_ids() {
+        assert_eq!(
+            hex_string(&DnsCategory::Wallet.id()),
+            "e8d44050873dba865aa7c170ab4cce64d90839a34dcfd6cf71d14e0205443b1b"
+        );
+        assert_eq!(
+            hex_string(&DnsCategory::Site.id()),
+            "fbae041b02c41ed0fd8a4efb039bc780dd6af4a1f0c420f42561ae705dda43fe"
+        );
+        assert_eq!(
+            hex_string(&DnsCategory::Storage.id()),
+            "49a25f9feefaffecad0fcd30c50dc9331cff8b55ece53def6285c09e17e6f5d7"
+        );
+        assert_eq!(
+            hex_string(&DnsCategory::NextResolver.id()),
+            "19f02441ee588fdb26ee24b2568dd035c3c9206e11ab979be62e55558a1d17ff"
+        );
+        assert_eq!(DnsCategory::Other([7; 32]).id(), [7; 32]);
+    }
+
+    #[test]
+    fn encodes_domains() {
+        assert_eq!(encode_domain("alice.ton").unwrap(), b"\0ton\0alice\0");
+        assert_eq!(encode_domain("Alice.TON.").unwrap(), b"\0ton\0alice\0");
+        assert_eq!(
+            encode_domain("wallet.alice.t.me").unwrap(),
+            b"\0me\0t\0alice\0wallet\0"
+        );
+        assert_eq!(encode_domain("ton").unwrap(), b"\0ton\0");
+
+        for domain in [
+            "",
+            ".",
+            "alice..ton",
+            ".alice.ton",
+            "ali ce.ton",
+            "alice\0.ton",
+        ] {
+            assert!(
+                matches!(
+                    encode_domain(domain),
+                    Err(ToncenterError::InvalidInput(InvalidInput::Domain(_)))
+                ),
+                "{:?} accepted",
+                domain
+            );
+        }
+    }
+
+    #[test]
+    fn domain_length_limit() {
+        // A leading zero byte and one after each label
+        let longest = "a".repeat(MAX_DOMAIN_BYTES - 2);
+        assert_eq!(encode_domain(&longest).unwrap().len(), MAX_DOMAIN_BYTES);
+        assert!(encode_domain(&format!("{}a", longest)).is_err());
+
+        let labels = vec!["ab"; (MAX_DOMAIN_BYTES - 1) / 3].join(".");
+        assert_eq!(encode_domain(&labels).unwrap().len(), MAX_DOMAIN_BYTES);
+        assert!(encode_domain(&format!("a.{}", labels)).is_err());
+    }
+
+    #[test]
+    fn decodes_records() {
+        assert_eq!(
+            DnsRecord::from_cell(&wallet_record(OWNER)).unwrap(),
+            DnsRecord::Wallet(address(OWNER))
+        );
+        assert_eq!(
+            DnsRecord::from_cell(&next_resolver_record(TON_RESOLVER)).unwrap(),
+            DnsRecord::NextResolver(address(TON_RESOLVER))
+        );
+
+        let adnl = cell(|b| {
+            b.store_uint(16, ADNL_ADDRESS_TAG)
+                .unwrap()
+                .store_bytes(&[0xad; 32])
+                .unwrap()
+                .store_u8(0)
+                .unwrap();
+        });
+        assert_eq!(
+            DnsRecord::from_cell(&adnl).unwrap(),
+            DnsRecord::Adnl([0xad; 32])
+        );
+
+        let storage = cell(|b| {
+            b.store_uint(16, STORAGE_ADDRESS_TAG)
+                .unwrap()
+                .store_bytes(&[0x73; 32])
+                .unwrap();
+        });
+        assert_eq!(
+            DnsRecord::from_cell(&storage).unwrap(),
+            DnsRecord::Storage([0x73; 32])
+        );
+
+        let unknown = cell(|b| {
+            b.store_uint(16, 0x1234).unwrap().store_u32(5).unwrap();
+        });
+        assert_eq!(
+            DnsRecord::from_cell(&unknown).unwrap(),
+            DnsRecord::Other(unknown.clone())
+        );
+        let short = cell(|b| {
+            b.store_u8(0x9f).unwrap();
+        });
+        assert_eq!(
+            DnsRecord::from_cell(&short).unwrap(),
+            DnsRecord::Other(short.clone())
+        );
+
+        let truncated = cell(|b| {
+            b.store_uint(16, ADNL_ADDRESS_TAG)
+                .unwrap()
+                .store_u8(1)
+                .unwrap();
+        });
+        assert!(DnsRecord::from_cell(&truncated).is_err());
+    }
+
+    #[tokio::test]
+    async fn follows_next_resolver() {
+        let client = mock_client(Arc::new(|request: &Request| {
+            let (name, category) = dnsresolve_args(request);
+            assert_eq!(category, hex_string(&DnsCategory::Wallet.id()));
+            if request.body["address"] == address(ROOT).to_string() {
+                assert_eq!(name, b"\0ton\0alice\0");
+                dnsresolve_result(5, Some(next_resolver_record(TON_RESOLVER))).into()
+            } else {
+                assert_eq!(request.body["address"], address(TON_RESOLVER).to_string());
+                assert_eq!(name, b"alice\0");
+                dnsresolve_result(6, Some(wallet_record(OWNER))).into()
+            }
+        }))
+        .await;
+
+        let resolver = DnsResolver::new(address(ROOT));
+        assert_eq!(
+            resolver.resolve_wallet(&client, "alice.ton").await.unwrap(),
+            Some(address(OWNER))
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_record_resolved_by_root() {
+        let client = mock_client(Arc::new(|request: &Request| {
+            let (name, _) = dnsresolve_args(request);
+            assert_eq!(request.body["address"], address(ROOT).to_string());
+            dnsresolve_result(name.len() as u64, Some(next_resolver_record(TON_RESOLVER))).into()
+        }))
+        .await;
+
+        let resolver = DnsResolver::new(address(ROOT));
+        assert_eq!(
+            resolver
+                .resolve(&client, "ton", DnsCategory::NextResolver)
+                .await
+                .unwrap(),
+            Some(DnsRecord::NextResolver(address(TON_RESOLVER)))
+        );
+        // A record of another category is not a wallet
+        assert_eq!(resolver.resolve_wallet(&client, "ton").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn missing_domains_and_records() {
+        // Unknown to the root
+        let client = mock_client(Arc::new(|_: &Request| dnsresolve_result(0, None).into())).await;
+        let resolver = DnsResolver::new(address(ROOT));
+        assert_eq!(
+            resolver.resolve_wallet(&client, "alice.ton").await.unwrap(),
+            None
+        );
+
+        // Resolved, but without a record of the category
+        let client = mock_client(Arc::new(|request: &Request| {
+            let (name, _) = dnsresolve_args(request);
+            dnsresolve_result(name.len() as u64, None).into()
+        }))
+        .await;
+        assert_eq!(
+            resolver.resolve_wallet(&client, "alice.ton").await.unwrap(),
+            None
+        );
+
+        // A prefix resolved without a next resolver
+        let client = mock_client(Arc::new(|_: &Request| dnsresolve_result(5, None).into())).await;
+        assert_eq!(
+            resolver.resolve_wallet(&client, "alice.ton").await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_resolutions() {
+        let resolver = DnsResolver::new(address(ROOT));
+
+        // A prefix resolved to something other than a next resolver
+        let client = mock_client(Arc::new(|_: &Request| {
+            dnsresolve_result(5, Some(wallet_record(OWNER))).into()
+        }))
+        .await;
+        assert!(is_invalid_cell(
+            resolver
+                .resolve(&client, "alice.ton", DnsCategory::Wallet)
+                .await
+        ));
+
+        // More than the name
+        let client = mock_client(Arc::new(|_: &Request| {
+            dnsresolve_result(12, Some(wallet_record(OWNER))).into()
+        }))
+        .await;
+        assert!(is_invalid_cell(
+            resolver
+                .resolve(&client, "alice.ton", DnsCategory::Wallet)
+                .await
+        ));
+
+        // Not a whole number of bytes
+        let client = mock_client(Arc::new(|_: &Request| {
+            get_method_result(vec![
+                StackEntry::Int(BigInt::from(36)),
+                StackEntry::Cell(next_resolver_record(TON_RESOLVER)),
+            ])
+            .into()
+        }))
+        .await;
+        assert!(is_invalid_cell(
+            resolver
+                .resolve(&client, "alice.ton", DnsCategory::Wallet)
+                .await
+        ));
+    }
+}
@@ -12,18 +12,105 @@ pub enum ToncenterError {
     RateLimitExceeded,
     HttpClientError { code: u32, message: String },
     HttpServerError { code: u32, message: String },
+    GetMethodFailed { exit_code: i32 },
+    MessageExpired { hash: String },
+    TransactionNotFound { account: String, hash: String },
 }
 
 #[derive(Debug)]
 pub enum InvalidInput {
     HeaderValue(InvalidHeaderValue),
     UrlParse(UrlParseError),
+    Address(AddressError),
+    Wallet(WalletError),
+    Mnemonic(MnemonicError),
+    Shard(ShardError),
+    Domain(String),
+    StateInit(String),
 }
 
 #[derive(Debug)]
 pub enum ProcessingError {
     Network(ReqwestError),
     Deserialization(SerdeError),
+    Cell(CellError),
+    Stack(StackError),
+    Verification(VerificationError),
+    Checkpoint(CheckpointError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellError {
+    CellOverflow,
+    CellUnderflow,
+    InvalidCell(String),
+    BocDeserialization(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StackError {
+    MissingEntry {
+        index: usize,
+    },
+    UnexpectedType {
+        expected: &'static str,
+        found: String,
+    },
+    IntegerOverflow(String),
+    InvalidEntry(String),
+    Cell(CellError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerificationError {
+    Malformed(String),
+    HashMismatch(String),
+    InvalidSignature { node_id_short: String },
+    InsufficientWeight { signed: u64, total: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WalletError {
+    TooManyMessages {
+        max: usize,
+    },
+    InvalidWalletId(String),
+    InvalidSendMode(u8),
+    InvalidQueryId(u32),
+    InvalidTimeout(u32),
+    /// The signer's public key is not the one the wallet checks signatures with.
+    KeyMismatch,
+    Signer(String),
+    Cell(CellError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MnemonicError {
+    WordCount(usize),
+    UnknownWord(String),
+    InvalidMnemonic,
+    Random(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckpointError {
+    Io(String),
+    Format(String),
+    Database(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShardError {
+    ZeroPrefix,
+    InvalidFormat(String),
+    /// Sixteen decimal digits that could as well be the hex form of another shard.
+    Ambiguous(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddressError {
+    InvalidFormat(String),
+    InvalidChecksum,
 }
 
 impl fmt::Display for ToncenterError {
@@ -38,6 +125,15 @@ impl fmt::Display for ToncenterError {
             ToncenterError::HttpServerError { code, message } => {
                 write!(f, "Server error {}: {}", code, message)
             }
+            ToncenterError::GetMethodFailed { exit_code } => {
+                write!(f, "Get method failed with exit code {}", exit_code)
+            }
+            ToncenterError::MessageExpired { hash } => {
+                write!(f, "Message {} expired before being processed", hash)
+            }
+            ToncenterError::TransactionNotFound { account, hash } => {
+                write!(f, "Transaction {} of {} not found", hash, account)
+            }
         }
     }
 }
@@ -47,6 +143,12 @@ impl fmt::Display for InvalidInput {
         match self {
             InvalidInput::HeaderValue(err) => write!(f, "Invalid header value: {}", err),
             InvalidInput::UrlParse(err) => write!(f, "URL parse error: {}", err),
+            InvalidInput::Address(err) => write!(f, "Invalid address: {}", err),
+            InvalidInput::Wallet(err) => write!(f, "Invalid wallet request: {}", err),
+            InvalidInput::Mnemonic(err) => write!(f, "Invalid mnemonic: {}", err),
+            InvalidInput::Shard(err) => write!(f, "Invalid shard: {}", err),
+            InvalidInput::Domain(domain) => write!(f, "Invalid domain: {}", domain),
+            InvalidInput::StateInit(reason) => write!(f, "Invalid state init: {}", reason),
         }
     }
 }
@@ -56,12 +158,139 @@ impl fmt::Display for ProcessingError {
         match self {
             ProcessingError::Network(err) => write!(f, "Network error: {}", err),
             ProcessingError::Deserialization(err) => write!(f, "Deserialization error: {}", err),
+            ProcessingError::Cell(err) => write!(f, "Cell error: {}", err),
+            ProcessingError::Stack(err) => write!(f, "Stack error: {}", err),
+            ProcessingError::Verification(err) => write!(f, "Verification failed: {}", err),
+            ProcessingError::Checkpoint(err) => write!(f, "Checkpoint store error: {}", err),
+        }
+    }
+}
+
+impl fmt::Display for CellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellError::CellOverflow => write!(f, "Cell overflow"),
+            CellError::CellUnderflow => write!(f, "Cell underflow"),
+            CellError::InvalidCell(message) => write!(f, "Invalid cell: {}", message),
+            CellError::BocDeserialization(message) => {
+                write!(f, "BOC deserialization error: {}", message)
+            }
+        }
+    }
+}
+
+impl fmt::Display for StackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackError::MissingEntry { index } => write!(f, "Missing stack entry {}", index),
+            StackError::UnexpectedType { expected, found } => {
+                write!(f, "Expected {}, found {}", expected, found)
+            }
+            StackError::IntegerOverflow(value) => write!(f, "Integer overflow: {}", value),
+            StackError::InvalidEntry(message) => write!(f, "Invalid stack entry: {}", message),
+            StackError::Cell(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::Malformed(message) => write!(f, "Malformed data: {}", message),
+            VerificationError::HashMismatch(message) => write!(f, "Hash mismatch: {}", message),
+            VerificationError::InvalidSignature { node_id_short } => {
+                write!(f, "Invalid signature from validator {}", node_id_short)
+            }
+            VerificationError::InsufficientWeight { signed, total } => {
+                write!(f, "Only {} of {} validator weight signed", signed, total)
+            }
+        }
+    }
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletError::TooManyMessages { max } => {
+                write!(f, "Wallet can send at most {} messages at once", max)
+            }
+            WalletError::InvalidWalletId(reason) => write!(f, "Invalid wallet id: {}", reason),
+            WalletError::InvalidSendMode(mode) => {
+                write!(f, "Send mode {} is not allowed by the wallet", mode)
+            }
+            WalletError::InvalidQueryId(query_id) => write!(f, "Invalid query id {}", query_id),
+            WalletError::InvalidTimeout(timeout) => write!(f, "Invalid timeout {}", timeout),
+            WalletError::KeyMismatch => {
+                write!(f, "Signer public key does not match the wallet's key")
+            }
+            WalletError::Signer(err) => write!(f, "Signing failed: {}", err),
+            WalletError::Cell(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnemonicError::WordCount(count) => {
+                write!(f, "Mnemonic must have 24 words, got {}", count)
+            }
+            MnemonicError::UnknownWord(word) => write!(f, "Unknown mnemonic word: {}", word),
+            MnemonicError::InvalidMnemonic => write!(f, "Invalid mnemonic or password"),
+            MnemonicError::Random(err) => write!(f, "Random generation failed: {}", err),
+        }
+    }
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(err) => write!(f, "I/O error: {}", err),
+            CheckpointError::Format(err) => write!(f, "Invalid checkpoint data: {}", err),
+            CheckpointError::Database(err) => write!(f, "Database error: {}", err),
+        }
+    }
+}
+
+impl fmt::Display for ShardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShardError::ZeroPrefix => write!(f, "Shard prefix can not be zero"),
+            ShardError::InvalidFormat(shard) => write!(f, "Invalid shard id: {}", shard),
+            ShardError::Ambiguous(shard) => {
+                write!(f, "Shard id {} can be read as decimal or hex", shard)
+            }
+        }
+    }
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::InvalidFormat(address) => write!(f, "Invalid format: {}", address),
+            AddressError::InvalidChecksum => write!(f, "Invalid checksum"),
         }
     }
 }
 
 impl Error for ToncenterError {}
 
+impl Error for CellError {}
+
+impl Error for StackError {}
+
+impl Error for VerificationError {}
+
+impl Error for WalletError {}
+
+impl Error for MnemonicError {}
+
+impl Error for CheckpointError {}
+
+impl Error for ShardError {}
+
+impl Error for AddressError {}
+
 impl From<InvalidHeaderValue> for ToncenterError {
     fn from(err: InvalidHeaderValue) -> ToncenterError {
         ToncenterError::InvalidInput(InvalidInput::HeaderValue(err))
@@ -85,3 +314,75 @@ impl From<SerdeError> for ToncenterError {
         ToncenterError::ProcessingError(ProcessingError::Deserialization(err))
     }
 }
+
+impl From<CellError> for ToncenterError {
+    fn from(err: CellError) -> ToncenterError {
+        ToncenterError::ProcessingError(ProcessingError::Cell(err))
+    }
+}
+
+impl From<StackError> for ToncenterError {
+    fn from(err: StackError) -> ToncenterError {
+        ToncenterError::ProcessingError(ProcessingError::Stack(err))
+    }
+}
+
+impl From<VerificationError> for ToncenterError {
+    fn from(err: VerificationError) -> ToncenterError {
+        ToncenterError::ProcessingError(ProcessingError::Verification(err))
+    }
+}
+
+impl From<CellError> for VerificationError {
+    fn from(err: CellError) -> VerificationError {
+        VerificationError::Malformed(err.to_string())
+    }
+}
+
+impl From<WalletError> for ToncenterError {
+    fn from(err: WalletError) -> ToncenterError {
+        ToncenterError::InvalidInput(InvalidInput::Wallet(err))
+    }
+}
+
+impl From<MnemonicError> for ToncenterError {
+    fn from(err: MnemonicError) -> ToncenterError {
+        ToncenterError::InvalidInput(InvalidInput::Mnemonic(err))
+    }
+}
+
+impl From<CellError> for WalletError {
+    fn from(err: CellError) -> WalletError {
+        WalletError::Cell(err)
+    }
+}
+
+impl From<CellError> for StackError {
+    fn from(err: CellError) -> StackError {
+        StackError::Cell(err)
+    }
+}
+
+impl From<AddressError> for ToncenterError {
+    fn from(err: AddressError) -> ToncenterError {
+        ToncenterError::InvalidInput(InvalidInput::Address(err))
+    }
+}
+
+impl From<ShardError> for ToncenterError {
+    fn from(err: ShardError) -> ToncenterError {
+        ToncenterError::InvalidInput(InvalidInput::Shard(err))
+    }
+}
+
+impl From<ShardError> for VerificationError {
+    fn from(err: ShardError) -> VerificationError {
+        VerificationError::Malformed(err.to_string())
+    }
+}
+
+impl From<CheckpointError> for ToncenterError {
+    fn from(err: CheckpointError) -> ToncenterError {
+        ToncenterError::ProcessingError(ProcessingError::Checkpoint(err))
+    }
+}
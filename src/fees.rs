@@ -0,0 +1,312 @@
+use crate::cell::{ArcCell, CellHash};
+use crate::coins::Coins;
+use crate::config::{BlockchainConfig, GasLimitsPrices, MsgForwardPrices, StoragePrices};
+use std::collections::HashSet;
+
+const MASTERCHAIN: i32 = -1;
+
+/// Number of unique cells and data bits in a cell tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StorageStats {
+    pub cells: u64,
+    pub bits: u64,
+}
+
+impl StorageStats {
+    /// Count unique cells and bits of a tree. Cells shared by several parents are counted once.
+    pub fn of_cell(cell: &ArcCell) -> Self {
+        Self::of_cells(std::slice::from_ref(cell))
+    }
+
+    /// Count unique cells and bits of several trees at once.
+    pub fn of_cells(cells: &[ArcCell]) -> Self {
+        let mut stats = Self::default();
+        let mut visited = HashSet::new();
+        for cell in cells {
+            stats.visit(cell, &mut visited);
+        }
+        stats
+    }
+
+    /// Stats of a message as used for forwarding fees: the root cell itself is not counted.
+    pub fn of_message(message: &ArcCell) -> Self {
+        let mut stats = Self::default();
+        let mut visited = HashSet::new();
+        for reference in message.references() {
+            stats.visit(reference, &mut visited);
+        }
+        stats
+    }
+
+    fn visit(&mut self, cell: &ArcCell, visited: &mut HashSet<CellHash>) {
+        if !visited.insert(cell.repr_hash()) {
+            return;
+        }
+        self.cells += 1;
+        self.bits += cell.bit_len() as u64;
+        for reference in cell.references() {
+            self.visit(reference, visited);
+        }
+    }
+}
+
+/// Local fee calculator implementing the validator node's fee formulas.
+///
+/// All prices come from the blockchain config: gas from params 20/21, forwarding from 24/25
+/// and storage from 18. Results are rounded up the same way the node does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeCalculator {
+    gas_prices: GasLimitsPrices,
+    msg_forward_prices: MsgForwardPrices,
+    storage_prices: Vec<StoragePrices>,
+    is_masterchain: bool,
+}
+
+impl FeeCalculator {
+    pub fn new(
+        gas_prices: GasLimitsPrices,
+        msg_forward_prices: MsgForwardPrices,
+        storage_prices: Vec<StoragePrices>,
+        is_masterchain: bool,
+    ) -> Self {
+        let mut storage_prices = storage_prices;
+        storage_prices.sort_by_key(|prices| prices.utime_since);
+        Self {
+            gas_prices,
+            msg_forward_prices,
+            storage_prices,
+            is_masterchain,
+        }
+    }
+
+    /// Calculator for accounts of the given workchain.
+    pub fn from_config(config: &BlockchainConfig, workchain: i32) -> Self {
+        Self::new(
+            config.gas_prices_for(workchain).clone(),
+            config.msg_forward_prices_for(workchain).clone(),
+            config.storage_prices.clone(),
+            workchain == MASTERCHAIN,
+        )
+    }
+
+    /// Compute phase fee for the given amount of gas.
+    pub fn gas_fee(&self, gas_used: u64) -> Coins {
+        let prices = &self.gas_prices;
+        if gas_used <= prices.flat_gas_limit {
+            return Coins::from_nanotons(prices.flat_gas_price as u128);
+        }
+        let variable = (prices.gas_price as u128 * (gas_used - prices.flat_gas_limit) as u128)
+            .div_ceil(1 << 16);
+        Coins::from_nanotons(prices.flat_gas_price as u128 + variable)
+    }
+
+    /// Total forwarding fee for a message whose cells (excluding the root) have the given size.
+    pub fn forward_fee(&self, cells: u64, bits: u64) -> Coins {
+        let prices = &self.msg_forward_prices;
+        let variable = (prices.bit_price as u128 * bits as u128
+            + prices.cell_price as u128 * cells as u128)
+            .div_ceil(1 << 16);
+        Coins::from_nanotons(prices.lump_price as u128 + variable)
+    }
+
+    /// Total forwarding fee for a serialized message.
+    pub fn message_forward_fee(&self, message: &ArcCell) -> Coins {
+        let stats = StorageStats::of_message(message);
+        self.forward_fee(stats.cells, stats.bits)
+    }
+
+    /// Split a total forwarding fee into the part collected by the sender's action phase
+    /// and the remainder stored in the outbound message's `fwd_fee`.
+    pub fn split_forward_fee(&self, total: Coins) -> (Coins, Coins) {
+        let first = (total.nanotons() * self.msg_forward_prices.first_frac as u128) >> 16;
+        (
+            Coins::from_nanotons(first),
+            Coins::from_nanotons(total.nanotons() - first),
+        )
+    }
+
+    /// Instant hypercube routing fee for a message with the given total forwarding fee.
+    pub fn ihr_fee(&self, forward_fee: Coins) -> Coins {
+        Coins::from_nanotons(
+            (forward_fee.nanotons() * self.msg_forward_prices.ihr_price_factor as u128) >> 16,
+        )
+    }
+
+    /// Storage fee for an account of the given size between `last_paid` and `now` (unix time).
+    ///
+    /// Price changes in between are applied to their respective periods,
+    /// time before the first known price is free.
+    pub fn storage_fee(&self, stats: StorageStats, last_paid: u32, now: u32) -> Coins {
+        let prices = &self.storage_prices;
+        if now <= last_paid || last_paid == 0 || prices.is_empty() {
+            return Coins::ZERO;
+        }
+
+        let mut index = prices
+            .iter()
+            .rposition(|p| p.utime_since <= last_paid)
+            .unwrap_or(0);
+        let mut upto = last_paid.max(prices[0].utime_since);
+        let mut total: u128 = 0;
+
+        while index < prices.len() && upto < now {
+            let valid_until = match prices.get(index + 1) {
+                Some(next) => now.min(next.utime_since),
+                None => now,
+            };
+            if upto < valid_until {
+                let price = &prices[index];
+                let (bit_price, cell_price) = if self.is_masterchain {
+                    (price.mc_bit_price_ps, price.mc_cell_price_ps)
+                } else {
+                    (price.bit_price_ps, price.cell_price_ps)
+                };
+                let per_second = stats.bits as u128 * bit_price as u128
+                    + stats.cells as u128 * cell_price as u128;
+                total += per_second * (valid_until - upto) as u128;
+            }
+            upto = upto.max(valid_until);
+            index += 1;
+        }
+
+        Coins::from_nanotons(total.div_ceil(1 << 16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{Cell, CellBuilder};
+    use crate::config::tests::synthetic_config_root;
+
+    fn calculator(workchain: i32) -> FeeCalculator {
+        let config = BlockchainConfig::from_cell(&synthetic_config_root()).unwrap();
+        FeeCalculator::from_config(&config, workchain)
+    }
+
+    #[test]
+    fn forward_fee_of_plain_transfer() {
+        // Basechain transfers without a body: 0.0004 TON, of which the sender's action phase
+        // keeps 133331 and the outbound message carries a fwd_fee of 266669
+        let fees = calculator(0);
+        let message = Cell::empty().to_arc();
+        let total = fees.message_forward_fee(&message);
+        assert_eq!(total.nanotons(), 400_000);
+        let (action, remaining) = fees.split_forward_fee(total);
+        assert_eq!(action.nanotons(), 133_331);
+        assert_eq!(remaining.nanotons(), 266_669);
+        assert_eq!(fees.ihr_fee(total).nanotons(), 600_000);
+    }
+
+    #[test]
+    fn forward_fee_counts_referenced_cells_once() {
+        let mut leaf = CellBuilder::new();
+        leaf.store_u32(0).unwrap();
+        let leaf = leaf.build().unwrap().to_arc();
+        let mut body = CellBuilder::new();
+        body.store_u64(0)
+            .unwrap()
+            .store_reference(leaf.clone())
+            .unwrap()
+            .store_reference(leaf)
+            .unwrap();
+        let mut message = CellBuilder::new();
+        message
+            .store_u8(0)
+            .unwrap()
+            .store_reference(body.build().unwrap().to_arc())
+            .unwrap();
+        let message = message.build().unwrap().to_arc();
+
+        let stats = StorageStats::of_message(&message);
+        assert_eq!(stats, StorageStats { cells: 2, bits: 96 });
+        // lump + 400 per bit + 40000 per cell
+        assert_eq!(
+            calculator(0).message_forward_fee(&message).nanotons(),
+            400_000 + 96 * 400 + 2 * 40_000
+        );
+        assert_eq!(
+            calculator(-1).forward_fee(2, 96).nanotons(),
+            10_000_000 + 96 * 10_000 + 2 * 1_000_000
+        );
+    }
+
+    #[test]
+    fn gas_fee() {
+        let basechain = calculator(0);
+        assert_eq!(basechain.gas_fee(0).nanotons(), 40_000);
+        assert_eq!(basechain.gas_fee(100).nanotons(), 40_000);
+        assert_eq!(basechain.gas_fee(101).nanotons(), 40_400);
+        assert_eq!(basechain.gas_fee(3308).nanotons(), 1_323_200);
+        assert_eq!(calculator(-1).gas_fee(10_000).nanotons(), 100_000_000);
+    }
+
+    #[test]
+    fn fees_round_up() {
+        let fees = FeeCalculator::new(
+            calculator(0).gas_prices.clone(),
+            MsgForwardPrices {
+                lump_price: 0,
+                bit_price: 1,
+                cell_price: 0,
+                ihr_price_factor: 0,
+                first_frac: 0,
+                next_frac: 0,
+            },
+            Vec::new(),
+            false,
+        );
+        assert_eq!(fees.forward_fee(0, 1).nanotons(), 1);
+        assert_eq!(fees.forward_fee(0, 65_537).nanotons(), 2);
+    }
+
+    #[test]
+    fn storage_fee() {
+        let stats = StorageStats {
+            cells: 22,
+            bits: 5_000,
+        };
+        // (5000 * 1 + 22 * 500) per second for a day, in 1/65536 nanotons
+        assert_eq!(
+            calculator(0)
+                .storage_fee(stats, 86_400, 2 * 86_400)
+                .nanotons(),
+            (16_000u128 * 86_400).div_ceil(1 << 16)
+        );
+        assert_eq!(
+            calculator(-1)
+                .storage_fee(stats, 86_400, 2 * 86_400)
+                .nanotons(),
+            ((5_000u128 * 1_000 + 22 * 500_000) * 86_400).div_ceil(1 << 16)
+        );
+        assert_eq!(calculator(0).storage_fee(stats, 100, 100), Coins::ZERO);
+    }
+
+    #[test]
+    fn storage_fee_across_price_changes() {
+        let prices = |utime_since, cell_price_ps| StoragePrices {
+            utime_since,
+            bit_price_ps: 0,
+            cell_price_ps,
+            mc_bit_price_ps: 0,
+            mc_cell_price_ps: 0,
+        };
+        let base = calculator(0);
+        let fees = FeeCalculator::new(
+            base.gas_prices.clone(),
+            base.msg_forward_prices.clone(),
+            vec![prices(2_000, 3 << 16), prices(1_000, 1 << 16)],
+            false,
+        );
+        let stats = StorageStats { cells: 1, bits: 0 };
+        // 500 seconds before the first price are free, then 1000 at 1 and 500 at 3
+        assert_eq!(
+            fees.storage_fee(stats, 500, 2_500).nanotons(),
+            1_000 + 1_500
+        );
+        assert_eq!(
+            fees.storage_fee(stats, 1_500, 2_500).nanotons(),
+            500 + 1_500
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use crate::address::Address;
+use crate::cell::{ArcCell, CellBuilder, CellSlice};
+use crate::client::ApiClientV2;
+use crate::coins::Coins;
+use crate::error::{CellError, StackError, ToncenterError};
+use crate::message::{InternalMessage, StateInit};
+use crate::tvm::{stack_field, FromStack, StackEntry};
+use std::sync::Arc;
+
+const TRANSFER_OP: u32 = 0x0f8a7ea5;
+const BURN_OP: u32 = 0x595f07bc;
+
+/// Result of the `get_jetton_data` get method of a jetton master.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JettonData {
+    pub total_supply: Coins,
+    pub mintable: bool,
+    /// `None` once the admin rights are revoked.
+    pub admin: Option<Address>,
+    /// Metadata in the TEP-64 format.
+    pub content: ArcCell,
+    pub wallet_code: ArcCell,
+}
+
+impl FromStack for JettonData {
+    fn from_stack(stack: &[StackEntry]) -> Result<Self, StackError> {
+        Ok(Self {
+            total_supply: stack_field(stack, 0)?,
+            mintable: stack_field(stack, 1)?,
+            admin: stack_field::<CellSlice>(stack, 2)?.load_optional_address()?,
+            content: stack_field(stack, 3)?,
+            wallet_code: stack_field(stack, 4)?,
+        })
+    }
+}
+
+/// Result of the `get_wallet_data` get method of a jetton wallet.
+#[derive(Debug, Clone, PartialEq, FromStack)]
+pub struct JettonWalletData {
+    /// Balance in the smallest jetton units.
+    pub balance: Coins,
+    pub owner: Address,
+    pub master: Address,
+    pub wallet_code: ArcCell,
+}
+
+/// Storage layout of a jetton wallet, needed to compute its address offline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JettonWalletLayout {
+    /// Reference TEP-74 wallet: `balance:Coins owner:MsgAddress master:MsgAddress code:^Cell`.
+    Standard,
+    /// Wallet of jettons with governance such as USDT:
+    /// `status:uint4 balance:Coins owner:MsgAddress master:MsgAddress`.
+    Governed,
+}
+
+/// Jetton master (minter) contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JettonMaster {
+    pub address: Address,
+}
+
+impl JettonMaster {
+    pub fn new(address: Address) -> Self {
+        Self { address }
+    }
+
+    /// Resolve the jetton wallet of `owner` with the `get_wallet_address` get method.
+    pub async fn wallet_address(
+        &self,
+        client: &ApiClientV2,
+        owner: &Address,
+    ) -> Result<Address, ToncenterError> {
+        let (address,): (Address,) = client
+            .run_get_method_typed(
+                &self.address.to_string(),
+                "get_wallet_address",
+                &[StackEntry::try_from(*owner)?],
+            )
+            .await?;
+        Ok(address)
+    }
+
+    /// Fetch supply, admin and metadata with the `get_jetton_data` get method.
+    pub async fn jetton_data(&self, client: &ApiClientV2) -> Result<JettonData, ToncenterError> {
+        client
+            .run_get_method_typed(&self.address.to_string(), "get_jetton_data", &[])
+            .await
+    }
+
+    /// Compute the jetton wallet of `owner` without a request.
+    ///
+    /// `wallet_code` is [`JettonData::wallet_code`], which only changes when the master is
+    /// upgraded, so it can be fetched once and cached. Wallets live in the basechain.
+    pub fn wallet_address_offline(
+        &self,
+        owner: &Address,
+        wallet_code: &ArcCell,
+        layout: JettonWalletLayout,
+    ) -> Result<Address, CellError> {
+        let mut data = CellBuilder::new();
+        match layout {
+            JettonWalletLayout::Standard => {
+                data.store_coins(0)?
+                    .store_address(owner)?
+                    .store_address(&self.address)?
+                    .store_reference(wallet_code.clone())?;
+            }
+            JettonWalletLayout::Governed => {
+                data.store_uint(4, 0)?
+                    .store_coins(0)?
+                    .store_address(owner)?
+                    .store_address(&self.address)?;
+            }
+        }
+        StateInit::new(wallet_code.clone(), Arc::new(data.build()?)).address(0)
+    }
+}
+
+/// Jetton wallet contract holding the balance of one owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JettonWallet {
+    pub address: Address,
+}
+
+impl JettonWallet {
+    pub fn new(address: Address) -> Self {
+        Self { address }
+    }
+
+    /// Fetch balance, owner and master with the `get_wallet_data` get method.
+    pub async fn data(&self, client: &ApiClientV2) -> Result<JettonWalletData, ToncenterError> {
+        client
+            .run_get_method_typed(&self.address.to_string(), "get_wallet_data", &[])
+            .await
+    }
+}
+
+/// TEP-74 `transfer` request, sent by the owner to their jetton wallet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JettonTransfer {
+    pub query_id: u64,
+    /// Amount in the smallest jetton units.
+    pub amount: u128,
+    /// Owner of the receiving jetton wallet, not the wallet itself.
+    pub destination: Address,
+    /// Where the excess TON is returned.
+    pub response_destination: Option<Address>,
+    pub custom_payload: Option<ArcCell>,
+    /// TON attached to the `transfer_notification` sent to `destination`. No notification
+    /// is sent when zero.
+    pub forward_ton_amount: Coins,
+    pub forward_payload: Option<ArcCell>,
+}
+
+impl JettonTransfer {
+    pub fn new(query_id: u64, amount: u128, destination: Address) -> Self {
+        Self {
+            query_id,
+            amount,
+            destination,
+            response_destination: None,
+            custom_payload: None,
+            forward_ton_amount: Coins::from_nanotons(0),
+            forward_payload: None,
+        }
+    }
+
+    pub fn with_response_destination(mut self, response_destination: Address) -> Self {
+        self.response_destination = Some(response_destination);
+        self
+    }
+
+    pub fn with_custom_payload(mut self, custom_payload: ArcCell) -> Self {
+        self.custom_payload = Some(custom_payload);
+        self
+    }
+
+    /// Notify the receiver with `forward_ton_amount` and `payload`, e.g. a
+    /// [`comment_body`](crate::message::comment_body).
+    pub fn with_forward_payload(mut self, forward_ton_amount: Coins, payload: ArcCell) -> Self {
+        self.forward_ton_amount = forward_ton_amount;
+        self.forward_payload = Some(payload);
+        self
+    }
+
+    pub fn with_forward_ton_amount(mut self, forward_ton_amount: Coins) -> Self {
+        self.forward_ton_amount = forward_ton_amount;
+        self
+    }
+
+    /// `transfer#0f8a7ea5 query_id:uint64 amount:Coins destination:MsgAddress
+    /// response_destination:MsgAddress custom_payload:(Maybe ^Cell) forward_ton_amount:Coins
+    /// forward_payload:(Either Cell ^Cell)`, with the forward payload stored by reference.
+    pub fn to_cell(&self) -> Result<ArcCell, CellError> {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(TRANSFER_OP)?
+            .store_u64(self.query_id)?
+            .store_coins(self.amount)?
+            .store_address(&self.destination)?
+            .store_optional_address(self.response_destination.as_ref())?
+            .store_maybe_reference(self.custom_payload.clone())?
+            .store_coins(self.forward_ton_amount.nanotons())?
+            .store_maybe_reference(self.forward_payload.clone())?;
+        Ok(Arc::new(builder.build()?))
+    }
+
+    /// Message to the sender's jetton wallet carrying `value` TON for fees and the forward amount.
+    pub fn to_message(
+        &self,
+        jetton_wallet: Address,
+        value: Coins,
+    ) -> Result<InternalMessage, CellError> {
+        Ok(InternalMessage::transfer(jetton_wallet, value, true).with_body(self.to_cell()?))
+    }
+}
+
+/// TEP-74 `burn` request, sent by the owner to their jetton wallet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JettonBurn {
+    pub query_id: u64,
+    /// Amount in the smallest jetton units.
+    pub amount: u128,
+    /// Where the excess TON is returned.
+    pub response_destination: Option<Address>,
+    pub custom_payload: Option<ArcCell>,
+}
+
+impl JettonBurn {
+    pub fn new(query_id: u64, amount: u128) -> Self {
+        Self {
+            query_id,
+            amount,
+            response_destination: None,
+            custom_payload: None,
+        }
+    }
+
+    pub fn with_response_destination(mut self, response_destination: Address) -> Self {
+        self.response_destination = Some(response_destination);
+        self
+    }
+
+    pub fn with_custom_payload(mut self, custom_payload: ArcCell) -> Self {
+        self.custom_payload = Some(custom_payload);
+        self
+    }
+
+    /// `burn#595f07bc query_id:uint64 amount:Coins response_destination:MsgAddress
+    /// custom_payload:(Maybe ^Cell)`.
+    pub fn to_cell(&self) -> Result<ArcCell, CellError> {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(BURN_OP)?
+            .store_u64(self.query_id)?
+            .store_coins(self.amount)?
+            .store_optional_address(self.response_destination.as_ref())?
+            .store_maybe_reference(self.custom_payload.clone())?;
+        Ok(Arc::new(builder.build()?))
+    }
+
+    /// Message to the owner's jetton wallet carrying `value` TON for fees.
+    pub fn to_message(
+        &self,
+        jetton_wallet: Address,
+        value: Coins,
+    ) -> Result<InternalMessage, CellError> {
+        Ok(InternalMessage::transfer(jetton_wallet, value, true).with_body(self.to_cell()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::hex_string;
+    use crate::message::comment_body;
+    use num_bigint::BigInt;
+
+    // Vectors below were computed by an independent implementation of the TL-B layouts.
+
+    const USDT_MASTER: &str = "EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs";
+    const OWNER: &str = "EQAvDfWFG0oYX19jwNDNBBL1rKNT9XfaGP9HyTb5nb2Eml6y";
+    const RESPONSE: &str = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR";
+
+    fn address(address: &str) -> Address {
+        address.parse().unwrap()
+    }
+
+    fn cell(build: impl FnOnce(&mut CellBuilder)) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        build(&mut builder);
+        Arc::new(builder.build().unwrap())
+    }
+
+    /// Stand-in for a wallet code, the address only depends on its hash.
+    fn wallet_code() -> ArcCell {
+        cell(|b| {
+            b.store_bytes(b"jetton wallet code").unwrap();
+        })
+    }
+
+    #[test]
+    fn transfer_layout() {
+        let transfer = JettonTransfer::new(7, 1_000_000, address(OWNER))
+            .with_response_destination(address(RESPONSE))
+            .with_forward_payload(
+                Coins::from_nanotons(10_000_000),
+                comment_body("order 42").unwrap(),
+            );
+        let body = transfer.to_cell().unwrap();
+        assert_eq!(
+            hex_string(&body.repr_hash()),
+            "65045bfe8a2eec473ebc666edf701c2afc90813b1bc985d27e7f8a6643ea3a7b"
+        );
+
+        let mut slice = body.parser();
+        assert_eq!(slice.load_u32().unwrap(), TRANSFER_OP);
+        assert_eq!(slice.load_u64().unwrap(), 7);
+        assert_eq!(slice.load_coins().unwrap(), 1_000_000);
+        assert_eq!(slice.load_address().unwrap(), address(OWNER));
+        assert_eq!(slice.load_address().unwrap(), address(RESPONSE));
+        assert_eq!(slice.load_maybe_reference().unwrap(), None);
+        assert_eq!(slice.load_coins().unwrap(), 10_000_000);
+        assert_eq!(
+            slice.load_maybe_reference().unwrap(),
+            Some(comment_body("order 42").unwrap())
+        );
+        assert!(slice.is_empty());
+
+        // Without response destination, custom or forward payload
+        let minimal = JettonTransfer::new(0, 5, address(OWNER)).to_cell().unwrap();
+        assert_eq!(
+            hex_string(&minimal.repr_hash()),
+            "6afe249eff8aab8cbab823fadda3a582c17be6df1a9009d70078bcb1685df081"
+        );
+    }
+
+    #[test]
+    fn burn_layout() {
+        let custom_payload = cell(|b| {
+            b.store_u32(0xdeadbeef).unwrap();
+        });
+        let burn = JettonBurn::new(8, 250)
+            .with_response_destination(address(RESPONSE))
+            .with_custom_payload(custom_payload.clone());
+        let body = burn.to_cell().unwrap();
+        assert_eq!(
+            hex_string(&body.repr_hash()),
+            "64479344c7d055b2b7c7ebb0a37f1440787289bd28b1c3ae2a0b0cc52a0cd15c"
+        );
+
+        let message = burn
+            .to_message(address(OWNER), Coins::from_nanotons(50_000_000))
+            .unwrap();
+        assert!(message.bounce);
+        assert_eq!(message.body, Some(body));
+    }
+
+    #[test]
+    fn wallet_address_offline() {
+        let master = JettonMaster::new(address(USDT_MASTER));
+        let code = wallet_code();
+        assert_eq!(
+            master
+                .wallet_address_offline(&address(OWNER), &code, JettonWalletLayout::Standard)
+                .unwrap()
+                .to_string(),
+            "EQDx65JtAuBt1SzQIJVOl9N0B_XtOJh4OfZGotMIuXZAM2Ju"
+        );
+        assert_eq!(
+            master
+                .wallet_address_offline(&address(OWNER), &code, JettonWalletLayout::Governed)
+                .unwrap()
+                .to_string(),
+            "EQB_nY8Y5VBIwXvNG5C9h2N7foAJjRKaMbCpZ_M2uFHmd7Oh"
+        );
+    }
+
+    fn jetton_data_stack(admin: Option<Address>) -> Vec<StackEntry> {
+        let admin = cell(|b| {
+            b.store_optional_address(admin.as_ref()).unwrap();
+        });
+        vec![
+            StackEntry::Int(BigInt::from(1_000_000_000_000_000u64)),
+            StackEntry::Int(BigInt::from(-1)),
+            StackEntry::Slice(admin),
+            StackEntry::Cell(cell(|b| {
+                b.store_u8(0x01).unwrap();
+            })),
+            StackEntry::Cell(wallet_code()),
+        ]
+    }
+
+    #[test]
+    fn jetton_data_from_stack() {
+        let data = JettonData::from_stack(&jetton_data_stack(Some(address(RESPONSE)))).unwrap();
+        assert_eq!(
+            data,
+            JettonData {
+                total_supply: Coins::from_nanotons(1_000_000_000_000_000),
+                mintable: true,
+                admin: Some(address(RESPONSE)),
+                content: cell(|b| {
+                    b.store_u8(0x01).unwrap();
+                }),
+                wallet_code: wallet_code(),
+            }
+        );
+
+        // Revoked admin rights
+        let data = JettonData::from_stack(&jetton_data_stack(None)).unwrap();
+        assert_eq!(data.admin, None);
+
+        let mut stack = jetton_data_stack(None);
+        stack.pop();
+        assert!(JettonData::from_stack(&stack).is_err());
+    }
+}
@@ -1,3 +1,23 @@
+// `#[derive(FromStack)]` emits `::toncenter::` paths, this makes them resolve inside the crate
+extern crate self as toncenter;
+
+pub mod address;
+pub mod cell;
+pub mod checkpoint;
 pub mod client;
+pub mod coins;
+pub mod config;
+pub mod dns;
+pub mod error;
+pub mod fees;
+pub mod jetton;
+pub mod message;
+pub mod mnemonic;
 pub mod models;
-pub mod error;
\ No newline at end of file
+pub mod nft;
+pub mod proof;
+pub mod shard;
+pub mod signer;
+pub mod tvm;
+pub mod wallet;
+pub mod watcher;
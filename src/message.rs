@@ -0,0 +1,667 @@
+use crate::address::Address;
+use crate::cell::{ArcCell, Cell, CellBuilder, CellHash, CellSlice};
+use crate::coins::Coins;
+use crate::error::CellError;
+use num_bigint::BigUint;
+use std::collections::BTreeMap;
+use std::ops::BitOr;
+use std::sync::Arc;
+
+/// Flags of the `SENDRAWMSG` action controlling how the value and fees of a message are paid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SendMode(u8);
+
+impl SendMode {
+    /// Deduct fees from the message value.
+    pub const ORDINARY: SendMode = SendMode(0);
+    /// Pay forwarding fees separately from the message value.
+    pub const PAY_FEES_SEPARATELY: SendMode = SendMode(1);
+    /// Ignore errors during the action phase.
+    pub const IGNORE_ERRORS: SendMode = SendMode(2);
+    /// Bounce the inbound message if the action fails.
+    pub const BOUNCE_ON_ACTION_FAIL: SendMode = SendMode(16);
+    /// Destroy the account if its balance becomes zero.
+    pub const DESTROY_IF_ZERO: SendMode = SendMode(32);
+    /// Carry the remaining value of the inbound message in addition to the value.
+    pub const CARRY_REMAINING_VALUE: SendMode = SendMode(64);
+    /// Carry the whole remaining balance of the account.
+    pub const CARRY_ALL_BALANCE: SendMode = SendMode(128);
+
+    pub fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+}
+
+impl BitOr for SendMode {
+    type Output = SendMode;
+
+    fn bitor(self, other: SendMode) -> SendMode {
+        SendMode(self.0 | other.0)
+    }
+}
+
+/// Internal message sent by a contract, as found in wallet action lists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternalMessage {
+    pub destination: Address,
+    pub value: Coins,
+    pub bounce: bool,
+    pub body: Option<ArcCell>,
+    pub state_init: Option<ArcCell>,
+}
+
+impl InternalMessage {
+    /// Plain transfer without body.
+    pub fn transfer(destination: Address, value: Coins, bounce: bool) -> Self {
+        Self {
+            destination,
+            value,
+            bounce,
+            body: None,
+            state_init: None,
+        }
+    }
+
+    pub fn with_body(mut self, body: ArcCell) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    pub fn with_state_init(mut self, state_init: ArcCell) -> Self {
+        self.state_init = Some(state_init);
+        self
+    }
+
+    /// Serialize as `Message Any`. Source, fees and timestamps are left for the sender to fill in.
+    pub fn to_cell(&self) -> Result<ArcCell, CellError> {
+        let mut builder = CellBuilder::new();
+        // int_msg_info$0 ihr_disabled:Bool bounce:Bool bounced:Bool src:addr_none
+        builder
+            .store_bit(false)?
+            .store_bit(true)?
+            .store_bit(self.bounce)?
+            .store_bit(false)?
+            .store_address_none()?
+            .store_address(&self.destination)?
+            .store_coins(self.value.nanotons())?
+            // no extra currencies, ihr_fee, fwd_fee, created_lt and created_at
+            .store_bit(false)?
+            .store_coins(0)?
+            .store_coins(0)?
+            .store_u64(0)?
+            .store_u32(0)?;
+        store_init_and_body(&mut builder, self.state_init.as_ref(), self.body.as_ref())?;
+        Ok(Arc::new(builder.build()?))
+    }
+}
+
+/// Inbound external message carrying a signed request to a contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalMessage {
+    pub destination: Address,
+    pub state_init: Option<ArcCell>,
+    pub body: ArcCell,
+}
+
+impl ExternalMessage {
+    /// Parse a `Message Any` with `ext_in_msg_info`. The source address and import fee are dropped.
+    pub fn from_cell(cell: &ArcCell) -> Result<Self, CellError> {
+        let mut slice = cell.parser();
+        if slice.load_uint(2)? != 0b10 {
+            return Err(CellError::InvalidCell(
+                "not an inbound external message".to_string(),
+            ));
+        }
+        match slice.load_uint(2)? {
+            0b00 => {}
+            0b01 => {
+                // addr_extern$01 len:(## 9) external_address:(bits len)
+                let len = slice.load_uint(9)? as usize;
+                slice.skip_bits(len)?;
+            }
+            _ => {
+                return Err(CellError::InvalidCell(
+                    "external message source must be an external address".to_string(),
+                ))
+            }
+        }
+        let destination = slice.load_address()?;
+        slice.load_coins()?;
+
+        let state_init = if slice.load_bit()? {
+            if slice.load_bit()? {
+                Some(slice.load_reference()?)
+            } else {
+                Some(load_inline_state_init(&mut slice)?)
+            }
+        } else {
+            None
+        };
+        let body = if slice.load_bit()? {
+            let body = slice.load_reference()?;
+            slice.ensure_empty()?;
+            body
+        } else {
+            slice.to_cell()?
+        };
+
+        Ok(Self {
+            destination,
+            state_init,
+            body,
+        })
+    }
+
+    /// Parse a base64 BOC, e.g. one about to be passed to `send_boc`.
+    pub fn from_boc_b64(boc: &str) -> Result<Self, CellError> {
+        Self::from_cell(&Cell::from_boc_b64(boc)?.to_arc())
+    }
+
+    /// Serialize as `Message Any`, ready to be passed to `send_boc`.
+    pub fn to_cell(&self) -> Result<ArcCell, CellError> {
+        let mut builder = CellBuilder::new();
+        // ext_in_msg_info$10 src:addr_none dest:MsgAddressInt import_fee:Grams
+        builder
+            .store_uint(2, 0b10)?
+            .store_address_none()?
+            .store_address(&self.destination)?
+            .store_coins(0)?;
+        store_init_and_body(&mut builder, self.state_init.as_ref(), Some(&self.body))?;
+        Ok(Arc::new(builder.build()?))
+    }
+
+    /// Serialize to a base64 BOC.
+    pub fn to_boc_b64(&self) -> Result<String, CellError> {
+        self.to_cell()?.to_boc_b64()
+    }
+
+    /// Hash of the message with `src`, `import_fee` and `init` stripped and the body stored
+    /// by reference. It identifies the message however the sender serialized it and is what
+    /// explorers and the v3 API index external messages by.
+    pub fn normalized_hash(&self) -> Result<CellHash, CellError> {
+        let normalized = ExternalMessage {
+            destination: self.destination,
+            state_init: None,
+            body: self.body.clone(),
+        };
+        Ok(normalized.to_cell()?.repr_hash())
+    }
+}
+
+/// Normalized hash of an external message given as a base64 BOC.
+///
+/// See [`ExternalMessage::normalized_hash`]. Computing it before sending lets a sender record
+/// the message id and later find its transaction, e.g. with the v3 `transactionsByMessage`.
+pub fn normalized_message_hash(boc: &str) -> Result<CellHash, CellError> {
+    ExternalMessage::from_boc_b64(boc)?.normalized_hash()
+}
+
+/// `tick_tock$_ tick:Bool tock:Bool`: whether a special account is invoked in tick or tock
+/// transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TickTock {
+    pub tick: bool,
+    pub tock: bool,
+}
+
+/// `simple_lib$_ public:Bool root:^Cell`: library cell published by an account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Library {
+    pub public: bool,
+    pub root: ArcCell,
+}
+
+/// Initial state of a contract. Its representation hash is the contract address.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StateInit {
+    pub split_depth: Option<u8>,
+    pub special: Option<TickTock>,
+    pub code: Option<ArcCell>,
+    pub data: Option<ArcCell>,
+    pub libraries: Vec<Library>,
+}
+
+impl StateInit {
+    /// State with code and data only, as most contracts are deployed.
+    pub fn new(code: ArcCell, data: ArcCell) -> Self {
+        Self {
+            code: Some(code),
+            data: Some(data),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_split_depth(mut self, split_depth: u8) -> Self {
+        self.split_depth = Some(split_depth);
+        self
+    }
+
+    pub fn with_special(mut self, special: TickTock) -> Self {
+        self.special = Some(special);
+        self
+    }
+
+    pub fn with_library(mut self, root: ArcCell, public: bool) -> Self {
+        self.libraries.push(Library { public, root });
+        self
+    }
+
+    /// Parse a `StateInit` cell.
+    pub fn from_cell(cell: &ArcCell) -> Result<Self, CellError> {
+        let mut slice = cell.parser();
+        let split_depth = if slice.load_bit()? {
+            Some(slice.load_uint(5)? as u8)
+        } else {
+            None
+        };
+        let special = if slice.load_bit()? {
+            Some(TickTock {
+                tick: slice.load_bit()?,
+                tock: slice.load_bit()?,
+            })
+        } else {
+            None
+        };
+        let code = slice.load_maybe_reference()?;
+        let data = slice.load_maybe_reference()?;
+        let libraries = slice
+            .load_dict(256)?
+            .into_values()
+            .map(|mut value| {
+                Ok(Library {
+                    public: value.load_bit()?,
+                    root: value.load_reference()?,
+                })
+            })
+            .collect::<Result<_, CellError>>()?;
+        slice.ensure_empty()?;
+        Ok(Self {
+            split_depth,
+            special,
+            code,
+            data,
+            libraries,
+        })
+    }
+
+    /// `split_depth:(Maybe (## 5)) special:(Maybe TickTock) code:(Maybe ^Cell)
+    /// data:(Maybe ^Cell) library:(HashmapE 256 SimpleLib)`.
+    pub fn to_cell(&self) -> Result<ArcCell, CellError> {
+        let mut builder = CellBuilder::new();
+        match self.split_depth {
+            Some(depth) if depth < 32 => {
+                builder.store_bit(true)?.store_uint(5, depth as u64)?;
+            }
+            Some(depth) => {
+                return Err(CellError::InvalidCell(format!(
+                    "split depth {} does not fit into 5 bits",
+                    depth
+                )))
+            }
+            None => {
+                builder.store_bit(false)?;
+            }
+        }
+        match self.special {
+            Some(special) => {
+                builder
+                    .store_bit(true)?
+                    .store_bit(special.tick)?
+                    .store_bit(special.tock)?;
+            }
+            None => {
+                builder.store_bit(false)?;
+            }
+        }
+        let libraries = self
+            .libraries
+            .iter()
+            .map(|library| {
+                let mut value = CellBuilder::new();
+                value
+                    .store_bit(library.public)?
+                    .store_reference(library.root.clone())?;
+                Ok((
+                    BigUint::from_bytes_be(&library.root.repr_hash()),
+                    Arc::new(value.build()?),
+                ))
+            })
+            .collect::<Result<BTreeMap<_, _>, CellError>>()?;
+        builder
+            .store_maybe_reference(self.code.clone())?
+            .store_maybe_reference(self.data.clone())?
+            .store_dict(256, &libraries)?;
+        Ok(Arc::new(builder.build()?))
+    }
+
+    /// Address of the contract in `workchain`.
+    ///
+    /// With a split depth the first `split_depth` bits of a deployed address may be
+    /// rewritten to place the contract into a given shard.
+    pub fn address(&self, workchain: i32) -> Result<Address, CellError> {
+        Ok(Address::new(workchain, self.to_cell()?.repr_hash()))
+    }
+
+    /// External message deploying the contract. Contracts usually expect `body` to be
+    /// signed; an empty body is sent without one.
+    pub fn deploy_external(
+        &self,
+        workchain: i32,
+        body: Option<ArcCell>,
+    ) -> Result<ExternalMessage, CellError> {
+        Ok(ExternalMessage {
+            destination: self.address(workchain)?,
+            state_init: Some(self.to_cell()?),
+            body: body.unwrap_or_else(|| Cell::empty().to_arc()),
+        })
+    }
+
+    /// Non-bounceable internal message deploying the contract with `value`, to be sent
+    /// from a wallet.
+    pub fn deploy_internal(
+        &self,
+        workchain: i32,
+        value: Coins,
+        body: Option<ArcCell>,
+    ) -> Result<InternalMessage, CellError> {
+        Ok(InternalMessage {
+            destination: self.address(workchain)?,
+            value,
+            bounce: false,
+            body,
+            state_init: Some(self.to_cell()?),
+        })
+    }
+}
+
+/// Body of a text comment: op `0` followed by the UTF-8 text, continued in a chain of
+/// references once a cell is full.
+pub fn comment_body(text: &str) -> Result<ArcCell, CellError> {
+    const CELL_BYTES: usize = 127;
+    let bytes = text.as_bytes();
+    let head = bytes.len().min(CELL_BYTES - 4);
+    let mut tail: Option<ArcCell> = None;
+    for chunk in bytes[head..].chunks(CELL_BYTES).rev() {
+        let mut builder = CellBuilder::new();
+        builder.store_bytes(chunk)?;
+        if let Some(next) = tail.take() {
+            builder.store_reference(next)?;
+        }
+        tail = Some(Arc::new(builder.build()?));
+    }
+    let mut builder = CellBuilder::new();
+    builder.store_u32(0)?.store_bytes(&bytes[..head])?;
+    if let Some(next) = tail {
+        builder.store_reference(next)?;
+    }
+    Ok(Arc::new(builder.build()?))
+}
+
+/// Text of a comment body, `None` if `body` is not a valid UTF-8 text comment.
+pub fn parse_comment(body: &ArcCell) -> Option<String> {
+    let mut slice = body.parser();
+    if slice.remaining_bits() < 32 || slice.load_u32().ok()? != 0 {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    loop {
+        if !slice.remaining_bits().is_multiple_of(8) {
+            return None;
+        }
+        bytes.extend(slice.load_bytes(slice.remaining_bits() / 8).ok()?);
+        if slice.remaining_refs() == 0 {
+            break;
+        }
+        slice = slice.load_reference().ok()?.parser();
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Load a `StateInit` stored inline and copy it into a cell of its own.
+fn load_inline_state_init(slice: &mut CellSlice) -> Result<ArcCell, CellError> {
+    // split_depth:(Maybe (## 5)) special:(Maybe TickTock) code:(Maybe ^Cell)
+    // data:(Maybe ^Cell) library:(HashmapE 256 SimpleLib)
+    let mut end = slice.clone();
+    if end.load_bit()? {
+        end.skip_bits(5)?;
+    }
+    if end.load_bit()? {
+        end.skip_bits(2)?;
+    }
+    for _ in 0..3 {
+        end.load_maybe_reference()?;
+    }
+    let bits = slice.remaining_bits() - end.remaining_bits();
+    let refs = slice.remaining_refs() - end.remaining_refs();
+    slice.load_slice(bits, refs)?.to_cell()
+}
+
+/// Store `init:(Maybe (Either StateInit ^StateInit)) body:(Either X ^X)`, both by reference.
+fn store_init_and_body(
+    builder: &mut CellBuilder,
+    state_init: Option<&ArcCell>,
+    body: Option<&ArcCell>,
+) -> Result<(), CellError> {
+    match state_init {
+        Some(state_init) => {
+            builder
+                .store_uint(2, 0b11)?
+                .store_reference(state_init.clone())?;
+        }
+        None => {
+            builder.store_bit(false)?;
+        }
+    }
+    match body {
+        Some(body) => {
+            builder.store_bit(true)?.store_reference(body.clone())?;
+        }
+        None => {
+            builder.store_bit(false)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::hex_string;
+    use crate::wallet::{WalletV3R2, DEFAULT_WALLET_ID};
+    use ed25519_dalek::SigningKey;
+
+    fn destination() -> Address {
+        Address::from_raw_string(
+            "0:8dca78972eab4a8e2b1d3f2a7e43c1c563e696f65bb9c5ae0845684cee138384",
+        )
+        .unwrap()
+    }
+
+    /// External message with an external source, an import fee, an inline state init and
+    /// an inline body, none of which a wallet would produce.
+    fn unusual_message() -> ArcCell {
+        let mut code = CellBuilder::new();
+        code.store_u32(0xc0de).unwrap();
+        let mut data = CellBuilder::new();
+        data.store_u32(0xda7a).unwrap();
+
+        let mut builder = CellBuilder::new();
+        builder
+            .store_uint(2, 0b10)
+            .unwrap()
+            // addr_extern$01 len:(## 9) external_address:(bits len)
+            .store_uint(2, 0b01)
+            .unwrap()
+            .store_uint(9, 16)
+            .unwrap()
+            .store_uint(16, 0xabcd)
+            .unwrap()
+            .store_address(&destination())
+            .unwrap()
+            .store_coins(1234)
+            .unwrap()
+            // init:(Maybe (Either StateInit ^StateInit)) inline with code and data
+            .store_uint(2, 0b10)
+            .unwrap()
+            .store_uint(5, 0b00110)
+            .unwrap()
+            .store_reference(code.build().unwrap().to_arc())
+            .unwrap()
+            .store_reference(data.build().unwrap().to_arc())
+            .unwrap()
+            // body:(Either X ^X) inline
+            .store_bit(false)
+            .unwrap()
+            .store_u32(0)
+            .unwrap()
+            .store_bytes(b"hello")
+            .unwrap();
+        builder.build().unwrap().to_arc()
+    }
+
+    #[test]
+    fn normalized_hash_strips_source_fee_and_init() {
+        let message = ExternalMessage::from_cell(&unusual_message()).unwrap();
+        assert_eq!(message.destination, destination());
+        assert!(message.state_init.is_some());
+        assert_eq!(message.body, comment_body("hello").unwrap());
+
+        // ext_in_msg_info$10 src:addr_none dest import_fee:0 init:nothing body:^Cell
+        let expected = "e4dbacb8be13ca0ba9cdcc4c6ab0e8eeb09f756bc065f4b04598d9f2124f58fe";
+        assert_eq!(hex_string(&message.normalized_hash().unwrap()), expected);
+        let boc = unusual_message().to_boc_b64().unwrap();
+        assert_eq!(
+            hex_string(&normalized_message_hash(&boc).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn normalized_hash_ignores_serialization() {
+        let message = ExternalMessage {
+            destination: destination(),
+            state_init: None,
+            body: comment_body("hello").unwrap(),
+        };
+        let normalized = message.normalized_hash().unwrap();
+        assert_eq!(normalized, message.to_cell().unwrap().repr_hash());
+        let deploy = ExternalMessage {
+            state_init: Some(unusual_message()),
+            ..message
+        };
+        assert_eq!(deploy.normalized_hash().unwrap(), normalized);
+        assert_ne!(deploy.to_cell().unwrap().repr_hash(), normalized);
+    }
+
+    // Addresses were computed by an independent implementation of the TL-B layouts.
+
+    fn u32_cell(value: u32) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(value).unwrap();
+        builder.build().unwrap().to_arc()
+    }
+
+    fn full_state_init() -> StateInit {
+        StateInit::new(u32_cell(0xc0de), u32_cell(0xda7a))
+            .with_split_depth(5)
+            .with_special(TickTock {
+                tick: true,
+                tock: false,
+            })
+            .with_library(u32_cell(0x11b), true)
+    }
+
+    #[test]
+    fn state_init_round_trip() {
+        for state_init in [
+            StateInit::default(),
+            StateInit::new(u32_cell(0xc0de), u32_cell(0xda7a)),
+            full_state_init(),
+        ] {
+            let cell = state_init.to_cell().unwrap();
+            assert_eq!(StateInit::from_cell(&cell).unwrap(), state_init);
+        }
+
+        // Libraries are keyed by hash, so they are read back in hash order
+        let libraries = full_state_init().with_library(u32_cell(0x11c), false);
+        let cell = libraries.to_cell().unwrap();
+        let parsed = StateInit::from_cell(&cell).unwrap();
+        assert_eq!(parsed.to_cell().unwrap(), cell);
+        let mut expected = libraries.libraries.clone();
+        expected.sort_by_key(|library| library.root.repr_hash());
+        assert_eq!(parsed.libraries, expected);
+
+        let too_deep = StateInit::default().with_split_depth(32);
+        assert!(too_deep.to_cell().is_err());
+        let mut trailing = CellBuilder::new();
+        trailing.store_uint(5, 0).unwrap().store_bit(true).unwrap();
+        assert!(StateInit::from_cell(&trailing.build().unwrap().to_arc()).is_err());
+    }
+
+    #[test]
+    fn state_init_address() {
+        let state_init = StateInit::new(u32_cell(0xc0de), u32_cell(0xda7a));
+        assert_eq!(
+            state_init.address(0).unwrap().to_string(),
+            "EQDyrGiX4N_G3jbrcsXuMpsSrA9UJkcJb5tqfXWQzExWeZ54"
+        );
+        assert_eq!(
+            state_init.address(-1).unwrap().to_string(),
+            "Ef_yrGiX4N_G3jbrcsXuMpsSrA9UJkcJb5tqfXWQzExWeWEw"
+        );
+        assert_eq!(
+            full_state_init().address(0).unwrap().to_string(),
+            "EQCUQcPtIoGoxUKNCyTID1sVlr_ipU-AJs1sWOFh1fBXfpGN"
+        );
+
+        // Wallet v3r2 of the key derived from the seed [1; 32]
+        let mut data = CellBuilder::new();
+        data.store_u32(0)
+            .unwrap()
+            .store_u32(DEFAULT_WALLET_ID)
+            .unwrap()
+            .store_bytes(SigningKey::from_bytes(&[1; 32]).verifying_key().as_bytes())
+            .unwrap();
+        let wallet = StateInit::new(WalletV3R2::code(), data.build().unwrap().to_arc());
+        assert_eq!(
+            wallet.address(0).unwrap().to_string(),
+            "EQCNyniXLqtKjisdPyp-Q8HFY-aW9lu5xa4IRWhM7hODhE64"
+        );
+    }
+
+    #[test]
+    fn deploy_messages() {
+        let state_init = full_state_init();
+        let address = state_init.address(0).unwrap();
+
+        let external = state_init.deploy_external(0, None).unwrap();
+        assert_eq!(external.destination, address);
+        assert_eq!(external.state_init, Some(state_init.to_cell().unwrap()));
+        assert_eq!(external.body, Cell::empty().to_arc());
+        let parsed = ExternalMessage::from_cell(&external.to_cell().unwrap()).unwrap();
+        assert_eq!(parsed, external);
+
+        let body = comment_body("deploy").unwrap();
+        let external = state_init.deploy_external(-1, Some(body.clone())).unwrap();
+        assert_eq!(external.destination, state_init.address(-1).unwrap());
+        assert_eq!(external.body, body);
+
+        let internal = state_init
+            .deploy_internal(0, Coins::from_nanotons(50_000_000), Some(body.clone()))
+            .unwrap();
+        assert_eq!(
+            internal,
+            InternalMessage {
+                destination: address,
+                value: Coins::from_nanotons(50_000_000),
+                bounce: false,
+                body: Some(body),
+                state_init: Some(state_init.to_cell().unwrap()),
+            }
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use crate::error::MnemonicError;
+use bip39::Language;
+use ed25519_dalek::SigningKey;
+use hmac::{Hmac, Mac};
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha512;
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Number of words in a TON mnemonic.
+pub const MNEMONIC_WORDS: usize = 24;
+
+const PBKDF_ITERATIONS: u32 = 100_000;
+const BASIC_SEED_SALT: &[u8] = b"TON seed version";
+const PASSWORD_SEED_SALT: &[u8] = b"TON fast seed version";
+const KEY_SEED_SALT: &[u8] = b"TON default seed";
+
+/// TON mnemonic: 24 words of the BIP-39 English wordlist.
+///
+/// Unlike BIP-39 the words carry no checksum. A phrase is valid when the seed derived
+/// from it passes TON's seed version check, which is what [`Mnemonic::generate`] looks for.
+/// Words are wiped from memory on drop.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    words: Vec<String>,
+}
+
+impl Mnemonic {
+    /// Generate a new random mnemonic, optionally protected by a password.
+    pub fn generate(password: Option<&str>) -> Result<Self, MnemonicError> {
+        let word_list = Language::English.word_list();
+        let password = password.unwrap_or_default();
+        loop {
+            let mut random = Zeroizing::new([0u8; 2 * MNEMONIC_WORDS]);
+            getrandom::getrandom(random.as_mut())
+                .map_err(|err| MnemonicError::Random(err.to_string()))?;
+            let mnemonic = Self {
+                words: random
+                    .chunks(2)
+                    .map(|pair| {
+                        let index = u16::from_le_bytes([pair[0], pair[1]]) & 2047;
+                        word_list[index as usize].to_string()
+                    })
+                    .collect(),
+            };
+            if !password.is_empty() && !mnemonic.is_password_needed() {
+                continue;
+            }
+            if is_basic_seed(&mnemonic.entropy(password)) {
+                return Ok(mnemonic);
+            }
+        }
+    }
+
+    /// Parse and validate a space separated phrase. Words are case-insensitive.
+    ///
+    /// `password` must be given for mnemonics generated with one.
+    pub fn from_phrase(phrase: &str, password: Option<&str>) -> Result<Self, MnemonicError> {
+        let words = phrase.split_whitespace().collect::<Vec<_>>();
+        Self::from_words(&words, password)
+    }
+
+    pub fn from_words(words: &[&str], password: Option<&str>) -> Result<Self, MnemonicError> {
+        if words.len() != MNEMONIC_WORDS {
+            return Err(MnemonicError::WordCount(words.len()));
+        }
+        let mnemonic = Self {
+            words: words
+                .iter()
+                .map(|word| word.trim().to_lowercase())
+                .collect(),
+        };
+        if let Some(word) = mnemonic
+            .words
+            .iter()
+            .find(|word| Language::English.find_word(word).is_none())
+        {
+            return Err(MnemonicError::UnknownWord(word.clone()));
+        }
+
+        let password = password.unwrap_or_default();
+        if !password.is_empty() && !mnemonic.is_password_needed() {
+            return Err(MnemonicError::InvalidMnemonic);
+        }
+        if !is_basic_seed(&mnemonic.entropy(password)) {
+            return Err(MnemonicError::InvalidMnemonic);
+        }
+        Ok(mnemonic)
+    }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Words joined by single spaces.
+    pub fn phrase(&self) -> Zeroizing<String> {
+        Zeroizing::new(self.words.join(" "))
+    }
+
+    /// Whether the mnemonic was generated with a password and can not be used without it.
+    pub fn is_password_needed(&self) -> bool {
+        let entropy = self.entropy("");
+        is_password_seed(&entropy) && !is_basic_seed(&entropy)
+    }
+
+    /// Derive the ed25519 key pair of the mnemonic.
+    pub fn to_key_pair(&self, password: Option<&str>) -> KeyPair {
+        let entropy = self.entropy(password.unwrap_or_default());
+        let seed = pbkdf2(&entropy, KEY_SEED_SALT, PBKDF_ITERATIONS);
+        let mut secret = Zeroizing::new([0u8; 32]);
+        secret.copy_from_slice(&seed[..32]);
+        KeyPair::from_secret_key(&secret)
+    }
+
+    /// `HMAC-SHA512(key = phrase, message = password)`.
+    fn entropy(&self, password: &str) -> Zeroizing<[u8; 64]> {
+        let mut mac = Hmac::<Sha512>::new_from_slice(self.phrase().as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(password.as_bytes());
+        Zeroizing::new(mac.finalize().into_bytes().into())
+    }
+}
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        self.words.zeroize();
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mnemonic([{} words redacted])", self.words.len())
+    }
+}
+
+/// Ed25519 key pair. The secret key is wiped from memory on drop.
+#[derive(Clone)]
+pub struct KeyPair {
+    signing_key: SigningKey,
+}
+
+impl KeyPair {
+    pub fn from_secret_key(secret_key: &[u8; 32]) -> Self {
+        Self {
+            signing_key: SigningKey::from_bytes(secret_key),
+        }
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.signing_key.verifying_key().to_bytes()
+    }
+
+    pub fn secret_key(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.signing_key.to_bytes())
+    }
+
+    /// Key to sign wallet messages with.
+    pub fn signing_key(&self) -> &SigningKey {
+        &self.signing_key
+    }
+}
+
+impl fmt::Debug for KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPair")
+            .field("public_key", &crate::cell::hex_string(&self.public_key()))
+            .finish_non_exhaustive()
+    }
+}
+
+fn pbkdf2(entropy: &[u8; 64], salt: &[u8], iterations: u32) -> Zeroizing<[u8; 64]> {
+    let mut seed = Zeroizing::new([0u8; 64]);
+    pbkdf2_hmac::<Sha512>(entropy, salt, iterations, seed.as_mut());
+    seed
+}
+
+fn is_basic_seed(entropy: &[u8; 64]) -> bool {
+    pbkdf2(entropy, BASIC_SEED_SALT, (PBKDF_ITERATIONS / 256).max(1))[0] == 0
+}
+
+fn is_password_seed(entropy: &[u8; 64]) -> bool {
+    pbkdf2(entropy, PASSWORD_SEED_SALT, 1)[0] == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::hex_string;
+
+    const PHRASE: &str = "long bomb own void gravity orphan social suspect picture breeze ranch \
+        exotic friend enable heavy squeeze practice shuffle cruel feel great confirm wagon write";
+    const PASSWORD_PHRASE: &str = "fiction cash photo pink comfort earn infant giraffe uphold \
+        damage accuse shy reform ride shoe rubber lounge catch type million when pluck kid belt";
+    const PASSWORD: &str = "correct horse";
+
+    #[test]
+    fn key_pair_from_mnemonic() {
+        let mnemonic = Mnemonic::from_phrase(PHRASE, None).unwrap();
+        assert!(!mnemonic.is_password_needed());
+        let key_pair = mnemonic.to_key_pair(None);
+        assert_eq!(
+            hex_string(key_pair.secret_key().as_ref()),
+            "5fe53d4429a3efdf19d2d6e592c8188399fdccadebdf6b6a16a6508e8b3fa5bc"
+        );
+        assert_eq!(
+            hex_string(&key_pair.public_key()),
+            "45f5593e6df0b494d32be82fc0b3f0bc2d8b1cddd17ab12ccb55d9f7061606d3"
+        );
+    }
+
+    #[test]
+    fn password_protected_mnemonic() {
+        let mnemonic = Mnemonic::from_phrase(PASSWORD_PHRASE, Some(PASSWORD)).unwrap();
+        assert!(mnemonic.is_password_needed());
+        assert_eq!(
+            hex_string(&mnemonic.to_key_pair(Some(PASSWORD)).public_key()),
+            "c085056b899926b944dca6fb731890474829b2bb542d1c61db44479218d0f3cb"
+        );
+        assert_eq!(
+            Mnemonic::from_phrase(PASSWORD_PHRASE, None),
+            Err(MnemonicError::InvalidMnemonic)
+        );
+        assert_eq!(
+            Mnemonic::from_phrase(PASSWORD_PHRASE, Some("wrong horse")),
+            Err(MnemonicError::InvalidMnemonic)
+        );
+        // A password can not be added to a mnemonic generated without one
+        assert_eq!(
+            Mnemonic::from_phrase(PHRASE, Some(PASSWORD)),
+            Err(MnemonicError::InvalidMnemonic)
+        );
+    }
+
+    #[test]
+    fn words_are_normalized() {
+        let mnemonic = Mnemonic::from_phrase(&PHRASE.to_uppercase(), None).unwrap();
+        assert_eq!(
+            mnemonic.phrase().as_str(),
+            PHRASE.split_whitespace().collect::<Vec<_>>().join(" ")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_words() {
+        assert_eq!(
+            Mnemonic::from_phrase("long bomb own", None),
+            Err(MnemonicError::WordCount(3))
+        );
+        let unknown = PHRASE.replace("gravity", "gravitas");
+        assert_eq!(
+            Mnemonic::from_phrase(&unknown, None),
+            Err(MnemonicError::UnknownWord("gravitas".to_string()))
+        );
+        // Known words that fail the seed version check
+        assert_eq!(
+            Mnemonic::from_phrase(&["abandon"; MNEMONIC_WORDS].join(" "), None),
+            Err(MnemonicError::InvalidMnemonic)
+        );
+    }
+
+    #[test]
+    fn generated_mnemonic_is_valid() {
+        let mnemonic = Mnemonic::generate(None).unwrap();
+        assert_eq!(mnemonic.words().len(), MNEMONIC_WORDS);
+        assert_eq!(
+            Mnemonic::from_phrase(&mnemonic.phrase(), None),
+            Ok(mnemonic)
+        );
+    }
+}
@@ -1,13 +1,16 @@
+use crate::cell::{ArcCell, Cell};
+use crate::error::CellError;
+use crate::tvm::StackEntry;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     pub ok: bool,
     #[serde(flatten)]
     pub data: ApiResponseResult<T>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ApiResponseResult<T> {
     Success {
@@ -21,7 +24,8 @@ pub enum ApiResponseResult<T> {
 }
 
 /// Represents `@type: raw.fullAccountState`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "raw.fullAccountState")]
 pub struct RawFullAccountState {
     pub balance: String,
     pub code: Option<String>,
@@ -36,7 +40,8 @@ pub struct RawFullAccountState {
 }
 
 /// Represents `@type: fullAccountState`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "fullAccountState")]
 pub struct FullAccountState {
     pub address: AccountAddress,
     pub balance: String,
@@ -50,52 +55,200 @@ pub struct FullAccountState {
 }
 
 /// Represents `@type: accountAddress`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "accountAddress")]
 pub struct AccountAddress {
     pub account_address: String,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(tag = "@type")]
+/// Represents tonlib's `accountState` family.
+///
+/// Types the crate does not know yet, and known types whose fields do not match, are kept
+/// as `Unknown` with the original JSON, so changes on the server side don't break
+/// deserialization.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", remote = "Self")]
 pub enum AccountState {
-    #[serde(rename = "uninited.accountState")]
-    UninitedAccountState { frozen_hash: String },
+    #[serde(rename = "raw.accountState")]
+    RawAccountState {
+        code: String,
+        data: String,
+        frozen_hash: String,
+    },
+    #[serde(rename = "wallet.v3.accountState")]
+    WalletV3AccountState { wallet_id: String, seqno: u32 },
     #[serde(rename = "wallet.v4.accountState")]
     WalletV4AccountState { wallet_id: String, seqno: u32 },
+    #[serde(rename = "wallet.highload.v1.accountState")]
+    WalletHighloadV1AccountState { wallet_id: String, seqno: u32 },
+    #[serde(rename = "wallet.highload.v2.accountState")]
+    WalletHighloadV2AccountState { wallet_id: String },
+    #[serde(rename = "dns.accountState")]
+    DnsAccountState { wallet_id: String },
+    #[serde(rename = "rwallet.accountState")]
+    RWalletAccountState {
+        wallet_id: String,
+        seqno: u32,
+        unlocked_balance: String,
+        config: RWalletConfig,
+    },
+    #[serde(rename = "pchan.accountState")]
+    PChanAccountState {
+        config: PChanConfig,
+        state: PChanState,
+        description: String,
+    },
+    #[serde(rename = "uninited.accountState")]
+    UninitedAccountState { frozen_hash: String },
+    #[serde(skip)]
+    Unknown {
+        type_name: String,
+        raw: serde_json::Value,
+    },
+}
+
+impl Serialize for AccountState {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            AccountState::Unknown { raw, .. } => raw.serialize(serializer),
+            known => AccountState::serialize(known, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AccountState {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = serde_json::Value::deserialize(deserializer)?;
+        // The variant renames are the list of known types: anything they reject, an unknown
+        // `@type` or a known one with different fields, is kept as is
+        match AccountState::deserialize(&raw) {
+            Ok(state) => Ok(state),
+            Err(_) => Ok(AccountState::Unknown {
+                type_name: raw["@type"].as_str().unwrap_or_default().to_string(),
+                raw,
+            }),
+        }
+    }
+}
+
+/// Represents `@type: rwallet.config`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "rwallet.config")]
+pub struct RWalletConfig {
+    pub start_at: i64,
+    pub limits: Vec<RWalletLimit>,
+}
+
+/// Represents `@type: rwallet.limit`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "rwallet.limit")]
+pub struct RWalletLimit {
+    pub seconds: i32,
+    pub value: String,
+}
+
+/// Represents `@type: pchan.config`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "pchan.config")]
+pub struct PChanConfig {
+    pub alice_public_key: String,
+    pub alice_address: AccountAddress,
+    pub bob_public_key: String,
+    pub bob_address: AccountAddress,
+    pub init_timeout: i32,
+    pub close_timeout: i32,
+    pub channel_id: String,
+}
+
+/// Represents tonlib's `pchan.State` family.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type")]
+pub enum PChanState {
+    #[serde(rename = "pchan.stateInit")]
+    Init {
+        #[serde(rename = "signed_A")]
+        signed_a: bool,
+        #[serde(rename = "signed_B")]
+        signed_b: bool,
+        #[serde(rename = "min_A")]
+        min_a: String,
+        #[serde(rename = "min_B")]
+        min_b: String,
+        expire_at: i64,
+        #[serde(rename = "A")]
+        a: String,
+        #[serde(rename = "B")]
+        b: String,
+    },
+    #[serde(rename = "pchan.stateClose")]
+    Close {
+        #[serde(rename = "signed_A")]
+        signed_a: bool,
+        #[serde(rename = "signed_B")]
+        signed_b: bool,
+        #[serde(rename = "min_A")]
+        min_a: String,
+        #[serde(rename = "min_B")]
+        min_b: String,
+        expire_at: i64,
+        #[serde(rename = "A")]
+        a: String,
+        #[serde(rename = "B")]
+        b: String,
+    },
+    #[serde(rename = "pchan.statePayout")]
+    Payout {
+        #[serde(rename = "A")]
+        a: String,
+        #[serde(rename = "B")]
+        b: String,
+    },
 }
 
 /// Represents `@type: internal.transactionId`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "internal.transactionId")]
 pub struct InternalTransactionId {
     pub lt: String,
     pub hash: String,
 }
 
 /// Represents `@type: ton.blockIdExt`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "ton.blockIdExt")]
 pub struct TonBlockIdExt {
     pub workchain: i32,
     pub shard: String,
     pub seqno: u32,
     pub root_hash: String,
     pub file_hash: String,
-    #[serde(rename = "@extra")]
+    #[serde(rename = "@extra", skip_serializing_if = "Option::is_none")]
     pub extra: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Result of `getWalletInformation`.
+///
+/// `wallet_type`, `seqno` and `wallet_id` are optional since toncenter omits them for
+/// accounts that are not wallets, which previously failed to deserialize. Code reading them
+/// as plain values needs to handle the `None` case, e.g. `seqno.unwrap_or(0)` for an
+/// undeployed wallet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WalletInformation {
     pub wallet: bool,
     pub balance: String,
     pub account_state: String,
-    pub wallet_type: String,
-    pub seqno: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seqno: Option<u32>,
     pub last_transaction_id: InternalTransactionId,
-    pub wallet_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_id: Option<u64>,
 }
 
 /// Represents `@type: raw.transaction`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "raw.transaction")]
 pub struct RawTransaction {
     pub address: AccountAddress,
     pub utime: u64,
@@ -109,7 +262,8 @@ pub struct RawTransaction {
 }
 
 /// Represents `@type: raw.message`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "raw.message")]
 pub struct RawMessage {
     pub source: Option<String>,
     pub destination: String,
@@ -119,18 +273,32 @@ pub struct RawMessage {
     pub created_lt: String,
     pub body_hash: String,
     pub msg_data: MsgDataRaw,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
 }
 
-/// Represents `@type: msg.dataRaw`.
-#[derive(Debug, Deserialize)]
+/// Represents tonlib's `msg.Data` family: `msg.dataRaw`, `msg.dataText` and the encrypted variants.
+///
+/// The original `@type` is kept so that the object serializes back unchanged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MsgDataRaw {
+    #[serde(rename = "@type", default = "MsgDataRaw::default_type")]
+    pub type_field: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub init_state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl MsgDataRaw {
+    fn default_type() -> String {
+        "msg.dataRaw".to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TokenData {
     pub init: bool,
     pub index: u32,
@@ -140,13 +308,13 @@ pub struct TokenData {
     pub contract_type: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TokenContent {
     pub r#type: String,
     pub data: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DetectAddressResult {
     pub raw_form: String,
     pub bounceable: AddressFormat,
@@ -155,14 +323,15 @@ pub struct DetectAddressResult {
     pub test_only: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AddressFormat {
     pub b64: String,
     pub b64url: String,
 }
 
 /// Represents `@type: blocks.masterchainInfo`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "blocks.masterchainInfo")]
 pub struct BlocksMasterchainInfo {
     pub last: TonBlockIdExt,
     pub state_root_hash: String,
@@ -171,7 +340,7 @@ pub struct BlocksMasterchainInfo {
     pub extra: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MasterchainBlockSignatures {
     #[serde(rename = "@type")]
     pub type_field: String,
@@ -182,14 +351,16 @@ pub struct MasterchainBlockSignatures {
 }
 
 /// Represents `@type: blocks.signature`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "blocks.signature")]
 pub struct BlocksSignature {
     pub node_id_short: String,
     pub signature: String,
 }
 
 /// Represents `@type: blocks.shardBlockProof`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "blocks.shardBlockProof")]
 pub struct BlocksShardBlockProof {
     pub from: TonBlockIdExt,
     pub mc_id: TonBlockIdExt,
@@ -200,14 +371,16 @@ pub struct BlocksShardBlockProof {
 }
 
 /// Represents `@type: blocks.shardBlockLink`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "blocks.shardBlockLink")]
 pub struct BlocksShardBlockLink {
     pub id: TonBlockIdExt,
     pub proof: String,
 }
 
 /// Represents `@type: blocks.blockLinkBack`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "blocks.blockLinkBack")]
 pub struct BlocksBlockLinkBack {
     pub to_key_block: bool,
     pub from: TonBlockIdExt,
@@ -217,33 +390,36 @@ pub struct BlocksBlockLinkBack {
     pub state_proof: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ConsensusBlock {
     pub consensus_block: u32,
     pub timestamp: f64,
 }
 
 /// Represents `@type: blocks.shards`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "blocks.shards")]
 pub struct BlocksShards {
     pub shards: Vec<TonBlockIdExt>,
-    #[serde(rename = "@extra")]
+    #[serde(rename = "@extra", skip_serializing_if = "Option::is_none")]
     pub extra: Option<String>,
 }
 
 /// Represents `@type: blocks.transactions`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "blocks.transactions")]
 pub struct BlocksTransactions {
     pub id: TonBlockIdExt,
     pub req_count: u32,
     pub incomplete: bool,
     pub transactions: Vec<BlocksShortTxId>,
-    #[serde(rename = "@extra")]
+    #[serde(rename = "@extra", skip_serializing_if = "Option::is_none")]
     pub extra: Option<String>,
 }
 
 /// Represents `@type: blocks.shortTxId`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "blocks.shortTxId")]
 pub struct BlocksShortTxId {
     pub mode: i32,
     pub account: String,
@@ -252,7 +428,8 @@ pub struct BlocksShortTxId {
 }
 
 /// Represents `@type: blocks.header`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "blocks.header")]
 pub struct BlocksHeader {
     pub id: TonBlockIdExt,
     pub global_id: i32,
@@ -272,43 +449,111 @@ pub struct BlocksHeader {
     pub end_lt: String,
     pub gen_utime: u64,
     pub prev_blocks: Vec<TonBlockIdExt>,
-    #[serde(rename = "@extra")]
+    #[serde(rename = "@extra", skip_serializing_if = "Option::is_none")]
     pub extra: Option<String>,
 }
 
 /// Represents `@type: configInfo`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "configInfo")]
 pub struct ConfigInfo {
     pub config: TvmCell,
-    #[serde(rename = "@extra")]
+    #[serde(rename = "@extra", skip_serializing_if = "Option::is_none")]
     pub extra: Option<String>,
 }
 
 /// Represents `@type: tvm.cell`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "tvm.cell")]
 pub struct TvmCell {
     pub bytes: String,
 }
 
+impl TvmCell {
+    /// Deserialize the base64-encoded BOC.
+    pub fn to_cell(&self) -> Result<ArcCell, CellError> {
+        Cell::from_boc_b64(&self.bytes).map(Cell::to_arc)
+    }
+}
+
 /// Represents `@type: smc.runResult`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "RawSmcRunResult")]
 pub struct SmcRunResult {
     pub gas_used: u32,
-    pub stack: Vec<(String, String)>,
+    pub stack: Vec<StackEntry>,
     pub exit_code: i32,
-    #[serde(rename = "@extra")]
     pub extra: Option<String>,
+    /// Stack as toncenter returned it, including the decoded `object` of cells. Entries
+    /// still equal to their counterpart in `stack` are serialized from here unchanged.
+    pub raw_stack: Vec<serde_json::Value>,
+}
+
+/// Wire format of [`SmcRunResult`].
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "smc.runResult")]
+struct RawSmcRunResult {
+    gas_used: u32,
+    stack: Vec<serde_json::Value>,
+    exit_code: i32,
+    #[serde(rename = "@extra", default, skip_serializing_if = "Option::is_none")]
+    extra: Option<String>,
+}
+
+impl TryFrom<RawSmcRunResult> for SmcRunResult {
+    type Error = String;
+
+    fn try_from(raw: RawSmcRunResult) -> Result<Self, Self::Error> {
+        Ok(Self {
+            gas_used: raw.gas_used,
+            stack: raw
+                .stack
+                .iter()
+                .map(StackEntry::from_json)
+                .collect::<Result<_, _>>()
+                .map_err(|err| err.to_string())?,
+            exit_code: raw.exit_code,
+            extra: raw.extra,
+            raw_stack: raw.stack,
+        })
+    }
+}
+
+impl Serialize for SmcRunResult {
+    /// Writes the stack in the shape `runGetMethod` returns it, e.g. `["cell", {"bytes": ".."}]`.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+        let stack = self
+            .stack
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| match self.raw_stack.get(index) {
+                Some(raw) if StackEntry::from_json(raw).as_ref() == Ok(entry) => Ok(raw.clone()),
+                _ => entry.to_response_json(),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(S::Error::custom)?;
+        RawSmcRunResult {
+            gas_used: self.gas_used,
+            stack,
+            exit_code: self.exit_code,
+            extra: self.extra.clone(),
+        }
+        .serialize(serializer)
+    }
 }
 
 /// Represents `@type: ok`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "ok")]
 pub struct Success {
     #[serde(rename = "@extra")]
     pub extra: String,
 }
 
 /// Represents `@type: raw.extMessageInfo`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "raw.extMessageInfo")]
 pub struct RawExtMessageInfo {
     pub hash: String,
     #[serde(rename = "@extra")]
@@ -316,7 +561,8 @@ pub struct RawExtMessageInfo {
 }
 
 /// Represents `@type: query.fees`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "query.fees")]
 pub struct QueryFees {
     pub source_fees: Fees,
     pub destination_fees: Vec<Fees>,
@@ -325,7 +571,8 @@ pub struct QueryFees {
 }
 
 /// Represents `@type: fees`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "@type", rename = "fees")]
 pub struct Fees {
     pub in_fwd_fee: u64,
     pub storage_fee: u64,
@@ -333,7 +580,7 @@ pub struct Fees {
     pub fwd_fee: u64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
     pub method: String,
@@ -341,7 +588,7 @@ pub struct JsonRpcRequest {
     pub id: serde_json::Value,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct JsonRpcResponse<T> {
     pub ok: bool,
     pub jsonrpc: String,
@@ -350,7 +597,7 @@ pub struct JsonRpcResponse<T> {
     pub id: serde_json::Value,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum JsonRpcResult<T> {
     Success {
@@ -362,3 +609,213 @@ pub enum JsonRpcResult<T> {
         code: u32,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn known_account_state() {
+        let json = json!({
+            "@type": "wallet.v3.accountState",
+            "wallet_id": "698983191",
+            "seqno": 12
+        });
+        let state: AccountState = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(
+            state,
+            AccountState::WalletV3AccountState {
+                wallet_id: "698983191".to_string(),
+                seqno: 12,
+            }
+        );
+        assert_eq!(serde_json::to_value(&state).unwrap(), json);
+    }
+
+    #[test]
+    fn unknown_account_state() {
+        let json = json!({
+            "@type": "wallet.v5.accountState",
+            "wallet_id": "2147483409",
+            "seqno": 3
+        });
+        let state: AccountState = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(
+            state,
+            AccountState::Unknown {
+                type_name: "wallet.v5.accountState".to_string(),
+                raw: json.clone(),
+            }
+        );
+        assert_eq!(serde_json::to_value(&state).unwrap(), json);
+    }
+
+    #[test]
+    fn known_account_state_with_other_fields() {
+        let json = json!({ "@type": "wallet.v4.accountState", "wallet_id": "698983191" });
+        let state: AccountState = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(
+            state,
+            AccountState::Unknown {
+                type_name: "wallet.v4.accountState".to_string(),
+                raw: json,
+            }
+        );
+    }
+
+    fn assert_round_trip<T>(json: serde_json::Value) -> T
+    where
+        T: serde::de::DeserializeOwned + Serialize,
+    {
+        let value: T = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&value).unwrap(), json);
+        value
+    }
+
+    #[test]
+    fn raw_full_account_state_round_trip() {
+        let state: RawFullAccountState = assert_round_trip(json!({
+            "@type": "raw.fullAccountState",
+            "balance": "1242462233",
+            "code": "te6cckEBAQEAAgAAAEysuc0=",
+            "data": "te6cckEBAQEAAgAAAEysuc0=",
+            "last_transaction_id": {
+                "@type": "internal.transactionId",
+                "lt": "47584224000001",
+                "hash": "8sOCHXuiGgEFJhDiHPvYUUCzMvUIlzmbfKUdZHtaQ9M="
+            },
+            "block_id": {
+                "@type": "ton.blockIdExt",
+                "workchain": -1,
+                "shard": "-9223372036854775808",
+                "seqno": 38743891,
+                "root_hash": "oZHCqWIAGxfYUwTB0KvOkkQ8wDMO9Jp9TM6Ts0MCOhk=",
+                "file_hash": "J/gzBy0DdOT2z9EQF6b2OV9c4kgNRg73azGYJYv1mg0="
+            },
+            "frozen_hash": "",
+            "sync_utime": 1717600394,
+            "@extra": "1717600401.2469604:0:0.3452085290328487",
+            "state": "active"
+        }));
+        assert_eq!(state.last_transaction_id.lt, "47584224000001");
+        assert_eq!(state.block_id.workchain, -1);
+    }
+
+    #[test]
+    fn smc_run_result_keeps_stack_objects() {
+        let json = json!({
+            "@type": "smc.runResult",
+            "gas_used": 1997,
+            "stack": [
+                ["num", "0x1c"],
+                ["cell", {
+                    "bytes": "te6cckEBAQEAAgAAAEysuc0=",
+                    "object": { "data": { "b64": "", "len": 0 }, "refs": [], "special": false }
+                }]
+            ],
+            "exit_code": 0,
+            "@extra": "1717600401.2469604:1:0.5"
+        });
+        let result: SmcRunResult = assert_round_trip(json);
+        assert_eq!(result.stack[0], StackEntry::Int(28.into()));
+        assert_eq!(result.stack[1], StackEntry::Cell(Cell::empty().to_arc()));
+    }
+
+    #[test]
+    fn smc_run_result_serializes_changed_entries() {
+        let mut result: SmcRunResult = serde_json::from_value(json!({
+            "@type": "smc.runResult",
+            "gas_used": 1997,
+            "stack": [["num", "0x1c"]],
+            "exit_code": 0
+        }))
+        .unwrap();
+        result.stack.push(StackEntry::Null);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["stack"], json!([["num", "0x1c"], ["null", null]]));
+    }
+
+    #[test]
+    fn raw_transaction_round_trip() {
+        let transaction: RawTransaction = assert_round_trip(json!({
+            "@type": "raw.transaction",
+            "address": {
+                "@type": "accountAddress",
+                "account_address": "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N"
+            },
+            "utime": 1717600385,
+            "data": "te6cckEBAQEAAgAAAEysuc0=",
+            "transaction_id": {
+                "@type": "internal.transactionId",
+                "lt": "47584224000001",
+                "hash": "8sOCHXuiGgEFJhDiHPvYUUCzMvUIlzmbfKUdZHtaQ9M="
+            },
+            "fee": "2750803",
+            "storage_fee": "3",
+            "other_fee": "2750800",
+            "in_msg": {
+                "@type": "raw.message",
+                "source": "EQBfAN7LfaUYgXZNw5Wc7GBgkEX2yhuJ5ka95J1JJwXXf4a8",
+                "destination": "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N",
+                "value": "1000000000",
+                "fwd_fee": "266669",
+                "ihr_fee": "0",
+                "created_lt": "47584223000002",
+                "body_hash": "lqKW0iTyhcZ77pPDD4owkVfw2qNdxbh+QQt4YwoJz8c=",
+                "msg_data": {
+                    "@type": "msg.dataText",
+                    "text": "aGVsbG8="
+                },
+                "message": "hello"
+            },
+            "out_msgs": [{
+                "@type": "raw.message",
+                "source": "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N",
+                "destination": "EQBfAN7LfaUYgXZNw5Wc7GBgkEX2yhuJ5ka95J1JJwXXf4a8",
+                "value": "500000000",
+                "fwd_fee": "266669",
+                "ihr_fee": "0",
+                "created_lt": "47584224000002",
+                "body_hash": "lqKW0iTyhcZ77pPDD4owkVfw2qNdxbh+QQt4YwoJz8c=",
+                "msg_data": {
+                    "@type": "msg.dataRaw",
+                    "body": "te6cckEBAQEAAgAAAEysuc0=",
+                    "init_state": ""
+                }
+            }]
+        }));
+        let in_msg = transaction.in_msg.unwrap();
+        assert_eq!(in_msg.msg_data.type_field, "msg.dataText");
+        assert_eq!(in_msg.message.as_deref(), Some("hello"));
+        assert_eq!(transaction.out_msgs[0].message, None);
+    }
+
+    #[test]
+    fn raw_account_state_round_trip() {
+        let state: AccountState = assert_round_trip(json!({
+            "@type": "raw.accountState",
+            "code": "te6cckEBAQEAAgAAAEysuc0=",
+            "data": "te6cckEBAQEAAgAAAEysuc0=",
+            "frozen_hash": ""
+        }));
+        assert!(matches!(state, AccountState::RawAccountState { .. }));
+    }
+
+    #[test]
+    fn wallet_information_of_non_wallet() {
+        let information: WalletInformation = assert_round_trip(json!({
+            "wallet": false,
+            "balance": "0",
+            "account_state": "uninitialized",
+            "last_transaction_id": {
+                "@type": "internal.transactionId",
+                "lt": "0",
+                "hash": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
+            }
+        }));
+        assert_eq!(information.seqno, None);
+        assert_eq!(information.wallet_type, None);
+        assert_eq!(information.wallet_id, None);
+    }
+}
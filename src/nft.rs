@@ -0,0 +1,466 @@
+use crate::address::Address;
+use crate::cell::{ArcCell, CellBuilder, CellSlice};
+use crate::client::ApiClientV2;
+use crate::coins::Coins;
+use crate::error::{CellError, StackError, ToncenterError};
+use crate::message::InternalMessage;
+use crate::tvm::{stack_field, FromStack, StackEntry};
+use num_bigint::BigInt;
+use std::sync::Arc;
+
+const TRANSFER_OP: u32 = 0x5fcc3d14;
+const PROVE_OWNERSHIP_OP: u32 = 0x04ded148;
+const REQUEST_OWNER_OP: u32 = 0xd0c3bfea;
+
+/// Result of the `get_nft_data` get method of an NFT item or SBT.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NftData {
+    pub initialized: bool,
+    pub index: BigInt,
+    /// `None` for items outside of a collection.
+    pub collection: Option<Address>,
+    /// `None` while the item is not initialized.
+    pub owner: Option<Address>,
+    /// Item content, to be combined with the collection content by
+    /// [`NftCollection::nft_content`] when the item belongs to a collection.
+    pub individual_content: ArcCell,
+}
+
+impl NftData {
+    /// Whether `address` is the current owner of the item.
+    pub fn is_owned_by(&self, address: &Address) -> bool {
+        self.initialized && self.owner.as_ref() == Some(address)
+    }
+}
+
+impl FromStack for NftData {
+    fn from_stack(stack: &[StackEntry]) -> Result<Self, StackError> {
+        Ok(Self {
+            initialized: stack_field(stack, 0)?,
+            index: stack_field(stack, 1)?,
+            collection: stack_field::<CellSlice>(stack, 2)?.load_optional_address()?,
+            owner: stack_field::<CellSlice>(stack, 3)?.load_optional_address()?,
+            individual_content: stack_field(stack, 4)?,
+        })
+    }
+}
+
+/// Result of the `get_collection_data` get method of an NFT collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NftCollectionData {
+    /// Index of the next item to be minted, `-1` when items are not minted in order.
+    pub next_item_index: BigInt,
+    /// Collection metadata in the TEP-64 format.
+    pub content: ArcCell,
+    /// `None` for collections without an owner.
+    pub owner: Option<Address>,
+}
+
+impl FromStack for NftCollectionData {
+    fn from_stack(stack: &[StackEntry]) -> Result<Self, StackError> {
+        Ok(Self {
+            next_item_index: stack_field(stack, 0)?,
+            content: stack_field(stack, 1)?,
+            owner: stack_field::<CellSlice>(stack, 2)?.load_optional_address()?,
+        })
+    }
+}
+
+/// NFT collection contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NftCollection {
+    pub address: Address,
+}
+
+impl NftCollection {
+    pub fn new(address: Address) -> Self {
+        Self { address }
+    }
+
+    /// Fetch the next index, content and owner with the `get_collection_data` get method.
+    pub async fn data(&self, client: &ApiClientV2) -> Result<NftCollectionData, ToncenterError> {
+        client
+            .run_get_method_typed(&self.address.to_string(), "get_collection_data", &[])
+            .await
+    }
+
+    /// Resolve the item with `index` with the `get_nft_address_by_index` get method.
+    pub async fn nft_address(
+        &self,
+        client: &ApiClientV2,
+        index: impl Into<BigInt>,
+    ) -> Result<Address, ToncenterError> {
+        let (address,): (Address,) = client
+            .run_get_method_typed(
+                &self.address.to_string(),
+                "get_nft_address_by_index",
+                &[StackEntry::Int(index.into())],
+            )
+            .await?;
+        Ok(address)
+    }
+
+    /// Full content of an item from its index and individual content, with the
+    /// `get_nft_content` get method.
+    pub async fn nft_content(
+        &self,
+        client: &ApiClientV2,
+        data: &NftData,
+    ) -> Result<ArcCell, ToncenterError> {
+        let stack = [
+            StackEntry::Int(data.index.clone()),
+            StackEntry::Cell(data.individual_content.clone()),
+        ];
+        let (content,): (ArcCell,) = client
+            .run_get_method_typed(&self.address.to_string(), "get_nft_content", &stack)
+            .await?;
+        Ok(content)
+    }
+}
+
+/// NFT item or soulbound token (SBT) contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NftItem {
+    pub address: Address,
+}
+
+impl NftItem {
+    pub fn new(address: Address) -> Self {
+        Self { address }
+    }
+
+    /// Fetch index, collection, owner and content with the `get_nft_data` get method.
+    pub async fn data(&self, client: &ApiClientV2) -> Result<NftData, ToncenterError> {
+        client
+            .run_get_method_typed(&self.address.to_string(), "get_nft_data", &[])
+            .await
+    }
+
+    /// Check on chain whether `address` currently owns the item.
+    pub async fn is_owned_by(
+        &self,
+        client: &ApiClientV2,
+        address: &Address,
+    ) -> Result<bool, ToncenterError> {
+        Ok(self.data(client).await?.is_owned_by(address))
+    }
+
+    /// Authority allowed to revoke an SBT, from the `get_authority_address` get method.
+    pub async fn sbt_authority(
+        &self,
+        client: &ApiClientV2,
+    ) -> Result<Option<Address>, ToncenterError> {
+        let (mut authority,): (CellSlice,) = client
+            .run_get_method_typed(&self.address.to_string(), "get_authority_address", &[])
+            .await?;
+        Ok(authority.load_optional_address()?)
+    }
+
+    /// Unix time an SBT was revoked at, `None` if it is not revoked. Uses the
+    /// `get_revoked_time` get method.
+    pub async fn sbt_revoked_time(
+        &self,
+        client: &ApiClientV2,
+    ) -> Result<Option<u64>, ToncenterError> {
+        let (time,): (u64,) = client
+            .run_get_method_typed(&self.address.to_string(), "get_revoked_time", &[])
+            .await?;
+        Ok((time != 0).then_some(time))
+    }
+}
+
+/// TEP-62 `transfer` request, sent by the owner to the item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NftTransfer {
+    pub query_id: u64,
+    pub new_owner: Address,
+    /// Where the excess TON is returned.
+    pub response_destination: Option<Address>,
+    pub custom_payload: Option<ArcCell>,
+    /// TON attached to the `ownership_assigned` notification sent to the new owner. No
+    /// notification is sent when zero.
+    pub forward_amount: Coins,
+    pub forward_payload: Option<ArcCell>,
+}
+
+impl NftTransfer {
+    pub fn new(query_id: u64, new_owner: Address) -> Self {
+        Self {
+            query_id,
+            new_owner,
+            response_destination: None,
+            custom_payload: None,
+            forward_amount: Coins::from_nanotons(0),
+            forward_payload: None,
+        }
+    }
+
+    pub fn with_response_destination(mut self, response_destination: Address) -> Self {
+        self.response_destination = Some(response_destination);
+        self
+    }
+
+    pub fn with_custom_payload(mut self, custom_payload: ArcCell) -> Self {
+        self.custom_payload = Some(custom_payload);
+        self
+    }
+
+    /// Notify the new owner with `forward_amount` and `payload`.
+    pub fn with_forward_payload(mut self, forward_amount: Coins, payload: ArcCell) -> Self {
+        self.forward_amount = forward_amount;
+        self.forward_payload = Some(payload);
+        self
+    }
+
+    pub fn with_forward_amount(mut self, forward_amount: Coins) -> Self {
+        self.forward_amount = forward_amount;
+        self
+    }
+
+    /// `transfer#5fcc3d14 query_id:uint64 new_owner:MsgAddress response_destination:MsgAddress
+    /// custom_payload:(Maybe ^Cell) forward_amount:Coins forward_payload:(Either Cell ^Cell)`,
+    /// with the forward payload stored by reference.
+    pub fn to_cell(&self) -> Result<ArcCell, CellError> {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(TRANSFER_OP)?
+            .store_u64(self.query_id)?
+            .store_address(&self.new_owner)?
+            .store_optional_address(self.response_destination.as_ref())?
+            .store_maybe_reference(self.custom_payload.clone())?
+            .store_coins(self.forward_amount.nanotons())?
+            .store_maybe_reference(self.forward_payload.clone())?;
+        Ok(Arc::new(builder.build()?))
+    }
+
+    /// Message to the item carrying `value` TON for fees and the forward amount.
+    pub fn to_message(&self, item: Address, value: Coins) -> Result<InternalMessage, CellError> {
+        Ok(InternalMessage::transfer(item, value, true).with_body(self.to_cell()?))
+    }
+}
+
+/// TEP-85 request making an SBT tell `destination` who owns it.
+///
+/// The SBT answers with `ownership_proof` for [`Self::prove_ownership`] and with
+/// `owner_info` for [`Self::request_owner`], both carrying `forward_payload` back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbtOwnershipRequest {
+    op: u32,
+    pub query_id: u64,
+    pub destination: Address,
+    pub forward_payload: ArcCell,
+    /// Attach the item content to the answer.
+    pub with_content: bool,
+}
+
+impl SbtOwnershipRequest {
+    /// `prove_ownership#04ded148`, only accepted from the owner.
+    pub fn prove_ownership(query_id: u64, destination: Address, forward_payload: ArcCell) -> Self {
+        Self {
+            op: PROVE_OWNERSHIP_OP,
+            query_id,
+            destination,
+            forward_payload,
+            with_content: false,
+        }
+    }
+
+    /// `request_owner#d0c3bfea`, accepted from anyone.
+    pub fn request_owner(query_id: u64, destination: Address, forward_payload: ArcCell) -> Self {
+        Self {
+            op: REQUEST_OWNER_OP,
+            query_id,
+            destination,
+            forward_payload,
+            with_content: false,
+        }
+    }
+
+    pub fn with_content(mut self, with_content: bool) -> Self {
+        self.with_content = with_content;
+        self
+    }
+
+    /// `query_id:uint64 dest:MsgAddress forward_payload:^Cell with_content:Bool` after the op.
+    pub fn to_cell(&self) -> Result<ArcCell, CellError> {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(self.op)?
+            .store_u64(self.query_id)?
+            .store_address(&self.destination)?
+            .store_reference(self.forward_payload.clone())?
+            .store_bit(self.with_content)?;
+        Ok(Arc::new(builder.build()?))
+    }
+
+    /// Message to the SBT carrying `value` TON for fees. The answer is sent with the
+    /// remaining value.
+    pub fn to_message(&self, sbt: Address, value: Coins) -> Result<InternalMessage, CellError> {
+        Ok(InternalMessage::transfer(sbt, value, true).with_body(self.to_cell()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::hex_string;
+    use crate::client::mock::{get_method_result, mock_client, Request};
+    use crate::message::comment_body;
+
+    // Expected hashes were computed by an independent implementation of the TL-B layouts.
+
+    const OWNER: &str = "EQAvDfWFG0oYX19jwNDNBBL1rKNT9XfaGP9HyTb5nb2Eml6y";
+    const OTHER: &str = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR";
+    const COLLECTION: &str = "EQC_1YoM8RBixN95lz7odcF3Vrkc_N8Ne7gQi7Abtlet_Efi";
+
+    fn address(address: &str) -> Address {
+        address.parse().unwrap()
+    }
+
+    fn cell(build: impl FnOnce(&mut CellBuilder)) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        build(&mut builder);
+        Arc::new(builder.build().unwrap())
+    }
+
+    fn address_slice(address: Option<&str>) -> StackEntry {
+        StackEntry::Slice(cell(|b| {
+            b.store_optional_address(address.map(self::address).as_ref())
+                .unwrap();
+        }))
+    }
+
+    fn content() -> ArcCell {
+        cell(|b| {
+            b.store_bytes(b"42.json").unwrap();
+        })
+    }
+
+    fn nft_data_stack(initialized: bool, owner: Option<&str>) -> Vec<StackEntry> {
+        vec![
+            StackEntry::Int(BigInt::from(-(initialized as i32))),
+            StackEntry::Int(BigInt::from(42)),
+            address_slice(Some(COLLECTION)),
+            address_slice(owner),
+            StackEntry::Cell(content()),
+        ]
+    }
+
+    #[test]
+    fn transfer_layout() {
+        let transfer = NftTransfer::new(11, address(OWNER))
+            .with_response_destination(address(OTHER))
+            .with_forward_payload(Coins::from_nanotons(1), comment_body("gift").unwrap());
+        let body = transfer.to_cell().unwrap();
+        assert_eq!(
+            hex_string(&body.repr_hash()),
+            "92df157dd610e9a31436b59eb7a586a270f23ac2d3037f3cfce69e48381257f1"
+        );
+
+        let mut slice = body.parser();
+        assert_eq!(slice.load_u32().unwrap(), TRANSFER_OP);
+        assert_eq!(slice.load_u64().unwrap(), 11);
+        assert_eq!(slice.load_address().unwrap(), address(OWNER));
+        assert_eq!(slice.load_address().unwrap(), address(OTHER));
+        assert_eq!(slice.load_maybe_reference().unwrap(), None);
+        assert_eq!(slice.load_coins().unwrap(), 1);
+        assert!(slice.load_maybe_reference().unwrap().is_some());
+        assert!(slice.is_empty());
+    }
+
+    #[test]
+    fn sbt_request_layouts() {
+        let payload = cell(|b| {
+            b.store_u32(0xcafe).unwrap();
+        });
+        let prove = SbtOwnershipRequest::prove_ownership(12, address(OTHER), payload.clone())
+            .with_content(true)
+            .to_cell()
+            .unwrap();
+        assert_eq!(
+            hex_string(&prove.repr_hash()),
+            "7b4c2feaf4bf9a047a0aeccc291e995530a2e3c8fbe498c5f8b6446194fb72ed"
+        );
+        let request = SbtOwnershipRequest::request_owner(13, address(OTHER), payload.clone());
+        assert_eq!(
+            hex_string(&request.to_cell().unwrap().repr_hash()),
+            "b447c6bd781e679721d1b455c3ff35cace22e4c8af742b45a8e161d008713cd8"
+        );
+
+        let message = request
+            .to_message(address(COLLECTION), Coins::from_nanotons(50_000_000))
+            .unwrap();
+        let mut body = message.body.unwrap().parser();
+        assert_eq!(body.load_u32().unwrap(), REQUEST_OWNER_OP);
+        assert_eq!(body.load_u64().unwrap(), 13);
+        assert_eq!(body.load_address().unwrap(), address(OTHER));
+        assert_eq!(body.load_reference().unwrap(), payload);
+        assert!(!body.load_bit().unwrap());
+    }
+
+    #[test]
+    fn nft_data_from_stack() {
+        let data = NftData::from_stack(&nft_data_stack(true, Some(OWNER))).unwrap();
+        assert_eq!(
+            data,
+            NftData {
+                initialized: true,
+                index: BigInt::from(42),
+                collection: Some(address(COLLECTION)),
+                owner: Some(address(OWNER)),
+                individual_content: content(),
+            }
+        );
+        assert!(data.is_owned_by(&address(OWNER)));
+        assert!(!data.is_owned_by(&address(OTHER)));
+
+        // Not initialized yet: no owner, whatever the stack says
+        let data = NftData::from_stack(&nft_data_stack(false, None)).unwrap();
+        assert_eq!(data.owner, None);
+        assert!(!data.is_owned_by(&address(OWNER)));
+        let data = NftData::from_stack(&nft_data_stack(false, Some(OWNER))).unwrap();
+        assert!(!data.is_owned_by(&address(OWNER)));
+    }
+
+    #[test]
+    fn collection_data_from_stack() {
+        let stack = vec![
+            StackEntry::Int(BigInt::from(-1)),
+            StackEntry::Cell(content()),
+            address_slice(None),
+        ];
+        assert_eq!(
+            NftCollectionData::from_stack(&stack).unwrap(),
+            NftCollectionData {
+                next_item_index: BigInt::from(-1),
+                content: content(),
+                owner: None,
+            }
+        );
+
+        let stack = vec![
+            StackEntry::Int(BigInt::from(1_000)),
+            StackEntry::Cell(content()),
+            address_slice(Some(OWNER)),
+        ];
+        let data = NftCollectionData::from_stack(&stack).unwrap();
+        assert_eq!(data.owner, Some(address(OWNER)));
+        assert!(NftCollectionData::from_stack(&stack[..2]).is_err());
+    }
+
+    #[tokio::test]
+    async fn item_ownership_from_get_method() {
+        let item = address("EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N");
+        let client = mock_client(Arc::new(move |request: &Request| {
+            assert_eq!(request.method, "runGetMethod");
+            assert_eq!(request.body["address"], item.to_string());
+            assert_eq!(request.body["method"], "get_nft_data");
+            get_method_result(nft_data_stack(true, Some(OWNER))).into()
+        }))
+        .await;
+
+        let item = NftItem::new(item);
+        assert!(item.is_owned_by(&client, &address(OWNER)).await.unwrap());
+        assert!(!item.is_owned_by(&client, &address(OTHER)).await.unwrap());
+    }
+}
@@ -0,0 +1,301 @@
+use super::block::{BlockHeader, BlockId, ShardState};
+use crate::address::Address;
+use crate::cell::{ArcCell, Cell, CellHash};
+use crate::error::{ToncenterError, VerificationError};
+use crate::models::TonBlockIdExt;
+use std::future::Future;
+
+/// Entry of the `ShardAccounts` dictionary proven to be part of a block's state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardAccount {
+    /// The `Account` cell. Usually pruned in proofs, compare hashes with [`Self::account_hash`].
+    pub account: ArcCell,
+    pub last_transaction_hash: CellHash,
+    pub last_transaction_lt: u64,
+}
+
+impl ShardAccount {
+    /// Hash of the account state cell, to be compared with a full state fetched separately.
+    pub fn account_hash(&self) -> CellHash {
+        self.account.hash(0)
+    }
+
+    /// Check that a full `Account` cell is the proven one.
+    pub fn verify_account(&self, account: &Cell) -> Result<(), VerificationError> {
+        if account.repr_hash() != self.account_hash() {
+            return Err(VerificationError::HashMismatch(
+                "account state does not match the proof".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Merkle proofs of an account state, as returned together by liteserver's `getAccountState`.
+#[derive(Debug, Clone)]
+pub struct AccountStateProof {
+    /// Proof of the block header down to its state update.
+    pub block_proof: ArcCell,
+    /// Proof of the resulting shard state down to the account.
+    pub state_proof: ArcCell,
+}
+
+/// Source of account state proofs, e.g. the caller's own liteserver connection.
+///
+/// toncenter's v2 API does not serve these proofs, so
+/// [`ApiClientV2::get_account_state_verified`](crate::client::ApiClientV2::get_account_state_verified)
+/// takes them from here and checks them against blocks it proved itself.
+pub trait AccountProofSource: Send + Sync {
+    /// Proofs of the state of `address` as of `block`, the shard block holding it.
+    fn account_proof(
+        &self,
+        block: &TonBlockIdExt,
+        address: &Address,
+    ) -> impl Future<Output = Result<AccountStateProof, ToncenterError>> + Send;
+}
+
+/// Prove the state of an account as of the given block.
+///
+/// `block_proof` is a Merkle proof of the block header down to its state update and
+/// `state_proof` a Merkle proof of the resulting shard state down to the account,
+/// as returned together by liteserver's `getAccountState`.
+/// Returns `None` if the proof shows that the account does not exist.
+///
+/// `block` must already be trusted, see
+/// [`ApiClientV2::get_account_state_verified`](crate::client::ApiClientV2::get_account_state_verified)
+/// for a getter that proves it first.
+pub fn verify_account_state(
+    block: &TonBlockIdExt,
+    block_proof: &ArcCell,
+    state_proof: &ArcCell,
+    address: &Address,
+) -> Result<Option<ShardAccount>, VerificationError> {
+    let id = BlockId::from_model(block)?;
+    if !id.shard.contains(address) {
+        return Err(VerificationError::Malformed(format!(
+            "address {} does not belong to shard {}",
+            address, id.shard
+        )));
+    }
+
+    let header = BlockHeader::from_proof(block_proof, &id)?;
+    let state = ShardState::from_proof(state_proof, &header.state_hash)?;
+    let Some(mut leaf) = state.account_leaf(address.hash_part())? else {
+        return Ok(None);
+    };
+
+    // depth_balance$_ split_depth:(#<= 30) balances:CurrencyCollection
+    leaf.skip_bits(5)?;
+    leaf.load_coins()?;
+    leaf.load_maybe_reference()?;
+
+    Ok(Some(ShardAccount {
+        account: leaf.load_reference()?,
+        last_transaction_hash: leaf.load_hash()?,
+        last_transaction_lt: leaf.load_u64()?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mock::{mock_client, Request};
+    use crate::error::ProcessingError;
+    use crate::models::{BlocksShardBlockLink, BlocksShardBlockProof};
+    use crate::proof::fixtures::*;
+    use crate::shard::ShardId;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    struct Fixture {
+        id: TonBlockIdExt,
+        block_proof: ArcCell,
+        state_proof: ArcCell,
+    }
+
+    fn fixture() -> Fixture {
+        let block = account_block();
+        Fixture {
+            id: block.id(),
+            block_proof: block.proof(),
+            state_proof: merkle_proof(shard_state(true)),
+        }
+    }
+
+    #[test]
+    fn proves_existing_account() {
+        let fixture = fixture();
+        let account = verify_account_state(
+            &fixture.id,
+            &fixture.block_proof,
+            &fixture.state_proof,
+            &address(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(account.account_hash(), self::account().hash(0));
+        assert_eq!(account.last_transaction_hash, [0xab; 32]);
+        assert_eq!(account.last_transaction_lt, LAST_TRANSACTION_LT);
+        account.verify_account(&self::account()).unwrap();
+        assert!(matches!(
+            account.verify_account(&filler(0)),
+            Err(VerificationError::HashMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn proves_missing_account() {
+        let fixture = fixture();
+        let missing = Address::new(0, [0x11; 32]);
+        let account = verify_account_state(
+            &fixture.id,
+            &fixture.block_proof,
+            &fixture.state_proof,
+            &missing,
+        )
+        .unwrap();
+        assert_eq!(account, None);
+    }
+
+    #[test]
+    fn rejects_proof_of_another_block() {
+        let mut fixture = fixture();
+        fixture.id.root_hash = STANDARD.encode([9u8; 32]);
+        let result = verify_account_state(
+            &fixture.id,
+            &fixture.block_proof,
+            &fixture.state_proof,
+            &address(),
+        );
+        assert!(matches!(result, Err(VerificationError::HashMismatch(_))));
+    }
+
+    #[test]
+    fn rejects_state_not_in_block() {
+        let fixture = fixture();
+        let other_state = merkle_proof(pruned(&filler(7)));
+        let result =
+            verify_account_state(&fixture.id, &fixture.block_proof, &other_state, &address());
+        assert!(matches!(result, Err(VerificationError::HashMismatch(_))));
+    }
+
+    #[test]
+    fn rejects_address_of_another_workchain() {
+        let fixture = fixture();
+        let result = verify_account_state(
+            &fixture.id,
+            &fixture.block_proof,
+            &fixture.state_proof,
+            &Address::new(-1, [0x5a; 32]),
+        );
+        assert!(matches!(result, Err(VerificationError::Malformed(_))));
+    }
+
+    struct Liteserver(Fixture);
+
+    impl AccountProofSource for Liteserver {
+        async fn account_proof(
+            &self,
+            block: &TonBlockIdExt,
+            address: &Address,
+        ) -> Result<AccountStateProof, ToncenterError> {
+            assert_eq!(block, &self.0.id);
+            assert_eq!(address, &self::address());
+            Ok(AccountStateProof {
+                block_proof: self.0.block_proof.clone(),
+                state_proof: self.0.state_proof.clone(),
+            })
+        }
+    }
+
+    /// Client answering `getShards` and `getShardBlockProof` for a masterchain block whose
+    /// only basechain shard is the one of [`account_block`].
+    async fn client(mc: &TestBlock) -> crate::client::ApiClientV2 {
+        let block = account_block();
+        let shards = json!({
+            "@type": "blocks.shards",
+            "shards": [serde_json::to_value(block.id()).unwrap()],
+        });
+        let proof = serde_json::to_value(BlocksShardBlockProof {
+            from: mc.id(),
+            mc_id: mc.id(),
+            links: vec![BlocksShardBlockLink {
+                id: block.id(),
+                proof: mc.proof().to_boc_b64().unwrap(),
+            }],
+            mc_proof: Vec::new(),
+            extra: "1717600394.5:0:0.1".to_string(),
+        })
+        .unwrap();
+        let seqno = mc.seqno.to_string();
+        mock_client(Arc::new(move |request: &Request| {
+            match request.method.as_str() {
+                "shards" => {
+                    assert_eq!(request.params["seqno"], seqno);
+                    shards.clone()
+                }
+                "getShardBlockProof" => {
+                    assert_eq!(request.params["from_seqno"], seqno);
+                    proof.clone()
+                }
+                other => panic!("unexpected method {}", other),
+            }
+            .into()
+        }))
+        .await
+    }
+
+    fn masterchain_block() -> TestBlock {
+        let block = account_block();
+        TestBlock {
+            shard: ShardId::root(-1),
+            seqno: 38_743_891,
+            key_block: false,
+            prev: ext_blk_ref(38_743_890, &[8; 32]),
+            state: filler(40),
+            extra: mc_block_extra(shard_descr(block.seqno, &block.cell(false).hash(0))),
+        }
+    }
+
+    #[tokio::test]
+    async fn client_proves_account_from_source() {
+        let mc = masterchain_block();
+        let client = self::client(&mc).await;
+        let account = client
+            .get_account_state_verified(&Liteserver(fixture()), &address(), &mc.id())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.account_hash(), self::account().hash(0));
+
+        // The source answers with proofs of a state the block does not lead to
+        let mut forged = fixture();
+        forged.state_proof = merkle_proof(pruned(&filler(7)));
+        assert!(matches!(
+            client
+                .get_account_state_verified(&Liteserver(forged), &address(), &mc.id())
+                .await,
+            Err(ToncenterError::ProcessingError(
+                ProcessingError::Verification(_)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn client_rejects_untrusted_masterchain_block() {
+        let mc = masterchain_block();
+        let client = self::client(&mc).await;
+        let mut trusted = mc.id();
+        trusted.root_hash = STANDARD.encode([9u8; 32]);
+        assert!(matches!(
+            client
+                .get_account_state_verified(&Liteserver(fixture()), &address(), &trusted)
+                .await,
+            Err(ToncenterError::ProcessingError(
+                ProcessingError::Verification(VerificationError::HashMismatch(_))
+            ))
+        ));
+    }
+}
@@ -0,0 +1,317 @@
+use super::merkle::{key_bits, lookup, open, verify_merkle_proof, verify_merkle_update};
+use super::signatures::decode_fixed;
+use crate::cell::{hex_string, ArcCell, CellHash, CellSlice};
+use crate::error::VerificationError;
+use crate::models::TonBlockIdExt;
+use crate::shard::ShardId;
+
+const BLOCK_TAG: u64 = 0x11ef55aa;
+const BLOCK_INFO_TAG: u64 = 0x9bc7a987;
+const BLOCK_EXTRA_TAG: u64 = 0x4a33f6fd;
+const MC_BLOCK_EXTRA_TAG: u64 = 0xcca5;
+const SHARD_STATE_TAG: u64 = 0x9023afe2;
+const MC_STATE_EXTRA_TAG: u64 = 0xcc26;
+
+/// Block id with decoded hashes, comparable regardless of how toncenter formatted it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BlockId {
+    pub shard: ShardId,
+    pub seqno: u32,
+    pub root_hash: CellHash,
+    pub file_hash: CellHash,
+}
+
+impl BlockId {
+    pub fn from_model(id: &TonBlockIdExt) -> Result<Self, VerificationError> {
+        Ok(Self {
+            shard: id.shard_id()?,
+            seqno: id.seqno,
+            root_hash: decode_fixed(&id.root_hash, "root hash")?,
+            file_hash: decode_fixed(&id.file_hash, "file hash")?,
+        })
+    }
+
+    /// Fail unless `self` is the block the proof claims it to be.
+    pub fn expect(&self, expected: &BlockId, what: &str) -> Result<(), VerificationError> {
+        if self != expected {
+            return Err(VerificationError::HashMismatch(format!(
+                "{} is {}, expected {}",
+                what,
+                self.describe(),
+                expected.describe()
+            )));
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "({},{},{}):{}",
+            self.shard.workchain(),
+            self.shard.shard_string(),
+            self.seqno,
+            hex_string(&self.root_hash)
+        )
+    }
+}
+
+/// Parts of a block header that proofs are checked against.
+pub(crate) struct BlockHeader {
+    pub shard: ShardId,
+    pub seqno: u32,
+    pub key_block: bool,
+    /// Blocks this one was built on, with their shards already resolved.
+    pub prev: Vec<BlockId>,
+    pub state_hash: CellHash,
+    extra: ArcCell,
+}
+
+impl BlockHeader {
+    /// Check a Merkle proof of a block and parse its header.
+    pub fn from_proof(proof: &ArcCell, id: &BlockId) -> Result<Self, VerificationError> {
+        let root = verify_merkle_proof(proof, &id.root_hash)?;
+        let header = Self::parse(&root)?;
+        if header.shard != id.shard || header.seqno != id.seqno {
+            return Err(VerificationError::HashMismatch(format!(
+                "block proof is for seqno {} of shard {}",
+                header.seqno, header.shard
+            )));
+        }
+        Ok(header)
+    }
+
+    fn parse(root: &ArcCell) -> Result<Self, VerificationError> {
+        let mut block = open(root)?;
+        expect_tag(&mut block, 32, BLOCK_TAG, "block")?;
+        block.load_i32()?;
+        let info = block.load_reference()?;
+        block.load_reference()?;
+        let state_update = block.load_reference()?;
+        let extra = block.load_reference()?;
+
+        let mut info = open(&info)?;
+        expect_tag(&mut info, 32, BLOCK_INFO_TAG, "block info")?;
+        info.load_u32()?;
+        let not_master = info.load_bit()?;
+        let after_merge = info.load_bit()?;
+        info.load_bit()?;
+        let after_split = info.load_bit()?;
+        info.load_bit()?;
+        info.load_bit()?;
+        let key_block = info.load_bit()?;
+        info.load_bit()?;
+        let flags = info.load_u8()?;
+        let seqno = info.load_u32()?;
+        info.load_u32()?;
+        let shard = load_shard_ident(&mut info)?;
+        // gen_utime, start_lt, end_lt, validator list hash, catchain seqno,
+        // min_ref_mc_seqno and prev_key_block_seqno
+        info.skip_bits(32 + 64 + 64 + 32 + 32 + 32 + 32)?;
+        if flags & 1 != 0 {
+            info.skip_bits(8 + 32 + 64)?;
+        }
+        if not_master {
+            info.load_reference()?;
+        }
+        let prev_ref = info.load_reference()?;
+
+        let prev = if after_merge {
+            let (left, right) = shard.children().ok_or_else(|| {
+                VerificationError::Malformed("merged shard has no children".to_string())
+            })?;
+            let mut prev_ref = open(&prev_ref)?;
+            vec![
+                load_ext_blk_ref(&mut open(&prev_ref.load_reference()?)?, left)?,
+                load_ext_blk_ref(&mut open(&prev_ref.load_reference()?)?, right)?,
+            ]
+        } else {
+            let prev_shard = if after_split {
+                shard.parent().ok_or_else(|| {
+                    VerificationError::Malformed("split shard has no parent".to_string())
+                })?
+            } else {
+                shard
+            };
+            vec![load_ext_blk_ref(&mut open(&prev_ref)?, prev_shard)?]
+        };
+
+        let (_, new_state) = verify_merkle_update(&state_update, None, None)?;
+
+        Ok(Self {
+            shard,
+            seqno,
+            key_block,
+            prev,
+            state_hash: new_state.hash(0),
+            extra,
+        })
+    }
+
+    /// Id of the shard block recorded in this masterchain block for the shard containing `shard`.
+    pub fn shard_top_block(&self, shard: &ShardId) -> Result<BlockId, VerificationError> {
+        let mut extra = open(&self.extra)?;
+        expect_tag(&mut extra, 32, BLOCK_EXTRA_TAG, "block extra")?;
+        for _ in 0..3 {
+            extra.load_reference()?;
+        }
+        extra.skip_bits(256 + 256)?;
+        let custom = extra.load_maybe_reference()?.ok_or_else(|| {
+            VerificationError::Malformed("block has no masterchain extra".to_string())
+        })?;
+
+        let mut custom = open(&custom)?;
+        expect_tag(
+            &mut custom,
+            16,
+            MC_BLOCK_EXTRA_TAG,
+            "masterchain block extra",
+        )?;
+        custom.load_bit()?;
+        let shard_hashes = custom.load_maybe_reference()?.ok_or_else(|| {
+            VerificationError::Malformed("masterchain block has no shards".to_string())
+        })?;
+
+        let key = key_bits(shard.workchain() as u32 as u64, 32);
+        let mut leaf = lookup(&shard_hashes, 32, &key)?.ok_or_else(|| {
+            VerificationError::Malformed(format!("workchain {} is not known", shard.workchain()))
+        })?;
+
+        // Walk the binary tree of shards following the prefix bits of the target shard
+        let mut node = open(&leaf.load_reference()?)?;
+        let mut current = ShardId::root(shard.workchain());
+        while node.load_bit()? {
+            let (left, right) = current.children().ok_or_else(|| {
+                VerificationError::Malformed("shard tree is too deep".to_string())
+            })?;
+            let go_right = shard.prefix() & (1 << (63 - current.prefix_len())) != 0;
+            let child = node.cell().reference(go_right as usize)?.clone();
+            current = if go_right { right } else { left };
+            node = open(&child)?;
+        }
+
+        node.skip_bits(4)?;
+        let seqno = node.load_u32()?;
+        node.skip_bits(32 + 64 + 64)?;
+        Ok(BlockId {
+            shard: current,
+            seqno,
+            root_hash: node.load_hash()?,
+            file_hash: node.load_hash()?,
+        })
+    }
+}
+
+/// Parts of a shard state that proofs are checked against.
+pub(crate) struct ShardState {
+    accounts: ArcCell,
+    custom: Option<ArcCell>,
+}
+
+impl ShardState {
+    /// Check a Merkle proof of a shard state and open its root.
+    pub fn from_proof(proof: &ArcCell, state_hash: &CellHash) -> Result<Self, VerificationError> {
+        let root = verify_merkle_proof(proof, state_hash)?;
+        let mut state = open(&root)?;
+        expect_tag(&mut state, 32, SHARD_STATE_TAG, "shard state")?;
+        state.load_i32()?;
+        load_shard_ident(&mut state)?;
+        state.skip_bits(32 + 32 + 32 + 64 + 32)?;
+        state.load_reference()?;
+        state.load_bit()?;
+        let accounts = state.load_reference()?;
+        state.load_reference()?;
+        let custom = state.load_maybe_reference()?;
+        Ok(Self { accounts, custom })
+    }
+
+    /// Leaf of the `ShardAccounts` dictionary for the account, positioned at its `DepthBalanceInfo`.
+    pub fn account_leaf(
+        &self,
+        hash_part: &[u8; 32],
+    ) -> Result<Option<CellSlice>, VerificationError> {
+        let mut accounts = open(&self.accounts)?;
+        if !accounts.load_bit()? {
+            return Ok(None);
+        }
+        let key = hash_part
+            .iter()
+            .flat_map(|byte| key_bits(*byte as u64, 8))
+            .collect::<Vec<_>>();
+        lookup(&accounts.load_reference()?, 256, &key)
+    }
+
+    /// Entry of `prev_blocks` in a masterchain state: `(is_key_block, block_id)`.
+    pub fn old_mc_block(&self, seqno: u32) -> Result<Option<(bool, BlockId)>, VerificationError> {
+        let custom = self.custom.as_ref().ok_or_else(|| {
+            VerificationError::Malformed("state is not a masterchain state".to_string())
+        })?;
+        let mut custom = open(custom)?;
+        expect_tag(
+            &mut custom,
+            16,
+            MC_STATE_EXTRA_TAG,
+            "masterchain state extra",
+        )?;
+        custom.load_maybe_reference()?;
+        custom.skip_bits(256)?;
+        custom.load_reference()?;
+
+        let mut other = open(&custom.load_reference()?)?;
+        other.skip_bits(16 + 32 + 32 + 1)?;
+        if !other.load_bit()? {
+            return Ok(None);
+        }
+        let Some(mut leaf) = lookup(&other.load_reference()?, 32, &key_bits(seqno as u64, 32))?
+        else {
+            return Ok(None);
+        };
+        leaf.skip_bits(1 + 64)?;
+        let is_key_block = leaf.load_bit()?;
+        let id = load_ext_blk_ref(&mut leaf, ShardId::root(-1))?;
+        Ok(Some((is_key_block, id)))
+    }
+}
+
+fn expect_tag(
+    slice: &mut CellSlice,
+    bits: usize,
+    tag: u64,
+    what: &str,
+) -> Result<(), VerificationError> {
+    if slice.load_uint(bits)? != tag {
+        return Err(VerificationError::Malformed(format!(
+            "invalid {} tag",
+            what
+        )));
+    }
+    Ok(())
+}
+
+/// Load `shard_ident$00 shard_pfx_bits:(#<= 60) workchain_id:int32 shard_prefix:uint64`.
+fn load_shard_ident(slice: &mut CellSlice) -> Result<ShardId, VerificationError> {
+    expect_tag(slice, 2, 0, "shard ident")?;
+    let prefix_len = slice.load_uint(6)? as u32;
+    let workchain = slice.load_i32()?;
+    let prefix = slice.load_u64()?;
+    if prefix_len > 60 {
+        return Err(VerificationError::Malformed(
+            "shard prefix is too long".to_string(),
+        ));
+    }
+    let tag = 1u64 << (63 - prefix_len);
+    Ok(ShardId::new(
+        workchain,
+        (prefix & !(tag | (tag - 1))) | tag,
+    )?)
+}
+
+/// Load `ext_blk_ref$_ end_lt:uint64 seq_no:uint32 root_hash:bits256 file_hash:bits256`.
+fn load_ext_blk_ref(slice: &mut CellSlice, shard: ShardId) -> Result<BlockId, VerificationError> {
+    slice.load_u64()?;
+    Ok(BlockId {
+        shard,
+        seqno: slice.load_u32()?,
+        root_hash: slice.load_hash()?,
+        file_hash: slice.load_hash()?,
+    })
+}
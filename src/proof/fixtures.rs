@@ -0,0 +1,364 @@
+//! Synthetic blocks, states and Merkle proofs shared by the proof tests.
+//!
+//! They follow the TL-B layouts the parsers read but are not taken from the network:
+//! hashes are computed over the cells built here and file hashes are made up.
+
+use crate::address::Address;
+use crate::cell::{build_hashmap, ArcCell, Cell, CellBuilder, CellHash};
+use crate::models::TonBlockIdExt;
+use crate::shard::ShardId;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use num_bigint::BigUint;
+use std::collections::BTreeMap;
+
+pub const SEQNO: u32 = 41_592_107;
+pub const LAST_TRANSACTION_LT: u64 = 47_584_224_000_001;
+
+pub fn pruned(cell: &ArcCell) -> ArcCell {
+    let mut data = vec![1, 1];
+    data.extend_from_slice(&cell.hash(0));
+    data.extend_from_slice(&cell.depth(0).to_be_bytes());
+    Cell::new(data, 288, Vec::new(), true).unwrap().to_arc()
+}
+
+pub fn merkle_proof(root: ArcCell) -> ArcCell {
+    let mut data = vec![3];
+    data.extend_from_slice(&root.hash(0));
+    data.extend_from_slice(&root.depth(0).to_be_bytes());
+    Cell::new(data, 280, vec![root], true).unwrap().to_arc()
+}
+
+pub fn merkle_update(old: ArcCell, new: ArcCell) -> ArcCell {
+    let mut data = vec![4];
+    data.extend_from_slice(&old.hash(0));
+    data.extend_from_slice(&new.hash(0));
+    data.extend_from_slice(&old.depth(0).to_be_bytes());
+    data.extend_from_slice(&new.depth(0).to_be_bytes());
+    Cell::new(data, 552, vec![old, new], true).unwrap().to_arc()
+}
+
+pub fn cell(build: impl FnOnce(&mut CellBuilder)) -> ArcCell {
+    let mut builder = CellBuilder::new();
+    build(&mut builder);
+    builder.build().unwrap().to_arc()
+}
+
+pub fn filler(value: u32) -> ArcCell {
+    cell(|b| {
+        b.store_u32(value).unwrap();
+    })
+}
+
+pub fn store_shard_ident(builder: &mut CellBuilder, shard: ShardId) {
+    builder
+        .store_uint(2, 0)
+        .unwrap()
+        .store_uint(6, shard.prefix_len() as u64)
+        .unwrap()
+        .store_i32(shard.workchain())
+        .unwrap()
+        .store_u64(shard.prefix())
+        .unwrap();
+}
+
+/// Made-up file hash, distinct per block.
+pub fn file_hash(seqno: u32) -> CellHash {
+    let mut hash = [0xf1; 32];
+    hash[..4].copy_from_slice(&seqno.to_be_bytes());
+    hash
+}
+
+pub fn block_id(shard: ShardId, seqno: u32, root_hash: &CellHash) -> TonBlockIdExt {
+    TonBlockIdExt {
+        workchain: shard.workchain(),
+        shard: shard.shard_string(),
+        seqno,
+        root_hash: STANDARD.encode(root_hash),
+        file_hash: STANDARD.encode(file_hash(seqno)),
+        extra: None,
+    }
+}
+
+/// `ext_blk_ref` pointing at a block built here.
+pub fn ext_blk_ref(seqno: u32, root_hash: &CellHash) -> ArcCell {
+    cell(|b| {
+        b.store_u64(LAST_TRANSACTION_LT - 1)
+            .unwrap()
+            .store_u32(seqno)
+            .unwrap()
+            .store_bytes(root_hash)
+            .unwrap()
+            .store_bytes(&file_hash(seqno))
+            .unwrap();
+    })
+}
+
+/// Block with only the fields the parsers look at filled in.
+pub struct TestBlock {
+    pub shard: ShardId,
+    pub seqno: u32,
+    pub key_block: bool,
+    /// `ext_blk_ref` of the previous block.
+    pub prev: ArcCell,
+    pub state: ArcCell,
+    pub extra: ArcCell,
+}
+
+impl TestBlock {
+    /// The block cell. With `prune`, the master reference and value flow are pruned
+    /// as in a liteserver proof, which keeps the hash intact.
+    pub fn cell(&self, prune: bool) -> ArcCell {
+        let maybe_pruned = |cell: ArcCell| if prune { pruned(&cell) } else { cell };
+        let not_master = self.shard.workchain() != -1;
+        let info = cell(|b| {
+            b.store_u32(0x9bc7a987)
+                .unwrap()
+                .store_u32(0)
+                .unwrap()
+                .store_bit(not_master)
+                .unwrap()
+                .store_uint(5, 0)
+                .unwrap()
+                .store_bit(self.key_block)
+                .unwrap()
+                .store_bit(false)
+                .unwrap()
+                .store_u8(0)
+                .unwrap()
+                .store_u32(self.seqno)
+                .unwrap()
+                .store_u32(0)
+                .unwrap();
+            store_shard_ident(b, self.shard);
+            b.store_zeros(32 + 64 + 64 + 32 + 32 + 32 + 32).unwrap();
+            if not_master {
+                b.store_reference(maybe_pruned(filler(3))).unwrap();
+            }
+            b.store_reference(self.prev.clone()).unwrap();
+        });
+        let update = merkle_update(pruned(&filler(4)), pruned(&self.state));
+        cell(|b| {
+            b.store_u32(0x11ef55aa)
+                .unwrap()
+                .store_i32(-239)
+                .unwrap()
+                .store_reference(info)
+                .unwrap()
+                .store_reference(maybe_pruned(filler(5)))
+                .unwrap()
+                .store_reference(update)
+                .unwrap()
+                .store_reference(self.extra.clone())
+                .unwrap();
+        })
+    }
+
+    pub fn id(&self) -> TonBlockIdExt {
+        block_id(self.shard, self.seqno, &self.cell(false).hash(0))
+    }
+
+    pub fn proof(&self) -> ArcCell {
+        merkle_proof(self.cell(true))
+    }
+}
+
+/// `ShardDescr` leaf of a shard binary tree.
+pub fn shard_descr(seqno: u32, root_hash: &CellHash) -> ArcCell {
+    cell(|b| {
+        b.store_bit(false)
+            .unwrap()
+            .store_uint(4, 0xa)
+            .unwrap()
+            .store_u32(seqno)
+            .unwrap()
+            .store_zeros(32 + 64 + 64)
+            .unwrap()
+            .store_bytes(root_hash)
+            .unwrap()
+            .store_bytes(&file_hash(seqno))
+            .unwrap();
+    })
+}
+
+/// Fork of a shard binary tree.
+pub fn shard_fork(left: ArcCell, right: ArcCell) -> ArcCell {
+    cell(|b| {
+        b.store_bit(true)
+            .unwrap()
+            .store_reference(left)
+            .unwrap()
+            .store_reference(right)
+            .unwrap();
+    })
+}
+
+/// Extra of a masterchain block recording the basechain shard tree `shards`.
+pub fn mc_block_extra(shards: ArcCell) -> ArcCell {
+    let leaf = cell(|b| {
+        b.store_reference(shards).unwrap();
+    });
+    let shard_hashes = build_hashmap(32, &BTreeMap::from([(BigUint::from(0u8), leaf)])).unwrap();
+    let custom = cell(|b| {
+        b.store_uint(16, 0xcca5)
+            .unwrap()
+            .store_bit(false)
+            .unwrap()
+            .store_maybe_reference(Some(shard_hashes.to_arc()))
+            .unwrap();
+    });
+    cell(|b| {
+        b.store_u32(0x4a33f6fd).unwrap();
+        for value in 10..13 {
+            b.store_reference(pruned(&filler(value))).unwrap();
+        }
+        b.store_zeros(256 + 256)
+            .unwrap()
+            .store_maybe_reference(Some(custom))
+            .unwrap();
+    })
+}
+
+/// Masterchain state whose `prev_blocks` holds `(seqno, root_hash, is_key_block)` entries.
+pub fn mc_state(seqno: u32, prev_blocks: &[(u32, CellHash, bool)]) -> ArcCell {
+    let entries = prev_blocks
+        .iter()
+        .map(|(seqno, root_hash, is_key_block)| {
+            let leaf = cell(|b| {
+                b.store_bit(false)
+                    .unwrap()
+                    .store_u64(0)
+                    .unwrap()
+                    .store_bit(*is_key_block)
+                    .unwrap()
+                    .store_u64(LAST_TRANSACTION_LT - 1)
+                    .unwrap()
+                    .store_u32(*seqno)
+                    .unwrap()
+                    .store_bytes(root_hash)
+                    .unwrap()
+                    .store_bytes(&file_hash(*seqno))
+                    .unwrap();
+            });
+            (BigUint::from(*seqno), leaf)
+        })
+        .collect();
+    let prev_blocks = build_hashmap(32, &entries).unwrap();
+    let other = cell(|b| {
+        b.store_zeros(16 + 32 + 32 + 1)
+            .unwrap()
+            .store_maybe_reference(Some(prev_blocks.to_arc()))
+            .unwrap();
+    });
+    let custom = cell(|b| {
+        b.store_uint(16, 0xcc26)
+            .unwrap()
+            .store_bit(false)
+            .unwrap()
+            .store_zeros(256)
+            .unwrap()
+            .store_reference(pruned(&filler(20)))
+            .unwrap()
+            .store_reference(other)
+            .unwrap();
+    });
+    cell(|b| {
+        b.store_u32(0x9023afe2).unwrap().store_i32(-239).unwrap();
+        store_shard_ident(b, ShardId::root(-1));
+        b.store_u32(seqno)
+            .unwrap()
+            .store_zeros(32 + 32 + 64 + 32)
+            .unwrap()
+            .store_reference(pruned(&filler(21)))
+            .unwrap()
+            .store_bit(false)
+            .unwrap()
+            .store_reference(pruned(&filler(22)))
+            .unwrap()
+            .store_reference(pruned(&filler(23)))
+            .unwrap()
+            .store_maybe_reference(Some(custom))
+            .unwrap();
+    })
+}
+
+pub fn address() -> Address {
+    Address::new(0, [0x5a; 32])
+}
+
+pub fn account() -> ArcCell {
+    cell(|b| {
+        b.store_bytes(b"account state").unwrap();
+    })
+}
+
+/// Shard state of the basechain holding a single account, with everything but the path
+/// to the account pruned, as in a liteserver proof.
+pub fn shard_state(prune: bool) -> ArcCell {
+    let maybe_pruned = |cell: ArcCell| if prune { pruned(&cell) } else { cell };
+    let leaf = cell(|b| {
+        // DepthBalanceInfo, then ShardAccount
+        b.store_uint(5, 0)
+            .unwrap()
+            .store_coins(1_000_000_000)
+            .unwrap()
+            .store_bit(false)
+            .unwrap()
+            .store_reference(maybe_pruned(account()))
+            .unwrap()
+            .store_bytes(&[0xab; 32])
+            .unwrap()
+            .store_u64(LAST_TRANSACTION_LT)
+            .unwrap();
+    });
+    let key = BigUint::from_bytes_be(address().hash_part());
+    let root = build_hashmap(256, &BTreeMap::from([(key, leaf)])).unwrap();
+    let accounts = cell(|b| {
+        b.store_bit(true)
+            .unwrap()
+            .store_reference(root.to_arc())
+            .unwrap()
+            .store_uint(5, 0)
+            .unwrap()
+            .store_coins(1_000_000_000)
+            .unwrap()
+            .store_bit(false)
+            .unwrap();
+    });
+    cell(|b| {
+        b.store_u32(0x9023afe2).unwrap().store_i32(-239).unwrap();
+        store_shard_ident(b, ShardId::root(0));
+        b.store_u32(SEQNO)
+            .unwrap()
+            .store_u32(0)
+            .unwrap()
+            .store_u32(1_717_600_394)
+            .unwrap()
+            .store_u64(LAST_TRANSACTION_LT + 1)
+            .unwrap()
+            .store_u32(38_743_891)
+            .unwrap()
+            .store_reference(maybe_pruned(filler(1)))
+            .unwrap()
+            .store_bit(false)
+            .unwrap()
+            .store_reference(accounts)
+            .unwrap()
+            .store_reference(maybe_pruned(filler(2)))
+            .unwrap()
+            .store_bit(false)
+            .unwrap();
+    })
+}
+
+/// Basechain block whose state update leads to [`shard_state`].
+pub fn account_block() -> TestBlock {
+    TestBlock {
+        shard: ShardId::root(0),
+        seqno: SEQNO,
+        key_block: false,
+        prev: ext_blk_ref(SEQNO - 1, &[1; 32]),
+        state: shard_state(false),
+        extra: pruned(&filler(6)),
+    }
+}
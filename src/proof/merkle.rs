@@ -0,0 +1,137 @@
+use crate::cell::{hex_string as hex, load_label, ArcCell, Cell, CellHash, CellSlice, CellType};
+use crate::error::VerificationError;
+
+/// Check a Merkle proof cell against the hash of the original tree and return its virtual root.
+///
+/// Pruned branches stay in the returned tree; use [`open`] to parse cells of it
+/// so that reading a pruned part fails instead of returning garbage.
+pub fn verify_merkle_proof(
+    proof: &ArcCell,
+    expected_hash: &CellHash,
+) -> Result<ArcCell, VerificationError> {
+    if proof.cell_type() != CellType::MerkleProof {
+        return Err(VerificationError::Malformed(
+            "expected a Merkle proof cell".to_string(),
+        ));
+    }
+    let root = proof.reference(0)?;
+    check_stored_hash(proof, 1, 33, root)?;
+    if &root.hash(0) != expected_hash {
+        return Err(VerificationError::HashMismatch(format!(
+            "Merkle proof is for {}, expected {}",
+            hex(&root.hash(0)),
+            hex(expected_hash)
+        )));
+    }
+    Ok(root.clone())
+}
+
+/// Check a Merkle update cell and return its old and new virtual roots.
+///
+/// Either hash can be omitted when only one side is of interest.
+pub fn verify_merkle_update(
+    update: &ArcCell,
+    old_hash: Option<&CellHash>,
+    new_hash: Option<&CellHash>,
+) -> Result<(ArcCell, ArcCell), VerificationError> {
+    let (old, new) = merkle_update_hashes(update)?;
+    let old_root = update.reference(0)?;
+    let new_root = update.reference(1)?;
+    check_stored_hash(update, 1, 65, old_root)?;
+    check_stored_hash(update, 33, 67, new_root)?;
+
+    for (expected, actual, side) in [(old_hash, old, "old"), (new_hash, new, "new")] {
+        if let Some(expected) = expected {
+            if expected != &actual {
+                return Err(VerificationError::HashMismatch(format!(
+                    "{} state of Merkle update is {}, expected {}",
+                    side,
+                    hex(&actual),
+                    hex(expected)
+                )));
+            }
+        }
+    }
+    Ok((old_root.clone(), new_root.clone()))
+}
+
+/// Old and new hashes stored in a Merkle update cell, readable even when both sides are pruned.
+pub fn merkle_update_hashes(update: &Cell) -> Result<(CellHash, CellHash), VerificationError> {
+    if update.cell_type() != CellType::MerkleUpdate {
+        return Err(VerificationError::Malformed(
+            "expected a Merkle update cell".to_string(),
+        ));
+    }
+    let data = update.data();
+    let mut old = [0u8; 32];
+    let mut new = [0u8; 32];
+    old.copy_from_slice(&data[1..33]);
+    new.copy_from_slice(&data[33..65]);
+    Ok((old, new))
+}
+
+/// Start parsing a cell of a proof tree, failing if the proof does not include it.
+pub fn open(cell: &ArcCell) -> Result<CellSlice, VerificationError> {
+    if cell.cell_type() == CellType::PrunedBranch {
+        return Err(VerificationError::Malformed(format!(
+            "cell {} is pruned from the proof",
+            hex(&cell.hash(0))
+        )));
+    }
+    Ok(cell.parser())
+}
+
+/// Look a single key up in a `Hashmap`/`HashmapAug` edge that may contain pruned branches.
+///
+/// Only the cells on the path to the key are opened. Returns the leaf slice positioned right
+/// after the label (at the extra value for augmented dictionaries), or `None` if the proof
+/// shows that the key is absent.
+pub(crate) fn lookup(
+    root: &ArcCell,
+    key_bits: usize,
+    key: &[bool],
+) -> Result<Option<CellSlice>, VerificationError> {
+    let mut slice = open(root)?;
+    let mut remaining = key_bits;
+    let mut key = key;
+
+    loop {
+        let label = load_label(&mut slice, remaining)?;
+        if !key.starts_with(&label) {
+            return Ok(None);
+        }
+        key = &key[label.len()..];
+        remaining -= label.len();
+        if remaining == 0 {
+            return Ok(Some(slice));
+        }
+
+        let child = slice.cell().reference(key[0] as usize)?.clone();
+        slice = open(&child)?;
+        key = &key[1..];
+        remaining -= 1;
+    }
+}
+
+/// Key bits of an unsigned integer, most significant first.
+pub(crate) fn key_bits(value: u64, bits: usize) -> Vec<bool> {
+    (0..bits).rev().map(|i| (value >> i) & 1 == 1).collect()
+}
+
+/// Compare the hash and depth a Merkle cell stores at the given byte offsets with its child.
+fn check_stored_hash(
+    cell: &Cell,
+    hash_offset: usize,
+    depth_offset: usize,
+    child: &ArcCell,
+) -> Result<(), VerificationError> {
+    let data = cell.data();
+    let stored_hash = &data[hash_offset..hash_offset + 32];
+    let stored_depth = u16::from_be_bytes([data[depth_offset], data[depth_offset + 1]]);
+    if stored_hash != child.hash(0) || stored_depth != child.depth(0) {
+        return Err(VerificationError::HashMismatch(
+            "Merkle cell does not match its child".to_string(),
+        ));
+    }
+    Ok(())
+}
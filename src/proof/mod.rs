@@ -0,0 +1,11 @@
+mod account;
+mod block;
+#[cfg(test)]
+mod fixtures;
+mod merkle;
+mod shard_block;
+mod signatures;
+
+pub use account::{verify_account_state, AccountProofSource, AccountStateProof, ShardAccount};
+pub use merkle::{merkle_update_hashes, open, verify_merkle_proof, verify_merkle_update};
+pub use signatures::verify_block_signatures;
@@ -0,0 +1,294 @@
+use super::block::{BlockHeader, BlockId, ShardState};
+use crate::cell::{ArcCell, Cell};
+use crate::error::VerificationError;
+use crate::models::{BlocksBlockLinkBack, BlocksShardBlockProof, TonBlockIdExt};
+
+impl BlocksShardBlockProof {
+    /// Chain the proven shard block to a trusted masterchain block and return its id.
+    ///
+    /// `trusted` must be `self.from`, e.g. a block checked with
+    /// [`MasterchainBlockSignatures::verify`](crate::models::MasterchainBlockSignatures::verify).
+    /// `mc_proof` links it back to `mc_id`, and every entry of `links` proves that its block
+    /// is referenced by the previous one, starting from the shard hashes of `mc_id`.
+    pub fn verify(&self, trusted: &TonBlockIdExt) -> Result<TonBlockIdExt, VerificationError> {
+        let trusted = BlockId::from_model(trusted)?;
+        BlockId::from_model(&self.from)?.expect(&trusted, "proof start")?;
+
+        let mut current = trusted;
+        for link in &self.mc_proof {
+            current = verify_link_back(link, &current)?;
+        }
+        let mc_id = BlockId::from_model(&self.mc_id)?;
+        current.expect(&mc_id, "end of masterchain proof")?;
+
+        let mut result = &self.mc_id;
+        for (index, link) in self.links.iter().enumerate() {
+            let id = BlockId::from_model(&link.id)?;
+            let header = BlockHeader::from_proof(&decode_proof(&link.proof)?, &current)?;
+            let expected = if index == 0 {
+                header.shard_top_block(&id.shard)?
+            } else {
+                header
+                    .prev
+                    .into_iter()
+                    .find(|prev| prev.shard.intersects(&id.shard))
+                    .ok_or_else(|| {
+                        VerificationError::Malformed(
+                            "block has no predecessor in the linked shard".to_string(),
+                        )
+                    })?
+            };
+            id.expect(&expected, "linked block")?;
+            current = id;
+            result = &link.id;
+        }
+        Ok(result.clone())
+    }
+}
+
+/// Verify a backward link and return the id of the older block it proves.
+fn verify_link_back(
+    link: &BlocksBlockLinkBack,
+    from: &BlockId,
+) -> Result<BlockId, VerificationError> {
+    BlockId::from_model(&link.from)?.expect(from, "masterchain link start")?;
+    let to = BlockId::from_model(&link.to)?;
+
+    let header = BlockHeader::from_proof(&decode_proof(&link.proof)?, from)?;
+    let state = ShardState::from_proof(&decode_proof(&link.state_proof)?, &header.state_hash)?;
+    let (is_key_block, old_block) = state.old_mc_block(to.seqno)?.ok_or_else(|| {
+        VerificationError::Malformed(format!(
+            "block {} is not among the previous blocks",
+            to.seqno
+        ))
+    })?;
+    old_block.expect(&to, "previous masterchain block")?;
+
+    if link.to_key_block {
+        let dest = BlockHeader::from_proof(&decode_proof(&link.dest_proof)?, &to)?;
+        if !is_key_block || !dest.key_block {
+            return Err(VerificationError::Malformed(format!(
+                "block {} is not a key block",
+                to.seqno
+            )));
+        }
+    }
+    Ok(to)
+}
+
+pub(crate) fn decode_proof(boc: &str) -> Result<ArcCell, VerificationError> {
+    Ok(Cell::from_boc_b64(boc)?.to_arc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mock::{mock_client, Request};
+    use crate::error::{ProcessingError, ToncenterError};
+    use crate::proof::fixtures::*;
+    use crate::shard::ShardId;
+    use std::sync::Arc;
+
+    const MC_SEQNO: u32 = 38_743_891;
+    const TRUSTED_SEQNO: u32 = MC_SEQNO + 5;
+    const SHARD_SEQNO: u32 = 44_210_350;
+
+    /// Synthetic `getShardBlockProof` answer: the basechain is split in two, the right
+    /// half has two blocks since the previous masterchain block, and the trusted block
+    /// links back to the masterchain block holding the newest of them.
+    struct Chain {
+        trusted: TestBlock,
+        mc: TestBlock,
+        /// Right shard blocks, oldest first.
+        shard_blocks: [TestBlock; 2],
+        proof: BlocksShardBlockProof,
+    }
+
+    fn right() -> ShardId {
+        ShardId::new(0, 0xc000_0000_0000_0000).unwrap()
+    }
+
+    fn boc(cell: &ArcCell) -> String {
+        cell.to_boc_b64().unwrap()
+    }
+
+    fn link(block: &TestBlock, proven_by: &TestBlock) -> crate::models::BlocksShardBlockLink {
+        crate::models::BlocksShardBlockLink {
+            id: block.id(),
+            proof: boc(&proven_by.proof()),
+        }
+    }
+
+    fn chain() -> Chain {
+        let older = TestBlock {
+            shard: right(),
+            seqno: SHARD_SEQNO,
+            key_block: false,
+            prev: ext_blk_ref(SHARD_SEQNO - 1, &[3; 32]),
+            state: filler(30),
+            extra: pruned(&filler(31)),
+        };
+        let newer = TestBlock {
+            shard: right(),
+            seqno: SHARD_SEQNO + 1,
+            key_block: false,
+            prev: ext_blk_ref(SHARD_SEQNO, &older.cell(false).hash(0)),
+            state: filler(32),
+            extra: pruned(&filler(33)),
+        };
+        let shards = shard_fork(
+            shard_descr(SHARD_SEQNO + 7, &[4; 32]),
+            shard_descr(newer.seqno, &newer.cell(false).hash(0)),
+        );
+        let mc = TestBlock {
+            shard: ShardId::root(-1),
+            seqno: MC_SEQNO,
+            key_block: false,
+            prev: ext_blk_ref(MC_SEQNO - 1, &[5; 32]),
+            state: filler(34),
+            extra: mc_block_extra(shards),
+        };
+        let state = mc_state(TRUSTED_SEQNO, &[(MC_SEQNO, mc.cell(false).hash(0), false)]);
+        let trusted = TestBlock {
+            shard: ShardId::root(-1),
+            seqno: TRUSTED_SEQNO,
+            key_block: false,
+            prev: ext_blk_ref(TRUSTED_SEQNO - 1, &[6; 32]),
+            state: state.clone(),
+            extra: pruned(&filler(35)),
+        };
+        let proof = BlocksShardBlockProof {
+            from: trusted.id(),
+            mc_id: mc.id(),
+            links: vec![link(&newer, &mc), link(&older, &newer)],
+            mc_proof: vec![BlocksBlockLinkBack {
+                to_key_block: false,
+                from: trusted.id(),
+                to: mc.id(),
+                dest_proof: String::new(),
+                proof: boc(&trusted.proof()),
+                state_proof: boc(&merkle_proof(state)),
+            }],
+            extra: "1717600394.5:0:0.1".to_string(),
+        };
+        Chain {
+            trusted,
+            mc,
+            shard_blocks: [older, newer],
+            proof,
+        }
+    }
+
+    #[test]
+    fn proves_shard_block_through_link_back() {
+        let chain = chain();
+        let proven = chain.proof.verify(&chain.trusted.id()).unwrap();
+        assert_eq!(proven, chain.shard_blocks[0].id());
+    }
+
+    #[test]
+    fn proves_shard_block_of_trusted_masterchain_block() {
+        let chain = chain();
+        let mut proof = chain.proof;
+        proof.from = chain.mc.id();
+        proof.mc_proof.clear();
+        proof.links.truncate(1);
+        assert_eq!(
+            proof.verify(&chain.mc.id()).unwrap(),
+            chain.shard_blocks[1].id()
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_trusted_block() {
+        let chain = chain();
+        assert!(matches!(
+            chain.proof.verify(&chain.mc.id()),
+            Err(VerificationError::HashMismatch(_))
+        ));
+
+        // The proof claims to start from the trusted block, but its hash differs
+        let mut trusted = chain.trusted.id();
+        trusted.root_hash = chain.mc.id().root_hash;
+        let mut proof = chain.proof;
+        proof.from = trusted.clone();
+        proof.mc_proof[0].from = trusted.clone();
+        assert!(proof.verify(&trusted).is_err());
+    }
+
+    #[test]
+    fn rejects_masterchain_link_to_another_block() {
+        let chain = chain();
+        let mut proof = chain.proof;
+        proof.mc_proof[0].to.seqno -= 1;
+        proof.mc_id.seqno -= 1;
+        assert!(matches!(
+            proof.verify(&chain.trusted.id()),
+            Err(VerificationError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_broken_link() {
+        let chain = chain();
+
+        // A block the previous one does not reference
+        let mut proof = chain.proof.clone();
+        proof.links[1].id.root_hash = proof.links[0].id.root_hash.clone();
+        assert!(matches!(
+            proof.verify(&chain.trusted.id()),
+            Err(VerificationError::HashMismatch(_))
+        ));
+
+        // A proof of another block than the one linked before
+        let mut proof = chain.proof.clone();
+        proof.links[1].proof = proof.links[0].proof.clone();
+        assert!(matches!(
+            proof.verify(&chain.trusted.id()),
+            Err(VerificationError::HashMismatch(_))
+        ));
+
+        // A shard block that is not the top of its shard in the masterchain block
+        let mut proof = chain.proof;
+        proof.links.remove(0);
+        proof.links[0].proof = link(&chain.shard_blocks[1], &chain.mc).proof;
+        assert!(matches!(
+            proof.verify(&chain.trusted.id()),
+            Err(VerificationError::HashMismatch(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn client_checks_the_requested_block() {
+        let chain = chain();
+        let response = serde_json::to_value(&chain.proof).unwrap();
+        let client = mock_client(Arc::new(move |request: &Request| {
+            assert_eq!(request.method, "getShardBlockProof");
+            assert_eq!(request.params["from_seqno"], TRUSTED_SEQNO.to_string());
+            response.clone().into()
+        }))
+        .await;
+
+        let older = chain.shard_blocks[0].id();
+        let proven = client
+            .get_shard_block_proof_verified(0, &older.shard, older.seqno, &chain.trusted.id())
+            .await
+            .unwrap();
+        assert_eq!(proven, older);
+
+        // A valid proof, but of the newer block
+        assert!(matches!(
+            client
+                .get_shard_block_proof_verified(
+                    0,
+                    &older.shard,
+                    older.seqno + 1,
+                    &chain.trusted.id()
+                )
+                .await,
+            Err(ToncenterError::ProcessingError(
+                ProcessingError::Verification(_)
+            ))
+        ));
+    }
+}
@@ -0,0 +1,417 @@
+use crate::config::{ValidatorDescr, ValidatorSet};
+use crate::error::VerificationError;
+use crate::models::{BlocksSignature, MasterchainBlockSignatures, TonBlockIdExt};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+/// TL constructor of `pub.ed25519 key:int256 = PublicKey`.
+const PUB_ED25519_TAG: u32 = 0x4813b4c6;
+/// TL constructor of `ton.blockId root_cell_hash:int256 file_hash:int256 = ton.BlockId`.
+const TON_BLOCK_ID_TAG: u32 = 0xc50b6e70;
+
+impl ValidatorDescr {
+    /// Short node id: SHA-256 of the TL-serialized `pub.ed25519` public key.
+    pub fn node_id_short(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(PUB_ED25519_TAG.to_le_bytes());
+        hasher.update(self.public_key);
+        hasher.finalize().into()
+    }
+}
+
+impl MasterchainBlockSignatures {
+    /// Check that the signatures are for `block` and valid for the validator set that
+    /// produced it.
+    ///
+    /// `block` is the masterchain block id the caller trusts: the response must be for that
+    /// block, and its root and file hashes are the ones checked against the signatures.
+    /// See [`verify_block_signatures`].
+    pub fn verify(
+        &self,
+        block: &TonBlockIdExt,
+        validator_set: &ValidatorSet,
+    ) -> Result<(), VerificationError> {
+        if block.workchain != -1 || self.id.workchain != -1 {
+            return Err(VerificationError::Malformed(format!(
+                "expected masterchain block signatures, got workchain {}",
+                self.id.workchain
+            )));
+        }
+        if self.id.seqno != block.seqno {
+            return Err(VerificationError::Malformed(format!(
+                "signatures are for block {} instead of {}",
+                self.id.seqno, block.seqno
+            )));
+        }
+        if decode_fixed::<32>(&self.id.root_hash, "root hash")?
+            != decode_fixed::<32>(&block.root_hash, "root hash")?
+            || decode_fixed::<32>(&self.id.file_hash, "file hash")?
+                != decode_fixed::<32>(&block.file_hash, "file hash")?
+        {
+            return Err(VerificationError::HashMismatch(format!(
+                "signatures are for another block {}",
+                block.seqno
+            )));
+        }
+        verify_block_signatures(block, &self.signatures, validator_set)
+    }
+}
+
+/// Verify ed25519 signatures of a masterchain block and require more than 2/3 of the weight.
+///
+/// Masterchain blocks are signed by the first `main` validators of the set,
+/// so `validator_set` must be config param 34 as of the previous masterchain block.
+/// Signatures from nodes outside of that subset are ignored, an invalid signature
+/// from a known validator is an error.
+pub fn verify_block_signatures(
+    id: &TonBlockIdExt,
+    signatures: &[BlocksSignature],
+    validator_set: &ValidatorSet,
+) -> Result<(), VerificationError> {
+    let mut message = Vec::with_capacity(68);
+    message.extend_from_slice(&TON_BLOCK_ID_TAG.to_le_bytes());
+    message.extend_from_slice(&decode_fixed::<32>(&id.root_hash, "root hash")?);
+    message.extend_from_slice(&decode_fixed::<32>(&id.file_hash, "file hash")?);
+
+    let main = (validator_set.main as usize).min(validator_set.validators.len());
+    let validators = validator_set.validators[..main]
+        .iter()
+        .map(|validator| (validator.node_id_short(), validator))
+        .collect::<HashMap<_, _>>();
+    let total = validators
+        .values()
+        .map(|validator| validator.weight)
+        .sum::<u64>();
+
+    let mut signed = 0u64;
+    let mut seen = HashSet::new();
+    for signature in signatures {
+        let node_id = decode_fixed::<32>(&signature.node_id_short, "node id")?;
+        let Some(validator) = validators.get(&node_id) else {
+            continue;
+        };
+        if !seen.insert(node_id) {
+            continue;
+        }
+
+        let invalid = || VerificationError::InvalidSignature {
+            node_id_short: signature.node_id_short.clone(),
+        };
+        let key = VerifyingKey::from_bytes(&validator.public_key).map_err(|_| invalid())?;
+        let bytes = decode_fixed::<64>(&signature.signature, "signature")?;
+        key.verify_strict(&message, &Signature::from_bytes(&bytes))
+            .map_err(|_| invalid())?;
+        signed += validator.weight;
+    }
+
+    if (signed as u128) * 3 <= (total as u128) * 2 {
+        return Err(VerificationError::InsufficientWeight { signed, total });
+    }
+    Ok(())
+}
+
+pub(crate) fn decode_fixed<const N: usize>(
+    value: &str,
+    what: &str,
+) -> Result<[u8; N], VerificationError> {
+    STANDARD
+        .decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| VerificationError::Malformed(format!("invalid {}: {}", what, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::CellBuilder;
+    use crate::client::mock::{mock_client, Request};
+    use crate::error::ToncenterError;
+    use ed25519_dalek::{Signer, SigningKey};
+    use num_bigint::BigUint;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    const SEQNO: u32 = 38_743_891;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn validator(seed: u8, weight: u64) -> ValidatorDescr {
+        ValidatorDescr {
+            public_key: key(seed).verifying_key().to_bytes(),
+            weight,
+            adnl_addr: None,
+        }
+    }
+
+    /// Set of four validators, the first three of which sign masterchain blocks.
+    fn validator_set(weights: [u64; 3]) -> ValidatorSet {
+        let validators = vec![
+            validator(1, weights[0]),
+            validator(2, weights[1]),
+            validator(3, weights[2]),
+            validator(4, 1_000),
+        ];
+        ValidatorSet {
+            utime_since: 1_700_000_000,
+            utime_until: 1_700_065_536,
+            total: 4,
+            main: 3,
+            total_weight: validators.iter().map(|v| v.weight).sum(),
+            validators,
+        }
+    }
+
+    fn block() -> TonBlockIdExt {
+        TonBlockIdExt {
+            workchain: -1,
+            shard: "-9223372036854775808".to_string(),
+            seqno: SEQNO,
+            root_hash: STANDARD.encode([0x11; 32]),
+            file_hash: STANDARD.encode([0x22; 32]),
+            extra: None,
+        }
+    }
+
+    fn sign(seed: u8, block: &TonBlockIdExt) -> BlocksSignature {
+        let mut message = TON_BLOCK_ID_TAG.to_le_bytes().to_vec();
+        message.extend_from_slice(&STANDARD.decode(&block.root_hash).unwrap());
+        message.extend_from_slice(&STANDARD.decode(&block.file_hash).unwrap());
+        BlocksSignature {
+            node_id_short: STANDARD.encode(validator(seed, 0).node_id_short()),
+            signature: STANDARD.encode(key(seed).sign(&message).to_bytes()),
+        }
+    }
+
+    fn signatures(seeds: &[u8]) -> MasterchainBlockSignatures {
+        MasterchainBlockSignatures {
+            type_field: "blocks.signatures".to_string(),
+            id: block(),
+            signatures: seeds.iter().map(|&seed| sign(seed, &block())).collect(),
+            extra: String::new(),
+        }
+    }
+
+    #[test]
+    fn node_id_short() {
+        // SHA-256 of 0x4813b4c6 (little endian) followed by the key, computed independently
+        assert_eq!(
+            STANDARD.encode(validator(1, 0).node_id_short()),
+            "y4iLUp1c2rLueqAqQSYmuaJZQMEEIgbNjumduy1KAfg="
+        );
+    }
+
+    #[test]
+    fn signs_ton_block_id() {
+        // ed25519 signature of the TL `ton.blockId` of block(), computed independently
+        assert_eq!(
+            sign(1, &block()).signature,
+            "d1UJWe1D8OJ8vYMny9swE3k9vPnn13dHVsGMm4PryLOw7CwKTpq38DvR2nhMSGiPUCLXBQhZwFVS9pBKNdhiDw=="
+        );
+    }
+
+    #[test]
+    fn accepts_two_thirds_of_weight() {
+        let set = validator_set([40, 30, 30]);
+        signatures(&[1, 2, 3]).verify(&block(), &set).unwrap();
+        signatures(&[3, 1]).verify(&block(), &set).unwrap();
+    }
+
+    #[test]
+    fn requires_more_than_two_thirds() {
+        let set = validator_set([40, 30, 30]);
+        assert_eq!(
+            signatures(&[2, 3]).verify(&block(), &set),
+            Err(VerificationError::InsufficientWeight {
+                signed: 60,
+                total: 100
+            })
+        );
+        // Exactly two thirds is not enough
+        let set = validator_set([20, 20, 20]);
+        assert_eq!(
+            signatures(&[1, 2]).verify(&block(), &set),
+            Err(VerificationError::InsufficientWeight {
+                signed: 40,
+                total: 60
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_signature() {
+        let set = validator_set([40, 30, 30]);
+        let mut signatures = signatures(&[1, 2, 3]);
+        let mut bytes = STANDARD
+            .decode(&signatures.signatures[1].signature)
+            .unwrap();
+        bytes[10] ^= 1;
+        signatures.signatures[1].signature = STANDARD.encode(bytes);
+        assert_eq!(
+            signatures.verify(&block(), &set),
+            Err(VerificationError::InvalidSignature {
+                node_id_short: signatures.signatures[1].node_id_short.clone()
+            })
+        );
+
+        // A valid signature of another block
+        let mut other = block();
+        other.root_hash = STANDARD.encode([0x33; 32]);
+        let mut signatures = self::signatures(&[1, 2, 3]);
+        signatures.signatures[0] = sign(1, &other);
+        assert!(matches!(
+            signatures.verify(&block(), &set),
+            Err(VerificationError::InvalidSignature { .. })
+        ));
+    }
+
+    #[test]
+    fn counts_duplicate_signers_once() {
+        let set = validator_set([40, 30, 30]);
+        signatures(&[1, 1, 2, 2]).verify(&block(), &set).unwrap();
+        // Counted three times, the signature would make up 90 of 100
+        assert_eq!(
+            signatures(&[2, 2, 2]).verify(&block(), &set),
+            Err(VerificationError::InsufficientWeight {
+                signed: 30,
+                total: 100
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_signers_outside_main_validators() {
+        // Validator 4 is in the set with most of the weight, but does not sign masterchain blocks
+        let set = validator_set([40, 30, 30]);
+        assert_eq!(
+            signatures(&[4, 2]).verify(&block(), &set),
+            Err(VerificationError::InsufficientWeight {
+                signed: 30,
+                total: 100
+            })
+        );
+        let mut unknown = signatures(&[1, 2]);
+        unknown.signatures.push(sign(9, &block()));
+        unknown.verify(&block(), &set).unwrap();
+    }
+
+    #[test]
+    fn rejects_signatures_of_another_block() {
+        let set = validator_set([40, 30, 30]);
+        let signatures = signatures(&[1, 2, 3]);
+
+        let mut expected = block();
+        expected.seqno += 1;
+        assert!(matches!(
+            signatures.verify(&expected, &set),
+            Err(VerificationError::Malformed(_))
+        ));
+
+        let mut expected = block();
+        expected.file_hash = STANDARD.encode([0x44; 32]);
+        assert!(matches!(
+            signatures.verify(&expected, &set),
+            Err(VerificationError::HashMismatch(_))
+        ));
+
+        let mut basechain = signatures.clone();
+        basechain.id.workchain = 0;
+        assert!(matches!(
+            basechain.verify(&block(), &set),
+            Err(VerificationError::Malformed(_))
+        ));
+    }
+
+    /// Config param 34 holding [`validator_set`], as `getConfigParam` returns it.
+    fn config_param_34(set: &ValidatorSet) -> String {
+        let entries = set
+            .validators
+            .iter()
+            .enumerate()
+            .map(|(index, validator)| {
+                let mut builder = CellBuilder::new();
+                builder
+                    .store_u8(0x53)
+                    .unwrap()
+                    .store_u32(0x8e81278a)
+                    .unwrap()
+                    .store_bytes(&validator.public_key)
+                    .unwrap()
+                    .store_u64(validator.weight)
+                    .unwrap();
+                (BigUint::from(index), builder.build().unwrap().to_arc())
+            })
+            .collect::<BTreeMap<_, _>>();
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u8(0x12)
+            .unwrap()
+            .store_u32(set.utime_since)
+            .unwrap()
+            .store_u32(set.utime_until)
+            .unwrap()
+            .store_uint(16, set.total.into())
+            .unwrap()
+            .store_uint(16, set.main.into())
+            .unwrap()
+            .store_u64(set.total_weight)
+            .unwrap()
+            .store_dict(16, &entries)
+            .unwrap();
+        builder.build().unwrap().to_arc().to_boc_b64().unwrap()
+    }
+
+    async fn client(response: MasterchainBlockSignatures) -> crate::client::ApiClientV2 {
+        let config = config_param_34(&validator_set([40, 30, 30]));
+        mock_client(Arc::new(move |request: &Request| {
+            match request.method.as_str() {
+                "getMasterchainBlockSignatures" => {
+                    assert_eq!(request.params["seqno"], SEQNO.to_string());
+                    serde_json::to_value(&response).unwrap()
+                }
+                "getConfigParam" => {
+                    assert_eq!(request.params["config_id"], "34");
+                    assert_eq!(request.params["seqno"], (SEQNO - 1).to_string());
+                    json!({
+                        "@type": "configInfo",
+                        "config": { "@type": "tvm.cell", "bytes": config }
+                    })
+                }
+                other => panic!("unexpected method {}", other),
+            }
+            .into()
+        }))
+        .await
+    }
+
+    #[tokio::test]
+    async fn client_checks_the_requested_block() {
+        let client = self::client(signatures(&[1, 2])).await;
+        let verified = client
+            .get_masterchain_block_signatures_verified(&block())
+            .await
+            .unwrap();
+        assert_eq!(verified.signatures.len(), 2);
+
+        // Valid signatures, but of the next block
+        let mut next = block();
+        next.seqno += 1;
+        next.root_hash = STANDARD.encode([0x55; 32]);
+        let mut response = signatures(&[]);
+        response.id = next.clone();
+        response.signatures = [1, 2, 3].map(|seed| sign(seed, &next)).to_vec();
+        let client = self::client(response).await;
+        assert!(matches!(
+            client
+                .get_masterchain_block_signatures_verified(&block())
+                .await,
+            Err(ToncenterError::ProcessingError(_))
+        ));
+    }
+}
@@ -0,0 +1,367 @@
+use crate::address::Address;
+use crate::error::ShardError;
+use crate::models::TonBlockIdExt;
+use std::fmt;
+use std::str::FromStr;
+
+const ROOT_PREFIX: u64 = 1 << 63;
+const MAX_SPLIT_DEPTH: u32 = 60;
+
+/// Shard of a workchain, identified by its 64-bit prefix with a terminating tag bit.
+///
+/// `0x8000000000000000` is the whole workchain, `0x4000000000000000` and
+/// `0xc000000000000000` are its two halves after a split and so on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShardId {
+    workchain: i32,
+    prefix: u64,
+}
+
+impl ShardId {
+    /// Create a shard from its tagged prefix. The prefix must be non-zero.
+    pub fn new(workchain: i32, prefix: u64) -> Result<Self, ShardError> {
+        if prefix == 0 {
+            return Err(ShardError::ZeroPrefix);
+        }
+        Ok(Self { workchain, prefix })
+    }
+
+    /// The whole workchain as a single shard.
+    pub fn root(workchain: i32) -> Self {
+        Self {
+            workchain,
+            prefix: ROOT_PREFIX,
+        }
+    }
+
+    /// Parse the shard part in any of its usual forms.
+    ///
+    /// Signed decimal like `"-9223372036854775808"` and hex with a `0x` prefix or `a-f`
+    /// digits are read as such. Sixteen digits without a sign, like `"8000000000000000"`,
+    /// are rejected as ambiguous: use [`ShardId::parse_signed`] or [`ShardId::parse_hex`].
+    pub fn parse(workchain: i32, shard: &str) -> Result<Self, ShardError> {
+        if shard.starts_with("0x") || shard.bytes().any(|b| b.is_ascii_alphabetic()) {
+            Self::parse_hex(workchain, shard)
+        } else if shard.len() == 16 && shard.bytes().all(|b| b.is_ascii_digit()) {
+            Err(ShardError::Ambiguous(shard.to_string()))
+        } else {
+            Self::parse_signed(workchain, shard)
+        }
+    }
+
+    /// Parse the shard part in signed or unsigned decimal form, as toncenter uses it.
+    pub fn parse_signed(workchain: i32, shard: &str) -> Result<Self, ShardError> {
+        let invalid = || ShardError::InvalidFormat(shard.to_string());
+        let prefix = if shard.starts_with('-') {
+            shard.parse::<i64>().map_err(|_| invalid())? as u64
+        } else {
+            shard.parse::<u64>().map_err(|_| invalid())?
+        };
+        Self::new(workchain, prefix)
+    }
+
+    /// Parse the shard part in hex form with an optional `0x` prefix, e.g. `"8000000000000000"`.
+    pub fn parse_hex(workchain: i32, shard: &str) -> Result<Self, ShardError> {
+        let hex = shard.strip_prefix("0x").unwrap_or(shard);
+        let prefix = u64::from_str_radix(hex, 16)
+            .map_err(|_| ShardError::InvalidFormat(shard.to_string()))?;
+        Self::new(workchain, prefix)
+    }
+
+    /// Shard of the given depth that contains an address.
+    pub fn for_address(address: &Address, depth: u32) -> Self {
+        let depth = depth.min(MAX_SPLIT_DEPTH);
+        let tag = ROOT_PREFIX >> depth;
+        let account = account_prefix(address);
+        Self {
+            workchain: address.workchain(),
+            prefix: (account & !(tag | (tag - 1))) | tag,
+        }
+    }
+
+    pub fn workchain(&self) -> i32 {
+        self.workchain
+    }
+
+    /// Tagged 64-bit prefix.
+    pub fn prefix(&self) -> u64 {
+        self.prefix
+    }
+
+    /// Number of significant bits in the prefix, `0` for the whole workchain.
+    pub fn prefix_len(&self) -> u32 {
+        63 - self.prefix.trailing_zeros()
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.prefix == ROOT_PREFIX
+    }
+
+    /// Shard this one was split from, `None` for the whole workchain.
+    pub fn parent(&self) -> Option<Self> {
+        if self.is_root() {
+            return None;
+        }
+        let tag = self.tag();
+        Some(Self {
+            workchain: self.workchain,
+            prefix: (self.prefix - tag) | (tag << 1),
+        })
+    }
+
+    /// Left and right halves of the shard, `None` at the maximum split depth.
+    pub fn children(&self) -> Option<(Self, Self)> {
+        if self.prefix_len() >= MAX_SPLIT_DEPTH {
+            return None;
+        }
+        let step = self.tag() >> 1;
+        Some((
+            Self {
+                workchain: self.workchain,
+                prefix: self.prefix - step,
+            },
+            Self {
+                workchain: self.workchain,
+                prefix: self.prefix + step,
+            },
+        ))
+    }
+
+    /// Whether `other` is this shard or one of its descendants.
+    pub fn is_ancestor_of(&self, other: &ShardId) -> bool {
+        self.workchain == other.workchain
+            && self.prefix_len() <= other.prefix_len()
+            && (self.prefix ^ other.prefix) & self.mask() == 0
+    }
+
+    /// Whether the two shards overlap, i.e. one is an ancestor of the other.
+    pub fn intersects(&self, other: &ShardId) -> bool {
+        self.is_ancestor_of(other) || other.is_ancestor_of(self)
+    }
+
+    /// Whether the account belongs to this shard.
+    pub fn contains(&self, address: &Address) -> bool {
+        self.workchain == address.workchain()
+            && (self.prefix ^ account_prefix(address)) & self.mask() == 0
+    }
+
+    /// Shard part in signed decimal form, as expected by toncenter requests.
+    pub fn shard_string(&self) -> String {
+        (self.prefix as i64).to_string()
+    }
+
+    fn tag(&self) -> u64 {
+        self.prefix & self.prefix.wrapping_neg()
+    }
+
+    /// Bits that are fixed within the shard.
+    fn mask(&self) -> u64 {
+        let tag = self.tag();
+        !(tag | (tag - 1))
+    }
+}
+
+fn account_prefix(address: &Address) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&address.hash_part()[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+impl FromStr for ShardId {
+    type Err = ShardError;
+
+    /// Parse the `workchain:shard` form produced by `Display`, e.g. `0:8000000000000000`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ShardError::InvalidFormat(s.to_string());
+        let (workchain, shard) = s.split_once(':').ok_or_else(invalid)?;
+        let workchain = workchain.parse::<i32>().map_err(|_| invalid())?;
+        Self::parse_hex(workchain, shard)
+    }
+}
+
+impl fmt::Display for ShardId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:016x}", self.workchain, self.prefix)
+    }
+}
+
+impl TonBlockIdExt {
+    /// Shard the block belongs to. The shard part is read as signed decimal, as toncenter
+    /// returns it.
+    pub fn shard_id(&self) -> Result<ShardId, ShardError> {
+        ShardId::parse_signed(self.workchain, &self.shard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// USDT jetton master.
+    const USDT: &str = "EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs";
+    /// TON DNS `.ton` collection.
+    const DNS: &str = "EQC_1YoM8RBixN95lz7odcF3Vrkc_N8Ne7gQi7Abtlet_Efi";
+    const WALLET: &str = "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N";
+    const LOW_WALLET: &str = "EQAvDfWFG0oYX19jwNDNBBL1rKNT9XfaGP9HyTb5nb2Eml6y";
+    const HIGH_WALLET: &str = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR";
+    const ELECTOR: &str = "Ef8zMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzM0vF";
+
+    fn address(address: &str) -> Address {
+        address.parse().unwrap()
+    }
+
+    fn shard(workchain: i32, prefix: u64) -> ShardId {
+        ShardId::new(workchain, prefix).unwrap()
+    }
+
+    #[test]
+    fn parses_decimal() {
+        let shard = ShardId::parse(0, "-4611686018427387904").unwrap();
+        assert_eq!(shard.to_string(), "0:c000000000000000");
+        let shard = ShardId::parse_signed(0, "2251799813685248").unwrap();
+        assert_eq!(shard.to_string(), "0:0008000000000000");
+        assert_eq!(shard.shard_string(), "2251799813685248");
+        let shard = ShardId::parse(0, "6917529027641081856").unwrap();
+        assert_eq!(shard.to_string(), "0:6000000000000000");
+    }
+
+    #[test]
+    fn rejects_ambiguous_shards() {
+        assert_eq!(
+            ShardId::parse(0, "8000000000000000"),
+            Err(ShardError::Ambiguous("8000000000000000".to_string()))
+        );
+        assert_eq!(
+            ShardId::parse(0, "2251799813685248"),
+            Err(ShardError::Ambiguous("2251799813685248".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_negative_decimal() {
+        let shard = ShardId::parse(-1, "-9223372036854775808").unwrap();
+        assert_eq!(shard, ShardId::root(-1));
+    }
+
+    #[test]
+    fn parses_hex() {
+        let shard = ShardId::parse(0, "c000000000000000").unwrap();
+        assert_eq!(shard.prefix(), 0xc000000000000000);
+        assert_eq!(
+            ShardId::parse(0, "0x8000000000000000").unwrap(),
+            ShardId::root(0)
+        );
+        assert_eq!(
+            ShardId::parse_hex(0, "8000000000000000").unwrap(),
+            ShardId::root(0)
+        );
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let shard = ShardId::parse_signed(0, "2251799813685248").unwrap();
+        assert_eq!(shard.to_string().parse::<ShardId>().unwrap(), shard);
+        assert_eq!(
+            "-1:8000000000000000".parse::<ShardId>().unwrap(),
+            ShardId::root(-1)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_shards() {
+        assert_eq!(ShardId::parse(0, "0"), Err(ShardError::ZeroPrefix));
+        assert!(matches!(
+            ShardId::parse(0, "0xg000000000000000"),
+            Err(ShardError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            ShardId::parse(0, "18446744073709551616"),
+            Err(ShardError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            "8000000000000000".parse::<ShardId>(),
+            Err(ShardError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn split_and_merge() {
+        let root = ShardId::root(0);
+        let (left, right) = root.children().unwrap();
+        assert_eq!(left.to_string(), "0:4000000000000000");
+        assert_eq!(right.to_string(), "0:c000000000000000");
+        assert_eq!(right.parent(), Some(root));
+        assert!(root.is_ancestor_of(&right));
+        assert!(!left.intersects(&right));
+    }
+
+    #[test]
+    fn routes_addresses_by_depth() {
+        let usdt = address(USDT);
+        assert_eq!(ShardId::for_address(&usdt, 0), ShardId::root(0));
+        assert_eq!(ShardId::for_address(&usdt, 1), shard(0, 0xc000000000000000));
+        assert_eq!(ShardId::for_address(&usdt, 2), shard(0, 0xa000000000000000));
+        assert_eq!(ShardId::for_address(&usdt, 4), shard(0, 0xb800000000000000));
+        assert_eq!(ShardId::for_address(&usdt, 8), shard(0, 0xb180000000000000));
+        assert_eq!(
+            ShardId::for_address(&usdt, 16),
+            shard(0, 0xb113800000000000)
+        );
+        assert_eq!(ShardId::for_address(&usdt, 60).prefix_len(), 60);
+        assert_eq!(ShardId::for_address(&usdt, 64).prefix_len(), 60);
+
+        let dns = address(DNS);
+        assert_eq!(ShardId::for_address(&dns, 4), shard(0, 0xb800000000000000));
+        assert_eq!(ShardId::for_address(&dns, 8), shard(0, 0xbf80000000000000));
+        let wallet = address(WALLET);
+        assert_eq!(
+            ShardId::for_address(&wallet, 4),
+            shard(0, 0x8800000000000000)
+        );
+        assert_eq!(
+            ShardId::for_address(&address(LOW_WALLET), 1),
+            shard(0, 0x4000000000000000)
+        );
+
+        let elector = address(ELECTOR);
+        assert_eq!(ShardId::for_address(&elector, 0), ShardId::root(-1));
+        assert_eq!(
+            ShardId::for_address(&elector, 2),
+            shard(-1, 0x2000000000000000)
+        );
+
+        for address in [usdt, dns, wallet, elector] {
+            for depth in [0, 1, 2, 4, 8, 30, 60] {
+                let shard = ShardId::for_address(&address, depth);
+                assert!(shard.contains(&address));
+                assert!(ShardId::root(address.workchain()).is_ancestor_of(&shard));
+            }
+        }
+    }
+
+    #[test]
+    fn routes_addresses_after_splits() {
+        let root = ShardId::root(0);
+        let (left, right) = root.children().unwrap();
+        let (right_left, right_right) = right.children().unwrap();
+        let accounts = [USDT, DNS, WALLET, LOW_WALLET, HIGH_WALLET].map(address);
+
+        for account in &accounts {
+            assert!(root.contains(account));
+            assert_ne!(left.contains(account), right.contains(account));
+            assert!(!ShardId::root(-1).contains(account));
+        }
+        assert!(left.contains(&address(LOW_WALLET)));
+        for account in [USDT, DNS, WALLET].map(address) {
+            assert!(right_left.contains(&account));
+            assert!(!right_right.contains(&account));
+        }
+        assert!(right_right.contains(&address(HIGH_WALLET)));
+
+        // Merging back routes every account of both halves to the parent
+        assert_eq!(right_left.parent(), Some(right));
+        assert!(right.contains(&address(HIGH_WALLET)));
+        assert!(!root.contains(&address(ELECTOR)));
+        assert!(ShardId::root(-1).contains(&address(ELECTOR)));
+    }
+}
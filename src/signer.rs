@@ -0,0 +1,322 @@
+use crate::address::Address;
+use crate::cell::{ArcCell, CellBuilder, CellHash};
+use crate::error::WalletError;
+use crate::message::ExternalMessage;
+use crate::mnemonic::KeyPair;
+use ed25519_dalek::SigningKey;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Source of ed25519 signatures for wallet requests.
+///
+/// Implement it to keep keys in an HSM, a KMS or another process. Message builders only
+/// ever ask for a signature of a cell representation hash.
+pub trait Signer: Send + Sync {
+    /// Sign the 32-byte representation hash of a cell. Report backend failures as
+    /// [`WalletError::Signer`].
+    fn sign(&self, hash: &CellHash) -> impl Future<Output = Result<[u8; 64], WalletError>> + Send;
+
+    /// Public key the signatures can be checked with.
+    fn public_key(&self) -> [u8; 32];
+}
+
+impl Signer for SigningKey {
+    async fn sign(&self, hash: &CellHash) -> Result<[u8; 64], WalletError> {
+        Ok(ed25519_dalek::Signer::sign(self, hash).to_bytes())
+    }
+
+    fn public_key(&self) -> [u8; 32] {
+        self.verifying_key().to_bytes()
+    }
+}
+
+impl Signer for KeyPair {
+    async fn sign(&self, hash: &CellHash) -> Result<[u8; 64], WalletError> {
+        self.signing_key().sign(hash).await
+    }
+
+    fn public_key(&self) -> [u8; 32] {
+        KeyPair::public_key(self)
+    }
+}
+
+/// Where a contract expects the signature relative to the signed payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SignatureLayout {
+    /// Signature bits followed by the payload inline.
+    Prefix,
+    /// Payload inline followed by the signature bits.
+    Suffix,
+    /// Signature bits and the payload as a reference.
+    Reference,
+}
+
+/// Message body waiting for a signature of [`Self::hash`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsignedBody {
+    payload: ArcCell,
+    layout: SignatureLayout,
+}
+
+impl UnsignedBody {
+    pub(crate) fn new(payload: ArcCell, layout: SignatureLayout) -> Self {
+        Self { payload, layout }
+    }
+
+    /// The signed part of the body.
+    pub fn payload(&self) -> &ArcCell {
+        &self.payload
+    }
+
+    /// Hash to be signed.
+    pub fn hash(&self) -> CellHash {
+        self.payload.repr_hash()
+    }
+
+    /// Complete the body with a signature obtained elsewhere.
+    pub fn with_signature(&self, signature: &[u8; 64]) -> Result<ArcCell, WalletError> {
+        let mut builder = CellBuilder::new();
+        match self.layout {
+            SignatureLayout::Prefix => {
+                builder.store_bytes(signature)?.store_cell(&self.payload)?;
+            }
+            SignatureLayout::Suffix => {
+                builder.store_cell(&self.payload)?.store_bytes(signature)?;
+            }
+            SignatureLayout::Reference => {
+                builder
+                    .store_bytes(signature)?
+                    .store_reference(self.payload.clone())?;
+            }
+        }
+        Ok(Arc::new(builder.build()?))
+    }
+
+    pub async fn sign<S: Signer>(&self, signer: &S) -> Result<ArcCell, WalletError> {
+        let signature = signer.sign(&self.hash()).await?;
+        self.with_signature(&signature)
+    }
+}
+
+/// External message waiting for a signature of [`Self::hash`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsignedExternalMessage {
+    pub destination: Address,
+    pub state_init: Option<ArcCell>,
+    pub body: UnsignedBody,
+}
+
+impl UnsignedExternalMessage {
+    /// Hash to be signed.
+    pub fn hash(&self) -> CellHash {
+        self.body.hash()
+    }
+
+    /// Complete the message with a signature obtained elsewhere.
+    pub fn with_signature(&self, signature: &[u8; 64]) -> Result<ExternalMessage, WalletError> {
+        Ok(ExternalMessage {
+            destination: self.destination,
+            state_init: self.state_init.clone(),
+            body: self.body.with_signature(signature)?,
+        })
+    }
+
+    pub async fn sign<S: Signer>(&self, signer: &S) -> Result<ExternalMessage, WalletError> {
+        let signature = signer.sign(&self.hash()).await?;
+        self.with_signature(&signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coins::Coins;
+    use crate::message::{InternalMessage, SendMode};
+    use crate::wallet::{
+        HighloadQueryId, HighloadWalletV3, WalletMessage, WalletV3R2, WalletV4R2, WalletV5R1,
+        WalletV5Request,
+    };
+    use std::sync::Mutex;
+
+    /// Signer standing in for an HSM: answers asynchronously and records what it signed.
+    struct RemoteSigner {
+        key: SigningKey,
+        signed: Mutex<Vec<CellHash>>,
+    }
+
+    impl RemoteSigner {
+        fn new(seed: u8) -> Self {
+            Self {
+                key: SigningKey::from_bytes(&[seed; 32]),
+                signed: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl Signer for RemoteSigner {
+        async fn sign(&self, hash: &CellHash) -> Result<[u8; 64], WalletError> {
+            tokio::task::yield_now().await;
+            self.signed.lock().unwrap().push(*hash);
+            Ok(ed25519_dalek::Signer::sign(&self.key, hash).to_bytes())
+        }
+
+        fn public_key(&self) -> [u8; 32] {
+            self.key.verifying_key().to_bytes()
+        }
+    }
+
+    fn local_key() -> SigningKey {
+        SigningKey::from_bytes(&[1; 32])
+    }
+
+    fn messages() -> Vec<WalletMessage> {
+        let destination = "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N"
+            .parse()
+            .unwrap();
+        vec![WalletMessage::new(
+            SendMode::PAY_FEES_SEPARATELY | SendMode::IGNORE_ERRORS,
+            InternalMessage::transfer(destination, Coins::from_nanotons(1_500_000_000), true),
+        )]
+    }
+
+    /// Sign the exported message with the key directly, as an offline signer would.
+    fn sign_exported(unsigned: &UnsignedExternalMessage) -> ExternalMessage {
+        let signature = ed25519_dalek::Signer::sign(&local_key(), &unsigned.hash()).to_bytes();
+        unsigned.with_signature(&signature).unwrap()
+    }
+
+    #[tokio::test]
+    async fn remote_signer_matches_local_key() {
+        let remote = RemoteSigner::new(1);
+        let public_key = remote.public_key();
+        let messages = messages();
+
+        let v3 = WalletV3R2::new(0, public_key);
+        let signed = v3
+            .create_transfer(&remote, 3, 1_700_000_000, &messages)
+            .await
+            .unwrap();
+        let local = v3
+            .create_transfer(&local_key(), 3, 1_700_000_000, &messages)
+            .await
+            .unwrap();
+        let exported = sign_exported(&v3.prepare_transfer(3, 1_700_000_000, &messages).unwrap());
+        assert_eq!(signed.to_boc_b64(), local.to_boc_b64());
+        assert_eq!(signed, exported);
+
+        let v4 = WalletV4R2::new(0, public_key);
+        let signed = v4
+            .create_transfer(&remote, 0, 1_700_000_000, &messages)
+            .await
+            .unwrap();
+        let exported = sign_exported(&v4.prepare_transfer(0, 1_700_000_000, &messages).unwrap());
+        assert_eq!(signed.to_boc_b64(), exported.to_boc_b64());
+
+        let v5 = WalletV5R1::new(0, public_key);
+        let request = WalletV5Request {
+            messages: messages.clone(),
+            extended_actions: Vec::new(),
+        };
+        let signed = v5
+            .create_external(&remote, 4, 1_700_000_000, &request)
+            .await
+            .unwrap();
+        let exported = sign_exported(&v5.prepare_external(4, 1_700_000_000, &request).unwrap());
+        assert_eq!(signed.to_boc_b64(), exported.to_boc_b64());
+
+        // Bodies alone, as relayed for gasless transfers
+        let signed = v5
+            .create_internal(&remote, 4, 1_700_000_000, &request)
+            .await
+            .unwrap();
+        let unsigned = v5.prepare_internal(4, 1_700_000_000, &request).unwrap();
+        let local = unsigned.sign(&local_key()).await.unwrap();
+        assert_eq!(signed, local);
+
+        // Any code will do, the request does not depend on it
+        let highload = HighloadWalletV3::new(WalletV3R2::code(), 0, public_key, 3600).unwrap();
+        let signed = highload
+            .create_external(
+                &remote,
+                HighloadQueryId::default(),
+                1_700_000_000,
+                &messages[0],
+            )
+            .await
+            .unwrap();
+        let exported = sign_exported(
+            &highload
+                .prepare_external(HighloadQueryId::default(), 1_700_000_000, &messages[0])
+                .unwrap(),
+        );
+        assert_eq!(signed.to_boc_b64(), exported.to_boc_b64());
+
+        // One signature per message, each over the payload hash
+        assert_eq!(remote.signed.lock().unwrap().len(), 5);
+        assert_eq!(remote.signed.lock().unwrap()[3], unsigned.hash());
+    }
+
+    #[test]
+    fn places_signature_by_layout() {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(0xdeadbeef).unwrap();
+        let payload = Arc::new(builder.build().unwrap());
+        let signature = [0x5a; 64];
+
+        let prefix = UnsignedBody::new(payload.clone(), SignatureLayout::Prefix)
+            .with_signature(&signature)
+            .unwrap();
+        let mut slice = prefix.parser();
+        assert_eq!(slice.load_bytes(64).unwrap(), signature);
+        assert_eq!(slice.load_u32().unwrap(), 0xdeadbeef);
+
+        let suffix = UnsignedBody::new(payload.clone(), SignatureLayout::Suffix)
+            .with_signature(&signature)
+            .unwrap();
+        let mut slice = suffix.parser();
+        assert_eq!(slice.load_u32().unwrap(), 0xdeadbeef);
+        assert_eq!(slice.load_bytes(64).unwrap(), signature);
+
+        let reference = UnsignedBody::new(payload.clone(), SignatureLayout::Reference)
+            .with_signature(&signature)
+            .unwrap();
+        let mut slice = reference.parser();
+        assert_eq!(slice.load_bytes(64).unwrap(), signature);
+        assert_eq!(slice.load_reference().unwrap(), payload);
+    }
+
+    #[tokio::test]
+    async fn rejects_signer_with_another_key() {
+        let remote = RemoteSigner::new(2);
+        let public_key = local_key().verifying_key().to_bytes();
+        let messages = messages();
+
+        assert_eq!(
+            WalletV4R2::new(0, public_key)
+                .create_transfer(&remote, 1, 1_700_000_000, &messages)
+                .await,
+            Err(WalletError::KeyMismatch)
+        );
+        assert_eq!(
+            WalletV5R1::new(0, public_key)
+                .create_external(&remote, 1, 1_700_000_000, &WalletV5Request::new())
+                .await,
+            Err(WalletError::KeyMismatch)
+        );
+        let highload = HighloadWalletV3::new(WalletV3R2::code(), 0, public_key, 3600).unwrap();
+        assert_eq!(
+            highload
+                .create_batch(
+                    &remote,
+                    HighloadQueryId::default(),
+                    1_700_000_000,
+                    Coins::from_ton(1),
+                    &messages
+                )
+                .await,
+            Err(WalletError::KeyMismatch)
+        );
+        // The signer is not asked for anything
+        assert!(remote.signed.lock().unwrap().is_empty());
+    }
+}
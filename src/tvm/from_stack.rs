@@ -0,0 +1,306 @@
+use super::StackEntry;
+use crate::address::Address;
+use crate::cell::{ArcCell, CellSlice};
+use crate::coins::Coins;
+use crate::error::StackError;
+use num_bigint::{BigInt, Sign};
+use num_traits::ToPrimitive;
+
+/// Conversion of a whole get-method result stack into a Rust value.
+///
+/// Entries are consumed by position. Extra entries at the end of the stack are
+/// ignored so that results stay readable when a contract starts returning more values.
+/// Derive it with `#[derive(FromStack)]` on a struct whose fields implement [`FromStackEntry`].
+pub trait FromStack: Sized {
+    fn from_stack(stack: &[StackEntry]) -> Result<Self, StackError>;
+}
+
+/// Conversion of a single stack entry into a Rust value.
+pub trait FromStackEntry: Sized {
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError>;
+}
+
+/// Fetch the entry at `index` and convert it. Used by the derive macro.
+pub fn stack_field<T: FromStackEntry>(stack: &[StackEntry], index: usize) -> Result<T, StackError> {
+    let entry = stack.get(index).ok_or(StackError::MissingEntry { index })?;
+    T::from_stack_entry(entry)
+}
+
+fn unexpected(expected: &'static str, entry: &StackEntry) -> StackError {
+    StackError::UnexpectedType {
+        expected,
+        found: format!("{:?}", entry),
+    }
+}
+
+impl FromStackEntry for StackEntry {
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+        Ok(entry.clone())
+    }
+}
+
+impl FromStackEntry for BigInt {
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+        entry
+            .as_int()
+            .cloned()
+            .ok_or_else(|| unexpected("int", entry))
+    }
+}
+
+macro_rules! impl_from_stack_entry_for_primitive {
+    ($($ty:ty => $conv:ident),* $(,)?) => {
+        $(
+            impl FromStackEntry for $ty {
+                fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+                    let value = entry.as_int().ok_or_else(|| unexpected("int", entry))?;
+                    value
+                        .$conv()
+                        .ok_or_else(|| StackError::IntegerOverflow(value.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_stack_entry_for_primitive!(
+    i8 => to_i8,
+    i16 => to_i16,
+    i32 => to_i32,
+    i64 => to_i64,
+    i128 => to_i128,
+    u8 => to_u8,
+    u16 => to_u16,
+    u32 => to_u32,
+    u64 => to_u64,
+    u128 => to_u128,
+);
+
+impl FromStackEntry for bool {
+    /// TVM represents `true` as `-1` and `false` as `0`; any non-zero value is treated as `true`.
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+        let value = entry.as_int().ok_or_else(|| unexpected("int", entry))?;
+        Ok(value.sign() != Sign::NoSign)
+    }
+}
+
+impl FromStackEntry for Coins {
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+        u128::from_stack_entry(entry).map(Coins::from_nanotons)
+    }
+}
+
+impl FromStackEntry for ArcCell {
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+        entry
+            .as_cell()
+            .cloned()
+            .ok_or_else(|| unexpected("cell", entry))
+    }
+}
+
+impl FromStackEntry for CellSlice {
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+        ArcCell::from_stack_entry(entry).map(CellSlice::new)
+    }
+}
+
+impl FromStackEntry for Address {
+    /// Reads a `MsgAddressInt` from a slice entry.
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+        let mut slice = CellSlice::from_stack_entry(entry)?;
+        Ok(slice.load_address()?)
+    }
+}
+
+impl<T: FromStackEntry> FromStackEntry for Option<T> {
+    /// `Null` becomes `None`, everything else is converted as `T`.
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+        if entry.is_null() {
+            Ok(None)
+        } else {
+            T::from_stack_entry(entry).map(Some)
+        }
+    }
+}
+
+impl<T: FromStackEntry> FromStackEntry for Vec<T> {
+    /// Reads every element of a tuple or list entry.
+    fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+        entry
+            .as_elements()
+            .ok_or_else(|| unexpected("tuple", entry))?
+            .iter()
+            .map(T::from_stack_entry)
+            .collect()
+    }
+}
+
+macro_rules! impl_from_stack_for_tuple {
+    ($($name:ident => $index:tt),+) => {
+        impl<$($name: FromStackEntry),+> FromStack for ($($name,)+) {
+            fn from_stack(stack: &[StackEntry]) -> Result<Self, StackError> {
+                Ok(($(stack_field::<$name>(stack, $index)?,)+))
+            }
+        }
+
+        impl<$($name: FromStackEntry),+> FromStackEntry for ($($name,)+) {
+            /// Reads a nested tuple entry.
+            fn from_stack_entry(entry: &StackEntry) -> Result<Self, StackError> {
+                let elements = entry.as_elements().ok_or_else(|| unexpected("tuple", entry))?;
+                Self::from_stack(elements)
+            }
+        }
+    };
+}
+
+impl_from_stack_for_tuple!(A => 0);
+impl_from_stack_for_tuple!(A => 0, B => 1);
+impl_from_stack_for_tuple!(A => 0, B => 1, C => 2);
+impl_from_stack_for_tuple!(A => 0, B => 1, C => 2, D => 3);
+impl_from_stack_for_tuple!(A => 0, B => 1, C => 2, D => 3, E => 4);
+impl_from_stack_for_tuple!(A => 0, B => 1, C => 2, D => 3, E => 4, F => 5);
+
+impl FromStack for Vec<StackEntry> {
+    fn from_stack(stack: &[StackEntry]) -> Result<Self, StackError> {
+        Ok(stack.to_vec())
+    }
+}
+
+/// Helper for derived impls: read a nested tuple entry as a struct.
+pub fn nested_from_stack<T: FromStack>(entry: &StackEntry) -> Result<T, StackError> {
+    let elements = entry
+        .as_elements()
+        .ok_or_else(|| unexpected("tuple", entry))?;
+    T::from_stack(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tvm::FromStack;
+
+    #[derive(Debug, PartialEq, FromStack)]
+    struct Named {
+        seqno: u32,
+        active: bool,
+        owner: Option<Address>,
+    }
+
+    #[derive(Debug, PartialEq, FromStack)]
+    struct Tuple(u64, i8);
+
+    #[derive(Debug, PartialEq, FromStack)]
+    struct Nested {
+        id: u8,
+        pair: Tuple,
+    }
+
+    #[derive(Debug, PartialEq, FromStack)]
+    struct Generic<T, U = u8>
+    where
+        U: Copy,
+    {
+        value: T,
+        items: Vec<U>,
+    }
+
+    #[derive(Debug, PartialEq, FromStack)]
+    struct Unit;
+
+    fn int(value: i64) -> StackEntry {
+        StackEntry::Int(BigInt::from(value))
+    }
+
+    #[test]
+    fn named_struct() {
+        let stack = [int(7), int(-1), StackEntry::Null];
+        assert_eq!(
+            Named::from_stack(&stack).unwrap(),
+            Named {
+                seqno: 7,
+                active: true,
+                owner: None,
+            }
+        );
+    }
+
+    #[test]
+    fn tuple_struct_ignores_extra_entries() {
+        let stack = [int(1), int(-2), int(3)];
+        assert_eq!(Tuple::from_stack(&stack).unwrap(), Tuple(1, -2));
+    }
+
+    #[test]
+    fn nested_struct() {
+        let stack = [int(1), StackEntry::Tuple(vec![int(2), int(3)])];
+        assert_eq!(
+            Nested::from_stack(&stack).unwrap(),
+            Nested {
+                id: 1,
+                pair: Tuple(2, 3),
+            }
+        );
+    }
+
+    #[test]
+    fn generic_struct() {
+        let stack = [int(-5), StackEntry::List(vec![int(1), int(2)])];
+        assert_eq!(
+            Generic::<i32>::from_stack(&stack).unwrap(),
+            Generic {
+                value: -5,
+                items: vec![1, 2],
+            }
+        );
+        let nested = StackEntry::Tuple(stack.to_vec());
+        assert_eq!(
+            Generic::<Option<i8>, u64>::from_stack_entry(&nested).unwrap(),
+            Generic {
+                value: Some(-5),
+                items: vec![1, 2],
+            }
+        );
+    }
+
+    #[test]
+    fn unit_struct() {
+        assert_eq!(Unit::from_stack(&[]).unwrap(), Unit);
+        assert_eq!(Unit::from_stack(&[int(1)]).unwrap(), Unit);
+    }
+
+    #[test]
+    fn short_stack() {
+        assert_eq!(
+            Named::from_stack(&[int(7)]),
+            Err(StackError::MissingEntry { index: 1 })
+        );
+        assert_eq!(
+            Nested::from_stack(&[int(1), StackEntry::Tuple(vec![int(2)])]),
+            Err(StackError::MissingEntry { index: 1 })
+        );
+    }
+
+    #[test]
+    fn decode_failures() {
+        let stack = [StackEntry::Null, int(0)];
+        assert!(matches!(
+            Tuple::from_stack(&stack),
+            Err(StackError::UnexpectedType {
+                expected: "int",
+                ..
+            })
+        ));
+        assert_eq!(
+            Tuple::from_stack(&[int(-1), int(0)]),
+            Err(StackError::IntegerOverflow("-1".to_string()))
+        );
+        assert!(matches!(
+            Nested::from_stack(&[int(1), int(2)]),
+            Err(StackError::UnexpectedType {
+                expected: "tuple",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,6 @@
+mod from_stack;
+mod stack;
+
+pub use self::from_stack::{nested_from_stack, stack_field, FromStack, FromStackEntry};
+pub use self::stack::StackEntry;
+pub use toncenter_derive::FromStack;
@@ -0,0 +1,494 @@
+use crate::address::Address;
+use crate::cell::{ArcCell, Cell, CellBuilder};
+use crate::error::StackError;
+use num_bigint::{BigInt, Sign};
+use num_traits::Num;
+use serde::de::Error as DeError;
+use serde::ser::Error as SerError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Single value of a TVM stack.
+///
+/// Serializes to the format expected by `runGetMethod` and deserializes from every
+/// shape toncenter returns, both the short `["num", "0x.."]` pairs and the
+/// tonlib `tvm.stackEntry*` objects used inside tuples and lists.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StackEntry {
+    /// 257-bit signed integer. Larger values are rejected when serialized.
+    Int(BigInt),
+    Cell(ArcCell),
+    Slice(ArcCell),
+    Builder(ArcCell),
+    Tuple(Vec<StackEntry>),
+    List(Vec<StackEntry>),
+    Null,
+}
+
+impl StackEntry {
+    pub fn as_int(&self) -> Option<&BigInt> {
+        match self {
+            StackEntry::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Cell behind a cell, slice or builder entry. Toncenter returns slices as cells.
+    pub fn as_cell(&self) -> Option<&ArcCell> {
+        match self {
+            StackEntry::Cell(cell) | StackEntry::Slice(cell) | StackEntry::Builder(cell) => {
+                Some(cell)
+            }
+            _ => None,
+        }
+    }
+
+    /// Elements of a tuple or list entry. `Null` is treated as an empty list.
+    pub fn as_elements(&self) -> Option<&[StackEntry]> {
+        match self {
+            StackEntry::Tuple(elements) | StackEntry::List(elements) => Some(elements),
+            StackEntry::Null => Some(&[]),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, StackEntry::Null)
+    }
+
+    /// Render the entry in `runGetMethod` request format.
+    pub fn to_request_json(&self) -> Result<Value, StackError> {
+        Ok(match self {
+            StackEntry::Int(value) => json!(["num", format_hex(value)?]),
+            StackEntry::Cell(cell) | StackEntry::Builder(cell) => {
+                json!(["tvm.Cell", boc_b64(cell)?])
+            }
+            StackEntry::Slice(cell) => json!(["tvm.Slice", boc_b64(cell)?]),
+            StackEntry::Tuple(elements) => {
+                json!(["tuple", { "@type": "tvm.tuple", "elements": tonlib_elements(elements)? }])
+            }
+            StackEntry::List(elements) => {
+                json!(["list", { "@type": "tvm.list", "elements": tonlib_elements(elements)? }])
+            }
+            StackEntry::Null => json!(["list", { "@type": "tvm.list", "elements": [] }]),
+        })
+    }
+
+    /// Render the entry in `runGetMethod` response format.
+    ///
+    /// Cells are written with their `bytes` only, the decoded `object` toncenter adds is not kept.
+    pub fn to_response_json(&self) -> Result<Value, StackError> {
+        Ok(match self {
+            StackEntry::Cell(cell) => json!(["cell", { "bytes": boc_b64(cell)? }]),
+            StackEntry::Slice(cell) => json!(["slice", { "bytes": boc_b64(cell)? }]),
+            StackEntry::Builder(cell) => json!(["builder", { "bytes": boc_b64(cell)? }]),
+            StackEntry::Null => json!(["null", null]),
+            other => other.to_request_json()?,
+        })
+    }
+
+    /// Render the entry as a tonlib `tvm.stackEntry*` object.
+    pub fn to_tonlib_json(&self) -> Result<Value, StackError> {
+        Ok(match self {
+            StackEntry::Int(value) => json!({
+                "@type": "tvm.stackEntryNumber",
+                "number": { "@type": "tvm.numberDecimal", "number": check_int(value)?.to_string() }
+            }),
+            StackEntry::Cell(cell) | StackEntry::Builder(cell) => json!({
+                "@type": "tvm.stackEntryCell",
+                "cell": { "@type": "tvm.cell", "bytes": boc_b64(cell)? }
+            }),
+            StackEntry::Slice(cell) => json!({
+                "@type": "tvm.stackEntrySlice",
+                "slice": { "@type": "tvm.slice", "bytes": boc_b64(cell)? }
+            }),
+            StackEntry::Tuple(elements) => json!({
+                "@type": "tvm.stackEntryTuple",
+                "tuple": { "@type": "tvm.tuple", "elements": tonlib_elements(elements)? }
+            }),
+            StackEntry::List(elements) => json!({
+                "@type": "tvm.stackEntryList",
+                "list": { "@type": "tvm.list", "elements": tonlib_elements(elements)? }
+            }),
+            StackEntry::Null => json!({
+                "@type": "tvm.stackEntryList",
+                "list": { "@type": "tvm.list", "elements": [] }
+            }),
+        })
+    }
+
+    /// Parse an entry from any of the shapes toncenter uses.
+    pub fn from_json(value: &Value) -> Result<Self, StackError> {
+        match value {
+            Value::Array(pair) if pair.len() == 2 => {
+                let kind = pair[0].as_str().ok_or_else(|| {
+                    StackError::InvalidEntry(format!("invalid stack entry type: {}", pair[0]))
+                })?;
+                Self::from_pair(kind, &pair[1])
+            }
+            Value::Object(_) => Self::from_tonlib_json(value),
+            Value::Null => Ok(StackEntry::Null),
+            other => Err(invalid(format!("invalid stack entry: {}", other))),
+        }
+    }
+
+    fn from_pair(kind: &str, value: &Value) -> Result<Self, StackError> {
+        match kind {
+            "num" | "number" | "int" => Ok(StackEntry::Int(parse_number(value)?)),
+            "cell" | "tvm.Cell" => Ok(StackEntry::Cell(parse_cell(value)?)),
+            "slice" | "tvm.Slice" => Ok(StackEntry::Slice(parse_cell(value)?)),
+            "builder" | "tvm.Builder" => Ok(StackEntry::Builder(parse_cell(value)?)),
+            "tuple" | "tvm.Tuple" => Ok(StackEntry::Tuple(parse_elements(value)?)),
+            "list" | "tvm.List" => Ok(StackEntry::List(parse_elements(value)?)),
+            "null" => Ok(StackEntry::Null),
+            other => Err(invalid(format!("unsupported stack entry type: {}", other))),
+        }
+    }
+
+    fn from_tonlib_json(value: &Value) -> Result<Self, StackError> {
+        let kind = value["@type"].as_str().unwrap_or_default();
+        match kind {
+            "tvm.stackEntryNumber" => Ok(StackEntry::Int(parse_number(&value["number"])?)),
+            "tvm.stackEntryCell" => Ok(StackEntry::Cell(parse_cell(&value["cell"])?)),
+            "tvm.stackEntrySlice" => Ok(StackEntry::Slice(parse_cell(&value["slice"])?)),
+            "tvm.stackEntryTuple" => Ok(StackEntry::Tuple(parse_elements(&value["tuple"])?)),
+            "tvm.stackEntryList" => Ok(StackEntry::List(parse_elements(&value["list"])?)),
+            "tvm.stackEntryUnsupported" => Ok(StackEntry::Null),
+            other => Err(invalid(format!("unsupported stack entry type: {}", other))),
+        }
+    }
+}
+
+fn invalid(message: String) -> StackError {
+    StackError::InvalidEntry(message)
+}
+
+/// Check that `value` fits into a TVM integer: -2^256 <= value < 2^256.
+fn check_int(value: &BigInt) -> Result<&BigInt, StackError> {
+    let fits = value.bits() <= 256
+        || value.sign() == Sign::Minus && value.bits() == 257 && is_power_of_two(value);
+    if fits {
+        Ok(value)
+    } else {
+        Err(StackError::IntegerOverflow(value.to_string()))
+    }
+}
+
+fn is_power_of_two(value: &BigInt) -> bool {
+    let magnitude = value.magnitude();
+    magnitude.trailing_zeros() == Some(magnitude.bits() - 1)
+}
+
+fn format_hex(value: &BigInt) -> Result<String, StackError> {
+    let magnitude = check_int(value)?.magnitude().to_str_radix(16);
+    Ok(if value.sign() == Sign::Minus {
+        format!("-0x{}", magnitude)
+    } else {
+        format!("0x{}", magnitude)
+    })
+}
+
+fn boc_b64(cell: &ArcCell) -> Result<String, StackError> {
+    Ok(cell.to_boc_b64()?)
+}
+
+fn tonlib_elements(elements: &[StackEntry]) -> Result<Vec<Value>, StackError> {
+    elements.iter().map(StackEntry::to_tonlib_json).collect()
+}
+
+fn parse_number(value: &Value) -> Result<BigInt, StackError> {
+    let number = match value {
+        Value::String(s) => {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, s.as_str()),
+            };
+            let parsed = match digits
+                .strip_prefix("0x")
+                .or_else(|| digits.strip_prefix("0X"))
+            {
+                Some(hex) => BigInt::from_str_radix(hex, 16),
+                None => BigInt::from_str_radix(digits, 10),
+            }
+            .map_err(|e| invalid(format!("invalid number {}: {}", s, e)))?;
+            if negative {
+                -parsed
+            } else {
+                parsed
+            }
+        }
+        Value::Number(n) => n
+            .as_i64()
+            .map(BigInt::from)
+            .or_else(|| n.as_u64().map(BigInt::from))
+            .ok_or_else(|| invalid(format!("invalid number: {}", n)))?,
+        Value::Object(_) if value.get("number").is_some() => return parse_number(&value["number"]),
+        other => return Err(invalid(format!("invalid number: {}", other))),
+    };
+    check_int(&number)?;
+    Ok(number)
+}
+
+fn parse_cell(value: &Value) -> Result<ArcCell, StackError> {
+    let bytes = match value {
+        Value::String(s) => s.as_str(),
+        Value::Object(_) => value["bytes"]
+            .as_str()
+            .ok_or_else(|| invalid(format!("cell without bytes: {}", value)))?,
+        other => return Err(invalid(format!("invalid cell: {}", other))),
+    };
+    Ok(Cell::from_boc_b64(bytes).map(Arc::new)?)
+}
+
+fn parse_elements(value: &Value) -> Result<Vec<StackEntry>, StackError> {
+    let elements = match value {
+        Value::Array(elements) => elements,
+        Value::Object(_) => value["elements"]
+            .as_array()
+            .ok_or_else(|| invalid(format!("tuple without elements: {}", value)))?,
+        Value::Null => return Ok(Vec::new()),
+        other => return Err(invalid(format!("invalid tuple: {}", other))),
+    };
+    elements.iter().map(StackEntry::from_json).collect()
+}
+
+impl Serialize for StackEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_request_json()
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StackEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        StackEntry::from_json(&value).map_err(D::Error::custom)
+    }
+}
+
+impl From<BigInt> for StackEntry {
+    fn from(value: BigInt) -> Self {
+        StackEntry::Int(value)
+    }
+}
+
+impl From<i64> for StackEntry {
+    fn from(value: i64) -> Self {
+        StackEntry::Int(BigInt::from(value))
+    }
+}
+
+impl From<u64> for StackEntry {
+    fn from(value: u64) -> Self {
+        StackEntry::Int(BigInt::from(value))
+    }
+}
+
+impl From<ArcCell> for StackEntry {
+    fn from(cell: ArcCell) -> Self {
+        StackEntry::Cell(cell)
+    }
+}
+
+impl From<Cell> for StackEntry {
+    fn from(cell: Cell) -> Self {
+        StackEntry::Cell(Arc::new(cell))
+    }
+}
+
+impl TryFrom<Address> for StackEntry {
+    type Error = StackError;
+
+    /// Slice holding the address, as get methods taking an address expect it.
+    fn try_from(address: Address) -> Result<Self, Self::Error> {
+        let mut builder = CellBuilder::new();
+        builder.store_address(&address)?;
+        Ok(StackEntry::Slice(Arc::new(builder.build()?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(value: u32) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(value).unwrap();
+        builder.build().unwrap().to_arc()
+    }
+
+    fn b64(value: u32) -> String {
+        cell(value).to_boc_b64().unwrap()
+    }
+
+    fn parse(value: Value) -> Result<StackEntry, StackError> {
+        StackEntry::from_json(&value)
+    }
+
+    fn int(value: i64) -> StackEntry {
+        StackEntry::Int(BigInt::from(value))
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(parse(json!(["num", "0x1f"])).unwrap(), int(31));
+        assert_eq!(parse(json!(["num", "-0x1F"])).unwrap(), int(-31));
+        assert_eq!(parse(json!(["num", "31"])).unwrap(), int(31));
+        assert_eq!(parse(json!(["int", "-31"])).unwrap(), int(-31));
+        assert_eq!(parse(json!(["number", 31])).unwrap(), int(31));
+        assert_eq!(
+            parse(json!(["num", "0x8000000000000000000000000000000000"])).unwrap(),
+            StackEntry::Int(BigInt::from(1u8) << 135)
+        );
+        assert!(matches!(
+            parse(json!(["num", "0xzz"])),
+            Err(StackError::InvalidEntry(_))
+        ));
+    }
+
+    #[test]
+    fn parses_cells() {
+        let object = json!({ "bytes": b64(1), "object": { "data": { "b64": "AAAAAQ==", "len": 32 }, "refs": [] } });
+        assert_eq!(
+            parse(json!(["cell", object])).unwrap(),
+            StackEntry::Cell(cell(1))
+        );
+        assert_eq!(
+            parse(json!(["slice", { "bytes": b64(2) }])).unwrap(),
+            StackEntry::Slice(cell(2))
+        );
+        assert_eq!(
+            parse(json!(["builder", { "bytes": b64(3) }])).unwrap(),
+            StackEntry::Builder(cell(3))
+        );
+        assert_eq!(
+            parse(json!(["tvm.Slice", b64(4)])).unwrap(),
+            StackEntry::Slice(cell(4))
+        );
+        assert!(matches!(
+            parse(json!(["cell", { "bytes": "AAAA" }])),
+            Err(StackError::Cell(_))
+        ));
+        assert!(matches!(
+            parse(json!(["cell", {}])),
+            Err(StackError::InvalidEntry(_))
+        ));
+    }
+
+    #[test]
+    fn parses_nested_tuples_and_lists() {
+        let value = json!(["tuple", {
+            "@type": "tvm.tuple",
+            "elements": [
+                { "@type": "tvm.stackEntryNumber", "number": { "@type": "tvm.numberDecimal", "number": "-7" } },
+                { "@type": "tvm.stackEntryCell", "cell": { "@type": "tvm.cell", "bytes": b64(5) } },
+                { "@type": "tvm.stackEntrySlice", "slice": { "@type": "tvm.slice", "bytes": b64(6) } },
+                { "@type": "tvm.stackEntryList", "list": { "@type": "tvm.list", "elements": [
+                    { "@type": "tvm.stackEntryTuple", "tuple": { "@type": "tvm.tuple", "elements": [
+                        { "@type": "tvm.stackEntryNumber", "number": { "number": "1" } }
+                    ] } }
+                ] } },
+                { "@type": "tvm.stackEntryUnsupported" }
+            ]
+        }]);
+        assert_eq!(
+            parse(value).unwrap(),
+            StackEntry::Tuple(vec![
+                int(-7),
+                StackEntry::Cell(cell(5)),
+                StackEntry::Slice(cell(6)),
+                StackEntry::List(vec![StackEntry::Tuple(vec![int(1)])]),
+                StackEntry::Null,
+            ])
+        );
+        assert_eq!(
+            parse(json!(["list", { "@type": "tvm.list", "elements": [] }])).unwrap(),
+            StackEntry::List(Vec::new())
+        );
+        assert!(matches!(
+            parse(json!({ "@type": "tvm.stackEntryFloat" })),
+            Err(StackError::InvalidEntry(_))
+        ));
+    }
+
+    #[test]
+    fn parses_null() {
+        assert_eq!(parse(json!(["null", null])).unwrap(), StackEntry::Null);
+        assert_eq!(parse(Value::Null).unwrap(), StackEntry::Null);
+        assert!(matches!(
+            parse(json!(["float", 1])),
+            Err(StackError::InvalidEntry(_))
+        ));
+        assert!(matches!(parse(json!(1)), Err(StackError::InvalidEntry(_))));
+    }
+
+    #[test]
+    fn request_json() {
+        let stack = [
+            int(-255),
+            StackEntry::Cell(cell(1)),
+            StackEntry::Slice(cell(2)),
+            StackEntry::Tuple(vec![int(3), StackEntry::Null]),
+            StackEntry::Null,
+        ];
+        let json = stack
+            .iter()
+            .map(StackEntry::to_request_json)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            json,
+            [
+                json!(["num", "-0xff"]),
+                json!(["tvm.Cell", b64(1)]),
+                json!(["tvm.Slice", b64(2)]),
+                json!(["tuple", { "@type": "tvm.tuple", "elements": [
+                    { "@type": "tvm.stackEntryNumber", "number": { "@type": "tvm.numberDecimal", "number": "3" } },
+                    { "@type": "tvm.stackEntryList", "list": { "@type": "tvm.list", "elements": [] } }
+                ] }]),
+                json!(["list", { "@type": "tvm.list", "elements": [] }]),
+            ]
+        );
+        assert_eq!(serde_json::to_value(&stack[0]).unwrap(), json[0]);
+        // `Null` is sent as an empty list, everything else reads back as it was
+        for (entry, value) in stack.iter().zip(&json).take(3) {
+            assert_eq!(&StackEntry::from_json(value).unwrap(), entry);
+        }
+    }
+
+    #[test]
+    fn enforces_int_range() {
+        let bound: BigInt = BigInt::from(1u8) << 256usize;
+        let max: BigInt = &bound - BigInt::from(1u8);
+        let min: BigInt = -bound;
+        assert!(StackEntry::Int(max.clone()).to_request_json().is_ok());
+        assert!(StackEntry::Int(min.clone()).to_request_json().is_ok());
+        assert!(matches!(
+            StackEntry::Int(&max + BigInt::from(1u8)).to_request_json(),
+            Err(StackError::IntegerOverflow(_))
+        ));
+        assert!(matches!(
+            StackEntry::Int(&min - BigInt::from(1u8)).to_tonlib_json(),
+            Err(StackError::IntegerOverflow(_))
+        ));
+        let too_large = format!("0x1{}", "0".repeat(64));
+        assert!(matches!(
+            parse(json!(["num", too_large])),
+            Err(StackError::IntegerOverflow(_))
+        ));
+        assert!(serde_json::to_value(StackEntry::Int(max + BigInt::from(1u8))).is_err());
+    }
+
+    #[test]
+    fn address_slice() {
+        let address = Address::new(0, [0x5a; 32]);
+        let entry = StackEntry::try_from(address).unwrap();
+        let StackEntry::Slice(cell) = &entry else {
+            panic!("expected a slice");
+        };
+        assert_eq!(cell.bit_len(), 267);
+        assert_eq!(cell.parser().load_address().unwrap(), address);
+    }
+}
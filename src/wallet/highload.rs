@@ -0,0 +1,474 @@
+use super::{check_signer, out_list, WalletMessage};
+use crate::address::Address;
+use crate::cell::{ArcCell, CellBuilder};
+use crate::client::ApiClientV2;
+use crate::coins::Coins;
+use crate::error::{ToncenterError, WalletError};
+use crate::message::{ExternalMessage, InternalMessage, SendMode, StateInit};
+use crate::signer::{SignatureLayout, Signer, UnsignedBody, UnsignedExternalMessage};
+use crate::tvm::StackEntry;
+use num_bigint::BigInt;
+use std::sync::Arc;
+
+/// Default `subwallet_id` of highload wallets v3.
+pub const HIGHLOAD_DEFAULT_SUBWALLET_ID: u32 = 0x10ad;
+
+const INTERNAL_TRANSFER_OP: u32 = 0xae42e5a4;
+const MAX_TIMEOUT: u32 = (1 << 22) - 1;
+
+/// Query id of a highload wallet v3: `shift:uint13 bit_number:uint10`.
+///
+/// Each id can be used once within the wallet timeout. Ids are ordered, so a sender
+/// can persist the last one used and continue with [`Self::next`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct HighloadQueryId {
+    shift: u16,
+    bit_number: u16,
+}
+
+impl HighloadQueryId {
+    pub const MAX_SHIFT: u16 = (1 << 13) - 1;
+    pub const MAX_BIT_NUMBER: u16 = 1022;
+
+    pub fn new(shift: u16, bit_number: u16) -> Result<Self, WalletError> {
+        if shift > Self::MAX_SHIFT || bit_number > Self::MAX_BIT_NUMBER {
+            return Err(WalletError::InvalidQueryId(
+                ((shift as u32) << 10) | bit_number as u32,
+            ));
+        }
+        Ok(Self { shift, bit_number })
+    }
+
+    pub fn from_query_id(query_id: u32) -> Result<Self, WalletError> {
+        if query_id >> 23 != 0 {
+            return Err(WalletError::InvalidQueryId(query_id));
+        }
+        Self::new((query_id >> 10) as u16, (query_id & 1023) as u16)
+    }
+
+    pub fn query_id(&self) -> u32 {
+        ((self.shift as u32) << 10) | self.bit_number as u32
+    }
+
+    pub fn shift(&self) -> u16 {
+        self.shift
+    }
+
+    pub fn bit_number(&self) -> u16 {
+        self.bit_number
+    }
+
+    /// The following query id, `None` once all of them are used.
+    pub fn next(&self) -> Option<Self> {
+        if self.bit_number < Self::MAX_BIT_NUMBER {
+            Some(Self {
+                shift: self.shift,
+                bit_number: self.bit_number + 1,
+            })
+        } else if self.shift < Self::MAX_SHIFT {
+            Some(Self {
+                shift: self.shift + 1,
+                bit_number: 0,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Highload wallet v3, made for sending many messages in parallel without a seqno.
+///
+/// The contract code is not bundled with the crate. Pass the compiled
+/// `highload-wallet-v3` code, e.g. fetched from an already deployed wallet, and compare
+/// [`Self::address`] with that wallet's address before sending funds to a new one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighloadWalletV3 {
+    code: ArcCell,
+    workchain: i32,
+    public_key: [u8; 32],
+    subwallet_id: u32,
+    timeout: u32,
+}
+
+impl HighloadWalletV3 {
+    /// Maximum number of messages sent by one internal transfer, leaving room for the
+    /// `set_code` action the wallet appends.
+    pub const MAX_ACTIONS: usize = 254;
+
+    /// Wallet with the default `subwallet_id`. `timeout` is in seconds.
+    pub fn new(
+        code: ArcCell,
+        workchain: i32,
+        public_key: [u8; 32],
+        timeout: u32,
+    ) -> Result<Self, WalletError> {
+        Self::with_subwallet_id(
+            code,
+            workchain,
+            public_key,
+            HIGHLOAD_DEFAULT_SUBWALLET_ID,
+            timeout,
+        )
+    }
+
+    pub fn with_subwallet_id(
+        code: ArcCell,
+        workchain: i32,
+        public_key: [u8; 32],
+        subwallet_id: u32,
+        timeout: u32,
+    ) -> Result<Self, WalletError> {
+        if timeout > MAX_TIMEOUT {
+            return Err(WalletError::InvalidTimeout(timeout));
+        }
+        Ok(Self {
+            code,
+            workchain,
+            public_key,
+            subwallet_id,
+            timeout,
+        })
+    }
+
+    pub fn code(&self) -> &ArcCell {
+        &self.code
+    }
+
+    pub fn workchain(&self) -> i32 {
+        self.workchain
+    }
+
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    pub fn subwallet_id(&self) -> u32 {
+        self.subwallet_id
+    }
+
+    pub fn timeout(&self) -> u32 {
+        self.timeout
+    }
+
+    /// Initial data: `public_key:uint256 subwallet_id:uint32 old_queries:(HashmapE 13 ^Cell)
+    /// queries:(HashmapE 13 ^Cell) last_clean_time:uint64 timeout:uint22`.
+    pub fn data(&self) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_bytes(&self.public_key)
+            .and_then(|b| b.store_u32(self.subwallet_id))
+            .and_then(|b| b.store_bit(false))
+            .and_then(|b| b.store_bit(false))
+            .and_then(|b| b.store_u64(0))
+            .and_then(|b| b.store_uint(22, self.timeout as u64))
+            .expect("wallet data fits into a cell");
+        Arc::new(builder.build().expect("wallet data fits into a cell"))
+    }
+
+    pub fn state_init(&self) -> ArcCell {
+        StateInit::new(self.code.clone(), self.data())
+            .to_cell()
+            .expect("state init fits into a cell")
+    }
+
+    pub fn address(&self) -> Address {
+        Address::new(self.workchain, self.state_init().repr_hash())
+    }
+
+    /// Prepare an external message making the wallet send one message, to be signed elsewhere.
+    ///
+    /// `created_at` must be in the past but not older than the timeout. The state init
+    /// is never attached, use [`Self::prepare_deploy`] while the wallet is not deployed.
+    pub fn prepare_external(
+        &self,
+        query_id: HighloadQueryId,
+        created_at: u64,
+        message: &WalletMessage,
+    ) -> Result<UnsignedExternalMessage, WalletError> {
+        // subwallet_id:uint32 message:^Cell mode:uint8 query_id:uint23
+        // created_at:uint64 timeout:uint22
+        let mut inner = CellBuilder::new();
+        inner
+            .store_u32(self.subwallet_id)?
+            .store_reference(message.message.to_cell()?)?
+            .store_u8(message.mode.bits())?
+            .store_uint(23, query_id.query_id() as u64)?
+            .store_u64(created_at)?
+            .store_uint(22, self.timeout as u64)?;
+        Ok(UnsignedExternalMessage {
+            destination: self.address(),
+            state_init: None,
+            body: UnsignedBody::new(Arc::new(inner.build()?), SignatureLayout::Reference),
+        })
+    }
+
+    /// Build a signed external message making the wallet send one message.
+    ///
+    /// Fails with [`WalletError::KeyMismatch`] if `signer` does not hold the wallet's key.
+    pub async fn create_external<S: Signer>(
+        &self,
+        signer: &S,
+        query_id: HighloadQueryId,
+        created_at: u64,
+        message: &WalletMessage,
+    ) -> Result<ExternalMessage, WalletError> {
+        check_signer(&self.public_key, signer)?;
+        self.prepare_external(query_id, created_at, message)?
+            .sign(signer)
+            .await
+    }
+
+    /// Prepare an external message deploying the wallet and making it send one message,
+    /// to be signed elsewhere.
+    ///
+    /// The wallet has no seqno telling whether it is deployed, so the state init is only
+    /// attached here. It is ignored once the wallet is active.
+    pub fn prepare_deploy(
+        &self,
+        query_id: HighloadQueryId,
+        created_at: u64,
+        message: &WalletMessage,
+    ) -> Result<UnsignedExternalMessage, WalletError> {
+        let mut external = self.prepare_external(query_id, created_at, message)?;
+        external.state_init = Some(self.state_init());
+        Ok(external)
+    }
+
+    /// Build a signed external message deploying the wallet and making it send one message.
+    ///
+    /// Fails with [`WalletError::KeyMismatch`] if `signer` does not hold the wallet's key.
+    pub async fn create_deploy<S: Signer>(
+        &self,
+        signer: &S,
+        query_id: HighloadQueryId,
+        created_at: u64,
+        message: &WalletMessage,
+    ) -> Result<ExternalMessage, WalletError> {
+        check_signer(&self.public_key, signer)?;
+        self.prepare_deploy(query_id, created_at, message)?
+            .sign(signer)
+            .await
+    }
+
+    /// Prepare an external message sending any number of messages at once, to be signed elsewhere.
+    ///
+    /// The wallet sends itself an internal transfer carrying the action list, chained
+    /// into further transfers every [`Self::MAX_ACTIONS`] messages. `value` pays for
+    /// processing each of these transfers and returns to the wallet.
+    pub fn prepare_batch(
+        &self,
+        query_id: HighloadQueryId,
+        created_at: u64,
+        value: Coins,
+        messages: &[WalletMessage],
+    ) -> Result<UnsignedExternalMessage, WalletError> {
+        let transfer = self.internal_transfer(query_id, value, messages)?;
+        let message = WalletMessage::new(SendMode::PAY_FEES_SEPARATELY, transfer);
+        self.prepare_external(query_id, created_at, &message)
+    }
+
+    /// Build a signed external message sending any number of messages at once.
+    pub async fn create_batch<S: Signer>(
+        &self,
+        signer: &S,
+        query_id: HighloadQueryId,
+        created_at: u64,
+        value: Coins,
+        messages: &[WalletMessage],
+    ) -> Result<ExternalMessage, WalletError> {
+        check_signer(&self.public_key, signer)?;
+        self.prepare_batch(query_id, created_at, value, messages)?
+            .sign(signer)
+            .await
+    }
+
+    /// `internal_transfer#ae42e5a4 query_id:uint64 actions:^OutList` sent to the wallet itself.
+    fn internal_transfer(
+        &self,
+        query_id: HighloadQueryId,
+        value: Coins,
+        messages: &[WalletMessage],
+    ) -> Result<InternalMessage, WalletError> {
+        let actions = if messages.len() > Self::MAX_ACTIONS {
+            let (batch, rest) = messages.split_at(Self::MAX_ACTIONS - 1);
+            let next = self.internal_transfer(query_id, value, rest)?;
+            let mut batch = batch.to_vec();
+            batch.push(WalletMessage::new(SendMode::PAY_FEES_SEPARATELY, next));
+            out_list(&batch)?
+        } else {
+            out_list(messages)?
+        };
+
+        let mut body = CellBuilder::new();
+        body.store_u32(INTERNAL_TRANSFER_OP)?
+            .store_u64(query_id.query_id() as u64)?
+            .store_reference(actions)?;
+        Ok(InternalMessage::transfer(self.address(), value, false)
+            .with_body(Arc::new(body.build()?)))
+    }
+
+    /// Check with the `processed?` get method whether the query id was already used.
+    ///
+    /// With `need_clean` the wallet first forgets queries older than the timeout, as it
+    /// would when processing the next external message.
+    pub async fn is_processed(
+        &self,
+        client: &ApiClientV2,
+        query_id: HighloadQueryId,
+        need_clean: bool,
+    ) -> Result<bool, ToncenterError> {
+        let stack = [
+            StackEntry::Int(BigInt::from(query_id.query_id())),
+            StackEntry::Int(BigInt::from(-(need_clean as i32))),
+        ];
+        let (processed,): (bool,) = client
+            .run_get_method_typed(&self.address().to_string(), "processed?", &stack)
+            .await?;
+        Ok(processed)
+    }
+
+    /// Time of the last cleanup of processed queries, from the `get_last_clean_time` get method.
+    pub async fn last_clean_time(&self, client: &ApiClientV2) -> Result<u64, ToncenterError> {
+        let (time,): (u64,) = client
+            .run_get_method_typed(&self.address().to_string(), "get_last_clean_time", &[])
+            .await?;
+        Ok(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, SigningKey, Verifier};
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[1; 32])
+    }
+
+    /// Stand-in for the contract code: the address only depends on its hash.
+    fn code() -> ArcCell {
+        let mut builder = CellBuilder::new();
+        builder.store_bytes(b"highload").unwrap();
+        builder.build().unwrap().to_arc()
+    }
+
+    fn wallet() -> HighloadWalletV3 {
+        HighloadWalletV3::new(code(), 0, Signer::public_key(&signing_key()), 3600).unwrap()
+    }
+
+    fn transfer(value: u64) -> WalletMessage {
+        WalletMessage::new(
+            SendMode::PAY_FEES_SEPARATELY,
+            InternalMessage::transfer(
+                wallet().address(),
+                Coins::from_nanotons(value as u128),
+                false,
+            ),
+        )
+    }
+
+    #[test]
+    fn address() {
+        assert_eq!(
+            wallet().address().to_raw_string(),
+            "0:1c94ab102e569a373c7427b59a37dd1cf9f66500c39b70eed9bd667d18323cbd"
+        );
+    }
+
+    #[test]
+    fn query_ids() {
+        let id = HighloadQueryId::from_query_id((5 << 10) | 1022).unwrap();
+        assert_eq!((id.shift(), id.bit_number()), (5, 1022));
+        assert_eq!(id.next(), Some(HighloadQueryId::new(6, 0).unwrap()));
+        let last = HighloadQueryId::new(HighloadQueryId::MAX_SHIFT, 1022).unwrap();
+        assert_eq!(last.next(), None);
+        assert!(HighloadQueryId::new(0, 1023).is_err());
+        assert!(HighloadQueryId::from_query_id(1 << 23).is_err());
+    }
+
+    #[test]
+    fn rejects_long_timeout() {
+        let result = HighloadWalletV3::new(code(), 0, [0; 32], 1 << 22);
+        assert_eq!(result, Err(WalletError::InvalidTimeout(1 << 22)));
+    }
+
+    /// Messages of an internal transfer's action list, in sending order.
+    fn actions(transfer: &InternalMessage) -> Vec<ArcCell> {
+        let mut body = transfer.body.as_ref().unwrap().parser();
+        assert_eq!(body.load_u32().unwrap(), INTERNAL_TRANSFER_OP);
+        body.load_u64().unwrap();
+        let mut list = body.load_reference().unwrap();
+        let mut messages = Vec::new();
+        while list.bit_len() > 0 {
+            messages.push(list.reference(1).unwrap().clone());
+            list = list.reference(0).unwrap().clone();
+        }
+        messages.reverse();
+        messages
+    }
+
+    #[test]
+    fn chains_large_batches() {
+        let wallet = wallet();
+        let query_id = HighloadQueryId::default();
+        let value = Coins::from_ton(1);
+        let messages = (0..300).map(transfer).collect::<Vec<_>>();
+
+        let first = wallet
+            .internal_transfer(query_id, value, &messages)
+            .unwrap();
+        let first_actions = actions(&first);
+        assert_eq!(first_actions.len(), HighloadWalletV3::MAX_ACTIONS);
+        assert_eq!(first_actions[0], messages[0].message.to_cell().unwrap());
+
+        // The last action carries the transfer of the remaining messages
+        let rest = &messages[HighloadWalletV3::MAX_ACTIONS - 1..];
+        let next = wallet.internal_transfer(query_id, value, rest).unwrap();
+        assert_eq!(
+            first_actions[HighloadWalletV3::MAX_ACTIONS - 1],
+            next.to_cell().unwrap()
+        );
+        assert_eq!(actions(&next).len(), rest.len());
+    }
+
+    #[tokio::test]
+    async fn rejects_signer_with_another_key() {
+        let result = wallet()
+            .create_external(
+                &SigningKey::from_bytes(&[2; 32]),
+                HighloadQueryId::default(),
+                1_700_000_000,
+                &transfer(1),
+            )
+            .await;
+        assert_eq!(result, Err(WalletError::KeyMismatch));
+    }
+
+    #[tokio::test]
+    async fn deploys_with_first_message() {
+        let wallet = wallet();
+        let query_id = HighloadQueryId::new(2, 7).unwrap();
+        let unsigned = wallet
+            .prepare_external(query_id, 1_700_000_000, &transfer(1))
+            .unwrap();
+        assert_eq!(unsigned.state_init, None);
+
+        let message = wallet
+            .create_deploy(&signing_key(), query_id, 1_700_000_000, &transfer(1))
+            .await
+            .unwrap();
+        let state_init = message.state_init.clone().unwrap();
+        assert_eq!(state_init, wallet.state_init());
+        assert_eq!(state_init.repr_hash(), *wallet.address().hash_part());
+        assert_eq!(message.destination, wallet.address());
+
+        // signature:bits512 ^inner, the same request as without the state init
+        let mut body = message.body.parser();
+        let signature = Signature::from_slice(&body.load_bytes(64).unwrap()).unwrap();
+        assert_eq!(body.load_reference().unwrap(), *unsigned.body.payload());
+        signing_key()
+            .verifying_key()
+            .verify(&unsigned.hash(), &signature)
+            .unwrap();
+    }
+}
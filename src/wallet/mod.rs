@@ -0,0 +1,131 @@
+mod highload;
+mod v3;
+mod v4;
+mod v5;
+
+pub use self::highload::{HighloadQueryId, HighloadWalletV3, HIGHLOAD_DEFAULT_SUBWALLET_ID};
+pub use self::v3::WalletV3R2;
+pub use self::v4::WalletV4R2;
+pub use self::v5::{
+    ExtendedAction, WalletV5R1, WalletV5Request, WalletV5State, MAINNET_GLOBAL_ID,
+    TESTNET_GLOBAL_ID,
+};
+
+use crate::address::Address;
+use crate::cell::{ArcCell, Cell, CellBuilder};
+use crate::client::ApiClientV2;
+use crate::error::{ToncenterError, WalletError};
+use crate::message::{InternalMessage, SendMode};
+use crate::signer::Signer;
+use std::sync::Arc;
+
+/// Base `wallet_id` of the standard wallets, the workchain id is added to it.
+pub const DEFAULT_WALLET_ID: u32 = 698983191;
+
+const ACTION_SEND_MSG_TAG: u32 = 0x0ec3c86d;
+
+/// Internal message together with the send mode it is sent with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletMessage {
+    pub mode: SendMode,
+    pub message: InternalMessage,
+}
+
+impl WalletMessage {
+    pub fn new(mode: SendMode, message: InternalMessage) -> Self {
+        Self { mode, message }
+    }
+}
+
+pub(crate) fn default_wallet_id(workchain: i32) -> u32 {
+    DEFAULT_WALLET_ID.wrapping_add(workchain as u32)
+}
+
+/// Fail on send modes the `SENDRAWMSG` action rejects: unknown flags, or carrying both
+/// the remaining inbound value and the whole balance.
+pub(crate) fn check_send_mode(mode: SendMode) -> Result<(), WalletError> {
+    let known = SendMode::PAY_FEES_SEPARATELY
+        | SendMode::IGNORE_ERRORS
+        | SendMode::BOUNCE_ON_ACTION_FAIL
+        | SendMode::DESTROY_IF_ZERO
+        | SendMode::CARRY_REMAINING_VALUE
+        | SendMode::CARRY_ALL_BALANCE;
+    let carry = SendMode::CARRY_REMAINING_VALUE | SendMode::CARRY_ALL_BALANCE;
+    if mode.bits() & !known.bits() != 0 || mode.bits() & carry.bits() == carry.bits() {
+        return Err(WalletError::InvalidSendMode(mode.bits()));
+    }
+    Ok(())
+}
+
+/// Build the signing payload of seqno-based wallets:
+/// `wallet_id valid_until seqno [op] (mode ^message)*`.
+pub(crate) fn seqno_payload(
+    wallet_id: u32,
+    valid_until: u32,
+    seqno: u32,
+    op: Option<u8>,
+    messages: &[WalletMessage],
+    max_messages: usize,
+) -> Result<ArcCell, WalletError> {
+    if messages.len() > max_messages {
+        return Err(WalletError::TooManyMessages { max: max_messages });
+    }
+    let mut builder = CellBuilder::new();
+    builder
+        .store_u32(wallet_id)?
+        .store_u32(valid_until)?
+        .store_u32(seqno)?;
+    if let Some(op) = op {
+        builder.store_u8(op)?;
+    }
+    for message in messages {
+        check_send_mode(message.mode)?;
+        builder
+            .store_u8(message.mode.bits())?
+            .store_reference(message.message.to_cell()?)?;
+    }
+    Ok(Arc::new(builder.build()?))
+}
+
+/// Build the `OutList` of `action_send_msg` actions, the format of the `c5` register.
+pub(crate) fn out_list(messages: &[WalletMessage]) -> Result<ArcCell, WalletError> {
+    // out_list$_ prev:^(OutList n) action:OutAction, the first action is the deepest
+    let mut list = Arc::new(Cell::empty());
+    for message in messages {
+        check_send_mode(message.mode)?;
+        let mut action = CellBuilder::new();
+        action
+            .store_reference(list)?
+            .store_u32(ACTION_SEND_MSG_TAG)?
+            .store_u8(message.mode.bits())?
+            .store_reference(message.message.to_cell()?)?;
+        list = Arc::new(action.build()?);
+    }
+    Ok(list)
+}
+
+/// Fail early when `signer` can not produce signatures the wallet accepts.
+pub(crate) fn check_signer<S: Signer>(
+    public_key: &[u8; 32],
+    signer: &S,
+) -> Result<(), WalletError> {
+    if &signer.public_key() != public_key {
+        return Err(WalletError::KeyMismatch);
+    }
+    Ok(())
+}
+
+/// Current seqno of a wallet, `0` if it is not deployed yet.
+pub(crate) async fn fetch_seqno(
+    client: &ApiClientV2,
+    address: &Address,
+) -> Result<u32, ToncenterError> {
+    let information = client.get_wallet_information(&address.to_string()).await?;
+    Ok(information.seqno.unwrap_or(0))
+}
+
+pub(crate) fn parse_code(boc: &str) -> ArcCell {
+    Cell::from_boc_b64(boc)
+        .expect("embedded wallet code is a valid BOC")
+        .to_arc()
+}
@@ -0,0 +1,235 @@
+use super::{
+    check_signer, default_wallet_id, fetch_seqno, parse_code, seqno_payload, WalletMessage,
+};
+use crate::address::Address;
+use crate::cell::{ArcCell, CellBuilder};
+use crate::client::ApiClientV2;
+use crate::error::{ToncenterError, WalletError};
+use crate::message::{ExternalMessage, StateInit};
+use crate::signer::{SignatureLayout, Signer, UnsignedBody, UnsignedExternalMessage};
+use std::sync::{Arc, OnceLock};
+
+const CODE_BOC: &str = "te6cckEBAQEAcQAA3v8AIN0gggFMl7ohggEznLqxn3Gw7UTQ0x/THzHXC//jBOCk8mCDCNcYINMf0x/TH/gjE7vyY+1E0NMf0x/T/9FRMrryoVFEuvKiBPkBVBBV+RDyo/gAkyDXSpbTB9QC+wDo0QGkyMsfyx/L/8ntVBC9ba0=";
+
+/// Wallet v3 revision 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WalletV3R2 {
+    workchain: i32,
+    public_key: [u8; 32],
+    wallet_id: u32,
+}
+
+impl WalletV3R2 {
+    /// Maximum number of messages in a single transfer.
+    pub const MAX_MESSAGES: usize = 4;
+
+    /// Wallet with the default `wallet_id` for the workchain.
+    pub fn new(workchain: i32, public_key: [u8; 32]) -> Self {
+        Self::with_wallet_id(workchain, public_key, default_wallet_id(workchain))
+    }
+
+    pub fn with_wallet_id(workchain: i32, public_key: [u8; 32], wallet_id: u32) -> Self {
+        Self {
+            workchain,
+            public_key,
+            wallet_id,
+        }
+    }
+
+    pub fn code() -> ArcCell {
+        static CODE: OnceLock<ArcCell> = OnceLock::new();
+        CODE.get_or_init(|| parse_code(CODE_BOC)).clone()
+    }
+
+    pub fn workchain(&self) -> i32 {
+        self.workchain
+    }
+
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    pub fn wallet_id(&self) -> u32 {
+        self.wallet_id
+    }
+
+    /// Initial data: `seqno:uint32 wallet_id:uint32 public_key:bits256`.
+    pub fn data(&self) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(0)
+            .and_then(|b| b.store_u32(self.wallet_id))
+            .and_then(|b| b.store_bytes(&self.public_key))
+            .expect("wallet data fits into a cell");
+        Arc::new(builder.build().expect("wallet data fits into a cell"))
+    }
+
+    pub fn state_init(&self) -> ArcCell {
+        StateInit::new(Self::code(), self.data())
+            .to_cell()
+            .expect("state init fits into a cell")
+    }
+
+    pub fn address(&self) -> Address {
+        Address::new(self.workchain, self.state_init().repr_hash())
+    }
+
+    /// Prepare a transfer of up to [`Self::MAX_MESSAGES`] messages to be signed elsewhere.
+    ///
+    /// The state init is attached when `seqno` is `0`, so the first transfer deploys the wallet.
+    pub fn prepare_transfer(
+        &self,
+        seqno: u32,
+        valid_until: u32,
+        messages: &[WalletMessage],
+    ) -> Result<UnsignedExternalMessage, WalletError> {
+        let payload = seqno_payload(
+            self.wallet_id,
+            valid_until,
+            seqno,
+            None,
+            messages,
+            Self::MAX_MESSAGES,
+        )?;
+        Ok(UnsignedExternalMessage {
+            destination: self.address(),
+            state_init: (seqno == 0).then(|| self.state_init()),
+            body: UnsignedBody::new(payload, SignatureLayout::Prefix),
+        })
+    }
+
+    /// Build a signed external message sending up to [`Self::MAX_MESSAGES`] messages.
+    ///
+    /// Fails with [`WalletError::KeyMismatch`] if `signer` does not hold the wallet's key.
+    pub async fn create_transfer<S: Signer>(
+        &self,
+        signer: &S,
+        seqno: u32,
+        valid_until: u32,
+        messages: &[WalletMessage],
+    ) -> Result<ExternalMessage, WalletError> {
+        check_signer(&self.public_key, signer)?;
+        self.prepare_transfer(seqno, valid_until, messages)?
+            .sign(signer)
+            .await
+    }
+
+    /// Fetch the current seqno, `0` if the wallet is not deployed yet.
+    pub async fn seqno(&self, client: &ApiClientV2) -> Result<u32, ToncenterError> {
+        fetch_seqno(client, &self.address()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{hex_string, Cell};
+    use crate::coins::Coins;
+    use crate::message::{comment_body, InternalMessage, SendMode};
+    use crate::wallet::DEFAULT_WALLET_ID;
+    use ed25519_dalek::{Signature, SigningKey, Verifier};
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[1; 32])
+    }
+
+    fn wallet() -> WalletV3R2 {
+        WalletV3R2::new(0, Signer::public_key(&signing_key()))
+    }
+
+    #[test]
+    fn address() {
+        assert_eq!(
+            wallet().address().to_raw_string(),
+            "0:8dca78972eab4a8e2b1d3f2a7e43c1c563e696f65bb9c5ae0845684cee138384"
+        );
+        assert_eq!(
+            wallet().address().to_string(),
+            "EQCNyniXLqtKjisdPyp-Q8HFY-aW9lu5xa4IRWhM7hODhE64"
+        );
+        let masterchain = WalletV3R2::new(-1, *wallet().public_key());
+        assert_eq!(masterchain.wallet_id(), DEFAULT_WALLET_ID - 1);
+        assert_eq!(
+            masterchain.address().to_raw_string(),
+            "-1:aa58fcae8bf060519f5c1e23079a8ea70eb09bd0f700cb4ef1e319380dcda32a"
+        );
+    }
+
+    #[tokio::test]
+    async fn signs_transfer() {
+        let wallet = wallet();
+        let messages = [WalletMessage::new(
+            SendMode::PAY_FEES_SEPARATELY,
+            InternalMessage::transfer(wallet.address(), Coins::from_ton(1), false),
+        )];
+        let unsigned = wallet
+            .prepare_transfer(0, 1_700_000_000, &messages)
+            .unwrap();
+        let message = wallet
+            .create_transfer(&signing_key(), 0, 1_700_000_000, &messages)
+            .await
+            .unwrap();
+        assert_eq!(message.destination, wallet.address());
+        assert_eq!(message.state_init, Some(wallet.state_init()));
+
+        let signature = Signature::from_slice(&message.body.data()[..64]).unwrap();
+        signing_key()
+            .verifying_key()
+            .verify(&unsigned.hash(), &signature)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_signer_with_another_key() {
+        let result = wallet()
+            .create_transfer(&SigningKey::from_bytes(&[2; 32]), 1, 1_700_000_000, &[])
+            .await;
+        assert_eq!(result, Err(WalletError::KeyMismatch));
+    }
+
+    /// Deploying transfer of 1.5 TON with a comment, built and signed by an
+    /// independent implementation of the message and request layouts.
+    const SIGNED_TRANSFER_BOC: &str = "te6ccgECBwEAAVkAAkWIARuU8S5dVpUcVjp+VPyHg4rHzS3st3OLXBCK0JncJwcIHgQBAZqe0hpVP7B0YA5JM6SsXvW3vBLEqyjJa6JX7sWiUEpsDLZttFW3PBcOviD4MYjpSnU8oWXawUvt28JYMuFGIKUGKamjF2VT8QAAAAAAAwIBaGIAQe/qqXMblNo5fl5kYi9eYzSLgSrFtHY6k/DdIB0HmNQiy0F4AAAAAAAAAAAAAAAAAAEDABIAAAAAaGVsbG8CATQGBQBQAAAAACmpoxeKiOPddAnxlf1S2y08ul1yymcJvx2UEhvzdIgBtA9vXADe/wAg3SCCAUyXuiGCATOcurGfcbDtRNDTH9MfMdcL/+ME4KTyYIMI1xgg0x/TH9Mf+CMTu/Jj7UTQ0x/TH9P/0VEyuvKhUUS68qIE+QFUEFX5EPKj+ACTINdKltMH1AL7AOjRAaTIyx/LH8v/ye1U";
+
+    fn payout() -> WalletMessage {
+        let destination = "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N"
+            .parse()
+            .unwrap();
+        WalletMessage::new(
+            SendMode::PAY_FEES_SEPARATELY | SendMode::IGNORE_ERRORS,
+            InternalMessage::transfer(destination, Coins::from_nanotons(1_500_000_000), true)
+                .with_body(comment_body("hello").unwrap()),
+        )
+    }
+
+    #[tokio::test]
+    async fn matches_known_signed_transfer() {
+        let message = wallet()
+            .create_transfer(&signing_key(), 0, 1_700_000_000, &[payout()])
+            .await
+            .unwrap();
+        let expected = Cell::from_boc_b64(SIGNED_TRANSFER_BOC).unwrap();
+        assert_eq!(message.to_cell().unwrap().as_ref(), &expected);
+        assert_eq!(
+            hex_string(&expected.repr_hash()),
+            "598bcbad7f162e766b7c24e796cb0f9d7fea8cfd4c19772f6b9c4e5de8dbe8ec"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_send_modes() {
+        for bits in [4, 8, 64 | 128] {
+            let mut message = payout();
+            message.mode = SendMode::from_bits(bits);
+            assert_eq!(
+                wallet().prepare_transfer(1, 1_700_000_000, &[message]),
+                Err(WalletError::InvalidSendMode(bits))
+            );
+        }
+        let mut message = payout();
+        message.mode = SendMode::CARRY_ALL_BALANCE | SendMode::DESTROY_IF_ZERO;
+        assert!(wallet()
+            .prepare_transfer(1, 1_700_000_000, &[message])
+            .is_ok());
+    }
+}
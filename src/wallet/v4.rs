@@ -0,0 +1,236 @@
+use super::{
+    check_signer, default_wallet_id, fetch_seqno, parse_code, seqno_payload, WalletMessage,
+};
+use crate::address::Address;
+use crate::cell::{ArcCell, CellBuilder};
+use crate::client::ApiClientV2;
+use crate::error::{ToncenterError, WalletError};
+use crate::message::{ExternalMessage, StateInit};
+use crate::signer::{SignatureLayout, Signer, UnsignedBody, UnsignedExternalMessage};
+use std::sync::{Arc, OnceLock};
+
+const CODE_BOC: &str = "te6cckECFAEAAtQAART/APSkE/S88sgLAQIBIAIDAgFIBAUE+PKDCNcYINMf0x/THwL4I7vyZO1E0NMf0x/T//QE0VFDuvKhUVG68qIF+QFUEGT5EPKj+AAkpMjLH1JAyx9SMMv/UhD0AMntVPgPAdMHIcAAn2xRkyDXSpbTB9QC+wDoMOAhwAHjACHAAuMAAcADkTDjDQOkyMsfEssfy/8QERITAubQAdDTAyFxsJJfBOAi10nBIJJfBOAC0x8hghBwbHVnvSKCEGRzdHK9sJJfBeAD+kAwIPpEAcjKB8v/ydDtRNCBAUDXIfQEMFyBAQj0Cm+hMbOSXwfgBdM/yCWCEHBsdWe6kjgw4w0DghBkc3RyupJfBuMNBgcCASAICQB4AfoA9AQw+CdvIjBQCqEhvvLgUIIQcGx1Z4MesXCAGFAEywUmzxZY+gIZ9ADLaRfLH1Jgyz8gyYBA+wAGAIpQBIEBCPRZMO1E0IEBQNcgyAHPFvQAye1UAXKwjiOCEGRzdHKDHrFwgBhQBcsFUAPPFiP6AhPLassfyz/JgED7AJJfA+ICASAKCwBZvSQrb2omhAgKBrkPoCGEcNQICEekk30pkQzmkD6f+YN4EoAbeBAUiYcVnzGEAgFYDA0AEbjJftRNDXCx+AA9sp37UTQgQFA1yH0BDACyMoHy//J0AGBAQj0Cm+hMYAIBIA4PABmtznaiaEAga5Drhf/AABmvHfaiaEAQa5DrhY/AAG7SB/oA1NQi+QAFyMoHFcv/ydB3dIAYyMsFywIizxZQBfoCFMtrEszMyXP7AMhAFIEBCPRR8qcCAHCBAQjXGPoA0z/IVCBHgQEI9FHyp4IQbm90ZXB0gBjIywXLAlAGzxZQBPoCFMtqEssfyz/Jc/sAAgBsgQEI1xj6ANM/MFIkgQEI9Fnyp4IQZHN0cnB0gBjIywXLAlAFzxZQA/oCE8tqyx8Syz/Jc/sAAAr0AMntVGliJeU=";
+
+/// `op` of the plain transfer request.
+const SIMPLE_SEND_OP: u8 = 0;
+
+/// Wallet v4 revision 2. Plugins are not supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WalletV4R2 {
+    workchain: i32,
+    public_key: [u8; 32],
+    wallet_id: u32,
+}
+
+impl WalletV4R2 {
+    /// Maximum number of messages in a single transfer.
+    pub const MAX_MESSAGES: usize = 4;
+
+    /// Wallet with the default `wallet_id` for the workchain.
+    pub fn new(workchain: i32, public_key: [u8; 32]) -> Self {
+        Self::with_wallet_id(workchain, public_key, default_wallet_id(workchain))
+    }
+
+    pub fn with_wallet_id(workchain: i32, public_key: [u8; 32], wallet_id: u32) -> Self {
+        Self {
+            workchain,
+            public_key,
+            wallet_id,
+        }
+    }
+
+    pub fn code() -> ArcCell {
+        static CODE: OnceLock<ArcCell> = OnceLock::new();
+        CODE.get_or_init(|| parse_code(CODE_BOC)).clone()
+    }
+
+    pub fn workchain(&self) -> i32 {
+        self.workchain
+    }
+
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    pub fn wallet_id(&self) -> u32 {
+        self.wallet_id
+    }
+
+    /// Initial data: `seqno:uint32 wallet_id:uint32 public_key:bits256 plugins:(HashmapE 256 Cell)`.
+    pub fn data(&self) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(0)
+            .and_then(|b| b.store_u32(self.wallet_id))
+            .and_then(|b| b.store_bytes(&self.public_key))
+            .and_then(|b| b.store_bit(false))
+            .expect("wallet data fits into a cell");
+        Arc::new(builder.build().expect("wallet data fits into a cell"))
+    }
+
+    pub fn state_init(&self) -> ArcCell {
+        StateInit::new(Self::code(), self.data())
+            .to_cell()
+            .expect("state init fits into a cell")
+    }
+
+    pub fn address(&self) -> Address {
+        Address::new(self.workchain, self.state_init().repr_hash())
+    }
+
+    /// Prepare a transfer of up to [`Self::MAX_MESSAGES`] messages to be signed elsewhere.
+    ///
+    /// The state init is attached when `seqno` is `0`, so the first transfer deploys the wallet.
+    pub fn prepare_transfer(
+        &self,
+        seqno: u32,
+        valid_until: u32,
+        messages: &[WalletMessage],
+    ) -> Result<UnsignedExternalMessage, WalletError> {
+        let payload = seqno_payload(
+            self.wallet_id,
+            valid_until,
+            seqno,
+            Some(SIMPLE_SEND_OP),
+            messages,
+            Self::MAX_MESSAGES,
+        )?;
+        Ok(UnsignedExternalMessage {
+            destination: self.address(),
+            state_init: (seqno == 0).then(|| self.state_init()),
+            body: UnsignedBody::new(payload, SignatureLayout::Prefix),
+        })
+    }
+
+    /// Build a signed external message sending up to [`Self::MAX_MESSAGES`] messages.
+    ///
+    /// Fails with [`WalletError::KeyMismatch`] if `signer` does not hold the wallet's key.
+    pub async fn create_transfer<S: Signer>(
+        &self,
+        signer: &S,
+        seqno: u32,
+        valid_until: u32,
+        messages: &[WalletMessage],
+    ) -> Result<ExternalMessage, WalletError> {
+        check_signer(&self.public_key, signer)?;
+        self.prepare_transfer(seqno, valid_until, messages)?
+            .sign(signer)
+            .await
+    }
+
+    /// Fetch the current seqno, `0` if the wallet is not deployed yet.
+    pub async fn seqno(&self, client: &ApiClientV2) -> Result<u32, ToncenterError> {
+        fetch_seqno(client, &self.address()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{hex_string, Cell};
+    use crate::coins::Coins;
+    use crate::message::{comment_body, InternalMessage, SendMode};
+    use ed25519_dalek::{Signature, SigningKey, Verifier};
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[1; 32])
+    }
+
+    fn wallet() -> WalletV4R2 {
+        WalletV4R2::new(0, Signer::public_key(&signing_key()))
+    }
+
+    #[test]
+    fn address() {
+        assert_eq!(
+            wallet().address().to_raw_string(),
+            "0:efaff4bac220f88b2e98eb1d9cffcca3bfe3b66ece31a7d6c5890d30dfd7afa5"
+        );
+        assert_eq!(
+            wallet().address().to_string(),
+            "EQDvr_S6wiD4iy6Y6x2c_8yjv-O2bs4xp9bFiQ0w39evpduQ"
+        );
+    }
+
+    #[tokio::test]
+    async fn signs_transfer() {
+        let wallet = wallet();
+        let messages = [WalletMessage::new(
+            SendMode::PAY_FEES_SEPARATELY,
+            InternalMessage::transfer(wallet.address(), Coins::from_ton(1), false),
+        )];
+        let unsigned = wallet
+            .prepare_transfer(3, 1_700_000_000, &messages)
+            .unwrap();
+        let mut payload = unsigned.body.payload().parser();
+        assert_eq!(payload.load_u32().unwrap(), wallet.wallet_id());
+        assert_eq!(payload.load_u32().unwrap(), 1_700_000_000);
+        assert_eq!(payload.load_u32().unwrap(), 3);
+        assert_eq!(payload.load_u8().unwrap(), SIMPLE_SEND_OP);
+
+        let message = wallet
+            .create_transfer(&signing_key(), 3, 1_700_000_000, &messages)
+            .await
+            .unwrap();
+        assert_eq!(message.state_init, None);
+        let signature = Signature::from_slice(&message.body.data()[..64]).unwrap();
+        signing_key()
+            .verifying_key()
+            .verify(&unsigned.hash(), &signature)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_signer_with_another_key() {
+        let result = wallet()
+            .create_transfer(&SigningKey::from_bytes(&[2; 32]), 1, 1_700_000_000, &[])
+            .await;
+        assert_eq!(result, Err(WalletError::KeyMismatch));
+    }
+
+    /// Transfer of 1.5 TON with a comment at seqno 7, built and signed by an
+    /// independent implementation of the message and request layouts.
+    const SIGNED_TRANSFER_BOC: &str = "te6ccgEBBAEAuQABRYgB31/pdYRB8RZdMdY7Of+ZR3/HbN2cY0+tixIaYb+vX0oMAQGckn4XoDPR7CEkI53VLvOeOzfWB4fFXumUdCCKucxnsPNCn9yqV8sG7ZqwS7Dz3j/c/0PpE6xVSWQyOTJqECyhCympoxdlU/E8AAAABwADAgFoYgBB7+qpcxuU2jl+XmRiL15jNIuBKsW0djqT8N0gHQeY1CLLQXgAAAAAAAAAAAAAAAAAAQMAEgAAAABoZWxsbw==";
+
+    fn payout() -> WalletMessage {
+        let destination = "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N"
+            .parse()
+            .unwrap();
+        WalletMessage::new(
+            SendMode::PAY_FEES_SEPARATELY | SendMode::IGNORE_ERRORS,
+            InternalMessage::transfer(destination, Coins::from_nanotons(1_500_000_000), true)
+                .with_body(comment_body("hello").unwrap()),
+        )
+    }
+
+    #[tokio::test]
+    async fn matches_known_signed_transfer() {
+        let message = wallet()
+            .create_transfer(&signing_key(), 7, 1_700_000_060, &[payout()])
+            .await
+            .unwrap();
+        let expected = Cell::from_boc_b64(SIGNED_TRANSFER_BOC).unwrap();
+        assert_eq!(message.to_cell().unwrap().as_ref(), &expected);
+        assert_eq!(
+            hex_string(&expected.repr_hash()),
+            "9f68c090752f537de55162178b1c32ab4006ed2143bfa9c3a19edd7ef5b2127d"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_send_modes() {
+        for bits in [4, 8, 64 | 128] {
+            let mut message = payout();
+            message.mode = SendMode::from_bits(bits);
+            assert_eq!(
+                wallet().prepare_transfer(1, 1_700_000_000, &[message]),
+                Err(WalletError::InvalidSendMode(bits))
+            );
+        }
+        let mut message = payout();
+        message.mode = SendMode::CARRY_ALL_BALANCE | SendMode::DESTROY_IF_ZERO;
+        assert!(wallet()
+            .prepare_transfer(1, 1_700_000_000, &[message])
+            .is_ok());
+    }
+}
@@ -0,0 +1,654 @@
+use super::{check_signer, out_list, parse_code, WalletMessage};
+use crate::address::Address;
+use crate::cell::{ArcCell, Cell, CellBuilder};
+use crate::client::ApiClientV2;
+use crate::error::{CellError, ToncenterError, WalletError};
+use crate::message::{ExternalMessage, SendMode, StateInit};
+use crate::signer::{SignatureLayout, Signer, UnsignedBody, UnsignedExternalMessage};
+use std::sync::{Arc, OnceLock};
+
+const CODE_BOC: &str = "te6cckECFAEAAoEAART/APSkE/S88sgLAQIBIAINAgFIAwQC3NAg10nBIJFbj2Mg1wsfIIIQZXh0br0hghBzaW50vbCSXwPgghBleHRuuo60gCDXIQHQdNch+kAw+kT4KPpEMFi9kVvg7UTQgQFB1yH0BYMH9A5voTGRMOGAQNchcH/bPOAxINdJgQKAuZEw4HDiEA8CASAFDAIBIAYJAgFuBwgAGa3OdqJoQCDrkOuF/8AAGa8d9qJoQBDrkOuFj8ACAUgKCwAXsyX7UTQcdch1wsfgABGyYvtRNDXCgCAAGb5fD2omhAgKDrkPoCwBAvIOAR4g1wsfghBzaWduuvLgin8PAeaO8O2i7fshgwjXIgKDCNcjIIAg1yHTH9Mf0x/tRNDSANMfINMf0//XCgAK+QFAzPkQmiiUXwrbMeHywIffArNQB7Dy0IRRJbry4IVQNrry4Ib4I7vy0IgikvgA3gGkf8jKAMsfAc8Wye1UIJL4D95w2zzYEAP27aLt+wL0BCFukmwhjkwCIdc5MHCUIccAs44tAdcoIHYeQ2wg10nACPLgkyDXSsAC8uCTINcdBscSwgBSMLDy0InXTNc5MAGk6GwShAe78uCT10rAAPLgk+1V4tIAAcAAkVvg69csCBQgkXCWAdcsCBwS4lIQseMPINdKERITAJYB+kAB+kT4KPpEMFi68uCR7UTQgQFB1xj0BQSdf8jKAEAEgwf0U/Lgi44UA4MH9Fvy4Iwi1woAIW4Bs7Dy0JDiyFADzxYS9ADJ7VQAcjDXLAgkji0h8uCS0gDtRNDSAFETuvLQj1RQMJExnAGBAUDXIdcKAPLgjuLIygBYzxbJ7VST8sCN4gAQk1vbMeHXTNC01sNe";
+
+/// `global_id` of the mainnet, part of the W5 `wallet_id`.
+pub const MAINNET_GLOBAL_ID: i32 = -239;
+/// `global_id` of the testnet, part of the W5 `wallet_id`.
+pub const TESTNET_GLOBAL_ID: i32 = -3;
+
+const EXTERNAL_SIGNED_OP: u32 = 0x7369676e;
+const INTERNAL_SIGNED_OP: u32 = 0x73696e74;
+const INTERNAL_EXTENSION_OP: u32 = 0x6578746e;
+const ADD_EXTENSION_TAG: u8 = 0x02;
+const REMOVE_EXTENSION_TAG: u8 = 0x03;
+const SET_SIGNATURE_ALLOWED_TAG: u8 = 0x04;
+
+/// Wallet-management action of a W5 request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtendedAction {
+    /// Allow a contract of the same workchain to send requests on behalf of the wallet.
+    AddExtension(Address),
+    RemoveExtension(Address),
+    /// Enable or disable requests signed with the wallet key.
+    /// Disabling is only accepted while at least one extension is installed.
+    SetSignatureAllowed(bool),
+}
+
+/// Actions performed by a single W5 request.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WalletV5Request {
+    pub messages: Vec<WalletMessage>,
+    pub extended_actions: Vec<ExtendedAction>,
+}
+
+impl WalletV5Request {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send(mut self, message: WalletMessage) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    pub fn add_extension(mut self, extension: Address) -> Self {
+        self.extended_actions
+            .push(ExtendedAction::AddExtension(extension));
+        self
+    }
+
+    pub fn remove_extension(mut self, extension: Address) -> Self {
+        self.extended_actions
+            .push(ExtendedAction::RemoveExtension(extension));
+        self
+    }
+
+    pub fn set_signature_allowed(mut self, allowed: bool) -> Self {
+        self.extended_actions
+            .push(ExtendedAction::SetSignatureAllowed(allowed));
+        self
+    }
+
+    /// Store `out_actions:(Maybe ^OutList) has_other_actions:(## 1) other_actions:ActionList`.
+    fn store(&self, builder: &mut CellBuilder) -> Result<(), WalletError> {
+        if self.messages.len() > WalletV5R1::MAX_MESSAGES {
+            return Err(WalletError::TooManyMessages {
+                max: WalletV5R1::MAX_MESSAGES,
+            });
+        }
+        let out_list = if self.messages.is_empty() {
+            None
+        } else {
+            Some(out_list(&self.messages)?)
+        };
+        builder.store_maybe_reference(out_list)?;
+
+        let Some((first, rest)) = self.extended_actions.split_first() else {
+            builder.store_bit(false)?;
+            return Ok(());
+        };
+        builder.store_bit(true)?;
+        // The first action is stored inline, every following one in a reference of the previous
+        let mut next = None;
+        for action in rest.iter().rev() {
+            let mut cell = CellBuilder::new();
+            store_extended_action(&mut cell, action)?;
+            if let Some(next) = next {
+                cell.store_reference(next)?;
+            }
+            next = Some(Arc::new(cell.build()?));
+        }
+        store_extended_action(builder, first)?;
+        if let Some(next) = next {
+            builder.store_reference(next)?;
+        }
+        Ok(())
+    }
+}
+
+fn store_extended_action(
+    builder: &mut CellBuilder,
+    action: &ExtendedAction,
+) -> Result<(), CellError> {
+    match action {
+        ExtendedAction::AddExtension(address) => {
+            builder
+                .store_u8(ADD_EXTENSION_TAG)?
+                .store_address(address)?;
+        }
+        ExtendedAction::RemoveExtension(address) => {
+            builder
+                .store_u8(REMOVE_EXTENSION_TAG)?
+                .store_address(address)?;
+        }
+        ExtendedAction::SetSignatureAllowed(allowed) => {
+            builder
+                .store_u8(SET_SIGNATURE_ALLOWED_TAG)?
+                .store_bit(*allowed)?;
+        }
+    }
+    Ok(())
+}
+
+/// Persistent data of a deployed W5 wallet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletV5State {
+    pub signature_allowed: bool,
+    pub seqno: u32,
+    pub wallet_id: u32,
+    pub public_key: [u8; 32],
+    pub extensions: Vec<Address>,
+}
+
+impl WalletV5State {
+    /// Decode `is_signature_allowed:Bool seqno:uint32 wallet_id:uint32 public_key:bits256
+    /// extensions:(HashmapE 256 int1)`. Extensions live in the workchain of the wallet.
+    pub fn from_data(data: &ArcCell, workchain: i32) -> Result<Self, CellError> {
+        let mut slice = data.parser();
+        let signature_allowed = slice.load_bit()?;
+        let seqno = slice.load_u32()?;
+        let wallet_id = slice.load_u32()?;
+        let public_key = slice.load_hash()?;
+        let extensions = slice
+            .load_dict(256)?
+            .into_keys()
+            .map(|key| {
+                let bytes = key.to_bytes_be();
+                let mut hash_part = [0u8; 32];
+                hash_part[32 - bytes.len()..].copy_from_slice(&bytes);
+                Address::new(workchain, hash_part)
+            })
+            .collect();
+        Ok(Self {
+            signature_allowed,
+            seqno,
+            wallet_id,
+            public_key,
+            extensions,
+        })
+    }
+}
+
+/// Wallet v5 revision 1, also known as W5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WalletV5R1 {
+    workchain: i32,
+    public_key: [u8; 32],
+    wallet_id: u32,
+}
+
+impl WalletV5R1 {
+    /// Maximum number of messages in a single request.
+    pub const MAX_MESSAGES: usize = 255;
+
+    /// Mainnet wallet with subwallet `0`.
+    pub fn new(workchain: i32, public_key: [u8; 32]) -> Self {
+        Self::with_wallet_id(
+            workchain,
+            public_key,
+            Self::client_wallet_id(MAINNET_GLOBAL_ID, workchain, 0)
+                .expect("subwallet 0 is always valid"),
+        )
+    }
+
+    /// Wallet with the `wallet_id` derived from the network, workchain and subwallet number.
+    pub fn with_subwallet(
+        network_global_id: i32,
+        workchain: i32,
+        public_key: [u8; 32],
+        subwallet: u32,
+    ) -> Result<Self, WalletError> {
+        let wallet_id = Self::client_wallet_id(network_global_id, workchain, subwallet)?;
+        Ok(Self::with_wallet_id(workchain, public_key, wallet_id))
+    }
+
+    pub fn with_wallet_id(workchain: i32, public_key: [u8; 32], wallet_id: u32) -> Self {
+        Self {
+            workchain,
+            public_key,
+            wallet_id,
+        }
+    }
+
+    /// `wallet_id` of client wallets: `network_global_id` XOR
+    /// `context_id_client$1 wc:int8 wallet_version:uint8 counter:uint15`.
+    pub fn client_wallet_id(
+        network_global_id: i32,
+        workchain: i32,
+        subwallet: u32,
+    ) -> Result<u32, WalletError> {
+        if subwallet >= 1 << 15 {
+            return Err(WalletError::InvalidWalletId(format!(
+                "subwallet {} does not fit into 15 bits",
+                subwallet
+            )));
+        }
+        let workchain = i8::try_from(workchain).map_err(|_| {
+            WalletError::InvalidWalletId(format!(
+                "workchain {} does not fit into 8 bits",
+                workchain
+            ))
+        })? as u8 as u32;
+        let context_id = (1 << 31) | (workchain << 23) | subwallet;
+        Ok(network_global_id as u32 ^ context_id)
+    }
+
+    pub fn code() -> ArcCell {
+        static CODE: OnceLock<ArcCell> = OnceLock::new();
+        CODE.get_or_init(|| parse_code(CODE_BOC)).clone()
+    }
+
+    pub fn workchain(&self) -> i32 {
+        self.workchain
+    }
+
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    pub fn wallet_id(&self) -> u32 {
+        self.wallet_id
+    }
+
+    /// Initial data: signatures allowed, seqno `0` and no extensions.
+    pub fn data(&self) -> ArcCell {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_bit(true)
+            .and_then(|b| b.store_u32(0))
+            .and_then(|b| b.store_u32(self.wallet_id))
+            .and_then(|b| b.store_bytes(&self.public_key))
+            .and_then(|b| b.store_bit(false))
+            .expect("wallet data fits into a cell");
+        Arc::new(builder.build().expect("wallet data fits into a cell"))
+    }
+
+    pub fn state_init(&self) -> ArcCell {
+        StateInit::new(Self::code(), self.data())
+            .to_cell()
+            .expect("state init fits into a cell")
+    }
+
+    pub fn address(&self) -> Address {
+        Address::new(self.workchain, self.state_init().repr_hash())
+    }
+
+    /// Build a signed external message sending up to [`Self::MAX_MESSAGES`] messages.
+    pub async fn create_transfer<S: Signer>(
+        &self,
+        signer: &S,
+        seqno: u32,
+        valid_until: u32,
+        messages: &[WalletMessage],
+    ) -> Result<ExternalMessage, WalletError> {
+        let request = WalletV5Request {
+            messages: messages.to_vec(),
+            extended_actions: Vec::new(),
+        };
+        self.create_external(signer, seqno, valid_until, &request)
+            .await
+    }
+
+    /// Prepare an external message performing the request, to be signed elsewhere.
+    ///
+    /// Every message must be sent with [`SendMode::IGNORE_ERRORS`], otherwise the wallet
+    /// rejects the request. The state init is attached when `seqno` is `0`.
+    pub fn prepare_external(
+        &self,
+        seqno: u32,
+        valid_until: u32,
+        request: &WalletV5Request,
+    ) -> Result<UnsignedExternalMessage, WalletError> {
+        if let Some(message) = request
+            .messages
+            .iter()
+            .find(|message| message.mode.bits() & SendMode::IGNORE_ERRORS.bits() == 0)
+        {
+            return Err(WalletError::InvalidSendMode(message.mode.bits()));
+        }
+        Ok(UnsignedExternalMessage {
+            destination: self.address(),
+            state_init: (seqno == 0).then(|| self.state_init()),
+            body: self.request_body(EXTERNAL_SIGNED_OP, seqno, valid_until, request)?,
+        })
+    }
+
+    /// Build a signed external message performing the request.
+    ///
+    /// Fails with [`WalletError::KeyMismatch`] if `signer` does not hold the wallet's key.
+    pub async fn create_external<S: Signer>(
+        &self,
+        signer: &S,
+        seqno: u32,
+        valid_until: u32,
+        request: &WalletV5Request,
+    ) -> Result<ExternalMessage, WalletError> {
+        check_signer(&self.public_key, signer)?;
+        self.prepare_external(seqno, valid_until, request)?
+            .sign(signer)
+            .await
+    }
+
+    /// Prepare the body of an internal message performing the request, to be signed elsewhere.
+    ///
+    /// Anyone may deliver the signed body to [`Self::address`] and pay for the delivery,
+    /// which is how relayers send gasless transfers on behalf of the wallet.
+    pub fn prepare_internal(
+        &self,
+        seqno: u32,
+        valid_until: u32,
+        request: &WalletV5Request,
+    ) -> Result<UnsignedBody, WalletError> {
+        self.request_body(INTERNAL_SIGNED_OP, seqno, valid_until, request)
+    }
+
+    /// Build the signed body of an internal message performing the request.
+    pub async fn create_internal<S: Signer>(
+        &self,
+        signer: &S,
+        seqno: u32,
+        valid_until: u32,
+        request: &WalletV5Request,
+    ) -> Result<ArcCell, WalletError> {
+        check_signer(&self.public_key, signer)?;
+        self.prepare_internal(seqno, valid_until, request)?
+            .sign(signer)
+            .await
+    }
+
+    /// Build the body an installed extension sends to the wallet to perform the request.
+    pub fn extension_request(
+        query_id: u64,
+        request: &WalletV5Request,
+    ) -> Result<ArcCell, WalletError> {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(INTERNAL_EXTENSION_OP)?
+            .store_u64(query_id)?;
+        request.store(&mut builder)?;
+        Ok(Arc::new(builder.build()?))
+    }
+
+    /// `op wallet_id valid_until seqno inner`, followed by the signature once signed.
+    fn request_body(
+        &self,
+        op: u32,
+        seqno: u32,
+        valid_until: u32,
+        request: &WalletV5Request,
+    ) -> Result<UnsignedBody, WalletError> {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(op)?
+            .store_u32(self.wallet_id)?
+            .store_u32(valid_until)?
+            .store_u32(seqno)?;
+        request.store(&mut builder)?;
+        Ok(UnsignedBody::new(
+            Arc::new(builder.build()?),
+            SignatureLayout::Suffix,
+        ))
+    }
+
+    /// Fetch and decode the wallet data, `None` if the wallet is not deployed yet.
+    pub async fn state(
+        &self,
+        client: &ApiClientV2,
+    ) -> Result<Option<WalletV5State>, ToncenterError> {
+        let information = client
+            .get_address_information(&self.address().to_string())
+            .await?;
+        match information.data.as_deref() {
+            Some(data) if information.state == "active" && !data.is_empty() => {
+                let data = Cell::from_boc_b64(data)?.to_arc();
+                Ok(Some(WalletV5State::from_data(&data, self.workchain)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Fetch the current seqno, `0` if the wallet is not deployed yet.
+    pub async fn seqno(&self, client: &ApiClientV2) -> Result<u32, ToncenterError> {
+        Ok(self.state(client).await?.map_or(0, |state| state.seqno))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::hex_string;
+    use crate::coins::Coins;
+    use crate::message::InternalMessage;
+    use ed25519_dalek::{Signature, SigningKey, Verifier};
+    use num_bigint::BigUint;
+    use std::collections::BTreeMap;
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[1; 32])
+    }
+
+    fn wallet() -> WalletV5R1 {
+        WalletV5R1::new(0, Signer::public_key(&signing_key()))
+    }
+
+    #[test]
+    fn default_wallet_id() {
+        assert_eq!(wallet().wallet_id(), 2147483409);
+    }
+
+    #[test]
+    fn client_wallet_ids() {
+        assert_eq!(
+            WalletV5R1::client_wallet_id(TESTNET_GLOBAL_ID, 0, 0).unwrap(),
+            2147483645
+        );
+        assert_eq!(
+            WalletV5R1::client_wallet_id(MAINNET_GLOBAL_ID, -1, 0).unwrap(),
+            8388369
+        );
+        assert_eq!(
+            WalletV5R1::client_wallet_id(MAINNET_GLOBAL_ID, 0, 1).unwrap(),
+            2147483408
+        );
+        assert!(matches!(
+            WalletV5R1::client_wallet_id(MAINNET_GLOBAL_ID, 0, 1 << 15),
+            Err(WalletError::InvalidWalletId(_))
+        ));
+        assert!(matches!(
+            WalletV5R1::client_wallet_id(MAINNET_GLOBAL_ID, 128, 0),
+            Err(WalletError::InvalidWalletId(_))
+        ));
+    }
+
+    #[test]
+    fn address() {
+        assert_eq!(
+            wallet().address().to_raw_string(),
+            "0:9d1e1843624c4d175a695a8c2de8a5a61f03b93336e8caa4e164bf6cbbab205e"
+        );
+        assert_eq!(
+            wallet().address().to_string(),
+            "EQCdHhhDYkxNF1ppWowt6KWmHwO5MzboyqThZL9su6sgXsq8"
+        );
+    }
+
+    #[test]
+    fn initial_state() {
+        let wallet = wallet();
+        let state = WalletV5State::from_data(&wallet.data(), 0).unwrap();
+        assert_eq!(
+            state,
+            WalletV5State {
+                signature_allowed: true,
+                seqno: 0,
+                wallet_id: wallet.wallet_id(),
+                public_key: *wallet.public_key(),
+                extensions: Vec::new(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn signs_transfer() {
+        let wallet = wallet();
+        let messages = [WalletMessage::new(
+            SendMode::PAY_FEES_SEPARATELY | SendMode::IGNORE_ERRORS,
+            InternalMessage::transfer(wallet.address(), Coins::from_ton(1), false),
+        )];
+        let request = WalletV5Request {
+            messages: messages.to_vec(),
+            extended_actions: Vec::new(),
+        };
+        let unsigned = wallet.prepare_external(0, 1_700_000_000, &request).unwrap();
+        let mut payload = unsigned.body.payload().parser();
+        assert_eq!(payload.load_u32().unwrap(), EXTERNAL_SIGNED_OP);
+        assert_eq!(payload.load_u32().unwrap(), 2147483409);
+
+        let message = wallet
+            .create_transfer(&signing_key(), 0, 1_700_000_000, &messages)
+            .await
+            .unwrap();
+        assert_eq!(message.state_init, Some(wallet.state_init()));
+        let mut body = message.body.parser();
+        body.skip_bits(unsigned.body.payload().bit_len()).unwrap();
+        let signature = Signature::from_slice(&body.load_bytes(64).unwrap()).unwrap();
+        signing_key()
+            .verifying_key()
+            .verify(&unsigned.hash(), &signature)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_requests() {
+        let wallet = wallet();
+        let message = WalletMessage::new(
+            SendMode::PAY_FEES_SEPARATELY,
+            InternalMessage::transfer(wallet.address(), Coins::from_ton(1), false),
+        );
+        assert_eq!(
+            wallet
+                .create_transfer(&signing_key(), 1, 1_700_000_000, &[message])
+                .await,
+            Err(WalletError::InvalidSendMode(1))
+        );
+        assert_eq!(
+            wallet
+                .create_internal(
+                    &SigningKey::from_bytes(&[2; 32]),
+                    1,
+                    1_700_000_000,
+                    &WalletV5Request::new()
+                )
+                .await,
+            Err(WalletError::KeyMismatch)
+        );
+    }
+
+    fn extension(address: &str) -> Address {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn builds_extended_actions_in_order() {
+        let first = extension("EQAvDfWFG0oYX19jwNDNBBL1rKNT9XfaGP9HyTb5nb2Eml6y");
+        let second = extension("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR");
+        let request = WalletV5Request::new()
+            .add_extension(first)
+            .remove_extension(second)
+            .set_signature_allowed(false);
+        assert_eq!(
+            request.extended_actions,
+            [
+                ExtendedAction::AddExtension(first),
+                ExtendedAction::RemoveExtension(second),
+                ExtendedAction::SetSignatureAllowed(false),
+            ]
+        );
+    }
+
+    #[test]
+    fn extension_request_layout() {
+        let first = extension("EQAvDfWFG0oYX19jwNDNBBL1rKNT9XfaGP9HyTb5nb2Eml6y");
+        let second = extension("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR");
+        let request = WalletV5Request::new()
+            .send(WalletMessage::new(
+                SendMode::PAY_FEES_SEPARATELY | SendMode::IGNORE_ERRORS,
+                InternalMessage::transfer(
+                    extension("EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N"),
+                    Coins::from_nanotons(50_000_000),
+                    false,
+                ),
+            ))
+            .add_extension(first)
+            .remove_extension(second)
+            .set_signature_allowed(false);
+        let body = WalletV5R1::extension_request(42, &request).unwrap();
+
+        let mut slice = body.parser();
+        assert_eq!(slice.load_u32().unwrap(), INTERNAL_EXTENSION_OP);
+        assert_eq!(slice.load_u64().unwrap(), 42);
+        let out_list = slice.load_maybe_reference().unwrap().unwrap();
+        assert!(slice.load_bit().unwrap());
+        assert_eq!(slice.load_u8().unwrap(), ADD_EXTENSION_TAG);
+        assert_eq!(slice.load_address().unwrap(), first);
+        let mut next = slice.load_reference().unwrap().parser();
+        assert!(slice.is_empty());
+        assert_eq!(next.load_u8().unwrap(), REMOVE_EXTENSION_TAG);
+        assert_eq!(next.load_address().unwrap(), second);
+        let mut last = next.load_reference().unwrap().parser();
+        assert_eq!(last.load_u8().unwrap(), SET_SIGNATURE_ALLOWED_TAG);
+        assert!(!last.load_bit().unwrap());
+        assert_eq!(out_list.parser().load_u32().unwrap(), 0x0ec3c86d);
+
+        // Same request built by an independent implementation of the W5 layouts
+        assert_eq!(
+            hex_string(&body.repr_hash()),
+            "099cb9f5d92cc2dd84081b2c52bdb7547841c0be0eca13a2b17e98dd7cef73b7"
+        );
+    }
+
+    /// Synthetic data of a wallet at seqno 5 with signatures disabled and two extensions,
+    /// one of them with a hash part starting with zero bytes.
+    #[test]
+    fn decodes_installed_extensions() {
+        let wallet = wallet();
+        let mut padded = [0u8; 32];
+        padded[2..].copy_from_slice(&[0x7c; 30]);
+        let extensions = [
+            Address::new(0, padded),
+            extension("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR"),
+        ];
+        let entries = extensions
+            .iter()
+            .map(|address| {
+                let mut value = CellBuilder::new();
+                value.store_bit(true).unwrap();
+                (
+                    BigUint::from_bytes_be(address.hash_part()),
+                    Arc::new(value.build().unwrap()),
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        let mut data = CellBuilder::new();
+        data.store_bit(false)
+            .unwrap()
+            .store_u32(5)
+            .unwrap()
+            .store_u32(wallet.wallet_id())
+            .unwrap()
+            .store_bytes(wallet.public_key())
+            .unwrap()
+            .store_dict(256, &entries)
+            .unwrap();
+
+        let state = WalletV5State::from_data(&Arc::new(data.build().unwrap()), 0).unwrap();
+        assert_eq!(
+            state,
+            WalletV5State {
+                signature_allowed: false,
+                seqno: 5,
+                wallet_id: wallet.wallet_id(),
+                public_key: *wallet.public_key(),
+                extensions: extensions.to_vec(),
+            }
+        );
+    }
+}
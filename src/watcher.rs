@@ -0,0 +1,547 @@
+use crate::address::Address;
+use crate::cell::{ArcCell, Cell};
+use crate::checkpoint::CheckpointStore;
+use crate::client::v2::parse_lt;
+use crate::client::ApiClientV2;
+use crate::coins::Coins;
+use crate::error::ToncenterError;
+use crate::message::parse_comment;
+use crate::models::{RawMessage, RawTransaction};
+use futures::stream::{self, Stream, TryStreamExt};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub use crate::checkpoint::TransactionCursor;
+
+const BOUNCE_PREFIX: u32 = 0xffffffff;
+const TRANSFER_NOTIFICATION_OP: u32 = 0x7362d09c;
+const PAGE_SIZE: u32 = 16;
+
+/// Incoming transfer detected by an [`AddressWatcher`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchEvent {
+    /// Watched account that received the message.
+    pub address: Address,
+    /// Logical time of the transaction that processed the message.
+    pub lt: u64,
+    /// Hash of the transaction that processed the message.
+    pub hash: String,
+    pub utime: u64,
+    pub kind: WatchEventKind,
+}
+
+impl WatchEvent {
+    /// Key identifying the event across redeliveries: `lt:hash` of its transaction.
+    pub fn idempotency_key(&self) -> String {
+        format!("{}:{}", self.lt, self.hash)
+    }
+
+    /// Cursor to resume after this event with [`AddressWatcher::watch`].
+    pub fn cursor(&self) -> TransactionCursor {
+        TransactionCursor {
+            lt: self.lt,
+            hash: self.hash.clone(),
+        }
+    }
+
+    /// Classify the inbound message of a transaction of `address`. Transactions without an
+    /// inbound internal message or with an empty one yield no event.
+    pub fn from_transaction(
+        address: Address,
+        transaction: &RawTransaction,
+    ) -> Result<Option<Self>, ToncenterError> {
+        let Some(kind) = transaction.in_msg.as_ref().and_then(classify) else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            address,
+            lt: parse_lt(&transaction.transaction_id.lt)?,
+            hash: transaction.transaction_id.hash.clone(),
+            utime: transaction.utime,
+            kind,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEventKind {
+    /// Plain TON transfer, optionally with a text comment.
+    TonReceived {
+        from: Address,
+        amount: Coins,
+        comment: Option<String>,
+    },
+    /// TEP-74 `transfer_notification` sent by a jetton wallet of the watched account.
+    ///
+    /// Anyone can send such a message: check that `jetton_wallet` is the wallet of the
+    /// watched account for the expected jetton, e.g. with
+    /// [`JettonMaster::wallet_address`](crate::jetton::JettonMaster::wallet_address).
+    JettonReceived {
+        jetton_wallet: Address,
+        /// Owner of the jetton wallet the jettons came from.
+        sender: Option<Address>,
+        amount: u128,
+        query_id: u64,
+        comment: Option<String>,
+    },
+    /// A message sent by the watched account bounced back with the remaining value.
+    Bounced { from: Address, amount: Coins },
+}
+
+/// Watches a changing set of accounts for incoming TON, jetton transfers and bounces.
+///
+/// Accounts are polled one by one through their transaction history. Events of an account
+/// are delivered in the order of its transactions. Delivery is at least once: a failed poll
+/// leaves every cursor where it was, so the next poll may return events again. Persist
+/// [`WatchEvent::cursor`] after handling an event, pass it back to [`Self::watch`] after a
+/// restart and deduplicate by [`WatchEvent::idempotency_key`], or let
+/// [`Self::stream_with_checkpoints`] persist cursors in a [`CheckpointStore`].
+pub struct AddressWatcher<'a> {
+    client: &'a ApiClientV2,
+    accounts: Mutex<BTreeMap<Address, Option<TransactionCursor>>>,
+}
+
+impl<'a> AddressWatcher<'a> {
+    pub fn new(client: &'a ApiClientV2) -> Self {
+        Self {
+            client,
+            accounts: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Start watching `address` after the transaction at `since`, or after its latest
+    /// transaction when `since` is `None`. Watching an address again moves its cursor.
+    pub fn watch(&self, address: Address, since: Option<TransactionCursor>) {
+        self.accounts.lock().unwrap().insert(address, since);
+    }
+
+    /// Stop watching `address`. Returns whether it was watched.
+    pub fn unwatch(&self, address: &Address) -> bool {
+        self.accounts.lock().unwrap().remove(address).is_some()
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.accounts.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Last transaction of `address` covered by a poll, including transactions without events.
+    pub fn cursor(&self, address: &Address) -> Option<TransactionCursor> {
+        self.accounts
+            .lock()
+            .unwrap()
+            .get(address)
+            .cloned()
+            .flatten()
+    }
+
+    /// Check every watched account once and return the new events.
+    pub async fn poll(&self) -> Result<Vec<WatchEvent>, ToncenterError> {
+        let accounts = self.accounts.lock().unwrap().clone();
+
+        let mut events = Vec::new();
+        let mut cursors = Vec::new();
+        for (address, cursor) in accounts {
+            let Some(cursor) = cursor else {
+                let last = self
+                    .client
+                    .get_address_information(&address.to_string())
+                    .await?
+                    .last_transaction_id;
+                let lt = parse_lt(&last.lt)?;
+                cursors.push((
+                    address,
+                    None,
+                    TransactionCursor {
+                        lt,
+                        hash: last.hash,
+                    },
+                ));
+                continue;
+            };
+
+            let mut transactions: Vec<RawTransaction> = self
+                .client
+                .transaction_stream(address, Some(cursor.lt), PAGE_SIZE)
+                .try_collect()
+                .await?;
+            transactions.reverse();
+
+            let mut last = cursor.clone();
+            for transaction in transactions {
+                last = TransactionCursor {
+                    lt: parse_lt(&transaction.transaction_id.lt)?,
+                    hash: transaction.transaction_id.hash.clone(),
+                };
+                events.extend(WatchEvent::from_transaction(address, &transaction)?);
+            }
+            cursors.push((address, Some(cursor), last));
+        }
+
+        // Only advance cursors that were not moved or removed during the poll
+        let mut accounts = self.accounts.lock().unwrap();
+        for (address, old, new) in cursors {
+            if let Some(cursor) = accounts.get_mut(&address) {
+                if *cursor == old {
+                    *cursor = Some(new);
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Poll every `poll_interval` and yield events as they are found.
+    ///
+    /// The stream ends after yielding an error. A failed poll moves no cursor, so a new
+    /// stream picks up where the failed one stopped.
+    pub fn stream(
+        &self,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<WatchEvent, ToncenterError>> + '_ {
+        let state = (VecDeque::new(), true);
+        stream::try_unfold(state, move |(mut buffer, mut first)| async move {
+            while buffer.is_empty() {
+                if !first {
+                    tokio::time::sleep(poll_interval).await;
+                }
+                first = false;
+                buffer.extend(self.poll().await?);
+            }
+            let event = buffer.pop_front();
+            Ok(event.map(|event| (event, (buffer, first))))
+        })
+    }
+
+    /// Poll like [`Self::stream`], keeping cursors in `store` across restarts.
+    ///
+    /// Accounts watched without a cursor resume from the one in `store` when there is one.
+    /// The cursor of an event is stored when the next event is requested, and the cursors of
+    /// every account once all events of a poll are handled, so that transactions without
+    /// events are not fetched again. Delivery stays at least once: deduplicate by
+    /// [`WatchEvent::idempotency_key`].
+    pub fn stream_with_checkpoints<'s, S: CheckpointStore>(
+        &'s self,
+        store: &'s S,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<WatchEvent, ToncenterError>> + 's {
+        let state = (VecDeque::new(), true, None::<WatchEvent>);
+        stream::try_unfold(state, move |(mut buffer, mut first, handled)| async move {
+            if let Some(event) = handled {
+                store
+                    .set_account_cursor(&event.address, &event.cursor())
+                    .await?;
+            }
+            while buffer.is_empty() {
+                if !first {
+                    self.store_cursors(store).await?;
+                    tokio::time::sleep(poll_interval).await;
+                }
+                first = false;
+                self.load_cursors(store).await?;
+                buffer.extend(self.poll().await?);
+            }
+            let event = buffer.pop_front();
+            Ok(event.map(|event| (event.clone(), (buffer, first, Some(event)))))
+        })
+    }
+
+    /// Set the cursor of accounts watched without one from `store`.
+    async fn load_cursors<S: CheckpointStore>(&self, store: &S) -> Result<(), ToncenterError> {
+        let unknown: Vec<Address> = self
+            .accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, cursor)| cursor.is_none())
+            .map(|(address, _)| *address)
+            .collect();
+        for address in unknown {
+            let Some(stored) = store.account_cursor(&address).await? else {
+                continue;
+            };
+            if let Some(cursor) = self.accounts.lock().unwrap().get_mut(&address) {
+                cursor.get_or_insert(stored);
+            }
+        }
+        Ok(())
+    }
+
+    async fn store_cursors<S: CheckpointStore>(&self, store: &S) -> Result<(), ToncenterError> {
+        let cursors: Vec<(Address, TransactionCursor)> = self
+            .accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(address, cursor)| Some((*address, cursor.clone()?)))
+            .collect();
+        for (address, cursor) in cursors {
+            store.set_account_cursor(&address, &cursor).await?;
+        }
+        Ok(())
+    }
+}
+
+fn classify(message: &RawMessage) -> Option<WatchEventKind> {
+    let from: Address = message
+        .source
+        .as_deref()
+        .filter(|source| !source.is_empty())?
+        .parse()
+        .ok()?;
+    let amount = Coins::from_nanotons(message.value.parse().ok()?);
+    let body = message
+        .msg_data
+        .body
+        .as_deref()
+        .and_then(|body| Cell::from_boc_b64(body).ok())
+        .map(Cell::to_arc);
+
+    let op = body.as_ref().and_then(|body| body.parser().load_u32().ok());
+    if op == Some(BOUNCE_PREFIX) {
+        return Some(WatchEventKind::Bounced { from, amount });
+    }
+    if op == Some(TRANSFER_NOTIFICATION_OP) {
+        if let Some(kind) = body
+            .as_ref()
+            .and_then(|body| jetton_notification(from, body))
+        {
+            return Some(kind);
+        }
+    }
+    if amount.nanotons() == 0 {
+        return None;
+    }
+
+    let comment = match &body {
+        Some(body) => parse_comment(body),
+        // Toncenter decodes text comments itself
+        None => message.message.clone().filter(|text| !text.is_empty()),
+    };
+    Some(WatchEventKind::TonReceived {
+        from,
+        amount,
+        comment,
+    })
+}
+
+/// `transfer_notification#7362d09c query_id:uint64 amount:Coins sender:MsgAddress
+/// forward_payload:(Either Cell ^Cell)`.
+fn jetton_notification(jetton_wallet: Address, body: &ArcCell) -> Option<WatchEventKind> {
+    let mut slice = body.parser();
+    slice.load_u32().ok()?;
+    let query_id = slice.load_u64().ok()?;
+    let amount = slice.load_coins().ok()?;
+    let sender = slice.load_optional_address().ok()?;
+    let forward_payload = match slice.load_bit() {
+        Ok(true) => slice.load_reference().ok(),
+        Ok(false) => slice.to_cell().ok(),
+        Err(_) => None,
+    };
+    Some(WatchEventKind::JettonReceived {
+        jetton_wallet,
+        sender,
+        amount,
+        query_id,
+        comment: forward_payload.as_ref().and_then(parse_comment),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::CellBuilder;
+    use crate::message::comment_body;
+    use crate::models::MsgDataRaw;
+
+    const SENDER: &str = "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N";
+    const WATCHED: &str = "EQBfAN7LfaUYgXZNw5Wc7GBgkEX2yhuJ5ka95J1JJwXXf4a8";
+
+    fn message(source: Option<&str>, value: u64, msg_data: MsgDataRaw) -> RawMessage {
+        RawMessage {
+            source: source.map(str::to_string),
+            destination: WATCHED.to_string(),
+            value: value.to_string(),
+            fwd_fee: "266669".to_string(),
+            ihr_fee: "0".to_string(),
+            created_lt: "47584223000002".to_string(),
+            body_hash: String::new(),
+            msg_data,
+            message: None,
+        }
+    }
+
+    fn raw_data(body: &ArcCell) -> MsgDataRaw {
+        MsgDataRaw {
+            type_field: "msg.dataRaw".to_string(),
+            body: Some(body.to_boc_b64().unwrap()),
+            init_state: Some(String::new()),
+            text: None,
+        }
+    }
+
+    fn sender() -> Address {
+        SENDER.parse().unwrap()
+    }
+
+    fn notification(forward_payload_by_ref: bool) -> ArcCell {
+        let owner: Address = WATCHED.parse().unwrap();
+        let comment = comment_body("for order 42").unwrap();
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(TRANSFER_NOTIFICATION_OP)
+            .unwrap()
+            .store_u64(7)
+            .unwrap()
+            .store_coins(1_500_000)
+            .unwrap()
+            .store_address(&owner)
+            .unwrap()
+            .store_bit(forward_payload_by_ref)
+            .unwrap();
+        if forward_payload_by_ref {
+            builder.store_reference(comment).unwrap();
+        } else {
+            builder.store_cell(&comment).unwrap();
+        }
+        builder.build().unwrap().to_arc()
+    }
+
+    #[test]
+    fn transfer_with_comment() {
+        let body = comment_body("hello").unwrap();
+        let message = message(Some(SENDER), 1_000_000_000, raw_data(&body));
+        assert_eq!(
+            classify(&message),
+            Some(WatchEventKind::TonReceived {
+                from: sender(),
+                amount: Coins::from_ton(1),
+                comment: Some("hello".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn transfer_with_decoded_comment() {
+        let mut message = message(
+            Some(SENDER),
+            5,
+            MsgDataRaw {
+                type_field: "msg.dataText".to_string(),
+                body: None,
+                init_state: None,
+                text: Some("aGk=".to_string()),
+            },
+        );
+        message.message = Some("hi".to_string());
+        assert_eq!(
+            classify(&message),
+            Some(WatchEventKind::TonReceived {
+                from: sender(),
+                amount: Coins::from_nanotons(5),
+                comment: Some("hi".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn jetton_notification_with_inline_payload() {
+        let message = message(Some(SENDER), 1, raw_data(&notification(false)));
+        assert_eq!(
+            classify(&message),
+            Some(WatchEventKind::JettonReceived {
+                jetton_wallet: sender(),
+                sender: Some(WATCHED.parse().unwrap()),
+                amount: 1_500_000,
+                query_id: 7,
+                comment: Some("for order 42".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn jetton_notification_with_referenced_payload() {
+        let message = message(Some(SENDER), 1, raw_data(&notification(true)));
+        let Some(WatchEventKind::JettonReceived {
+            amount, comment, ..
+        }) = classify(&message)
+        else {
+            panic!("expected a jetton transfer");
+        };
+        assert_eq!(amount, 1_500_000);
+        assert_eq!(comment.as_deref(), Some("for order 42"));
+    }
+
+    #[test]
+    fn bounce() {
+        let mut body = CellBuilder::new();
+        body.store_u32(BOUNCE_PREFIX)
+            .unwrap()
+            .store_u32(0x0f8a7ea5)
+            .unwrap();
+        let body = body.build().unwrap().to_arc();
+        let message = message(Some(SENDER), 900_000_000, raw_data(&body));
+        assert_eq!(
+            classify(&message),
+            Some(WatchEventKind::Bounced {
+                from: sender(),
+                amount: Coins::from_nanotons(900_000_000),
+            })
+        );
+    }
+
+    #[test]
+    fn zero_value_message() {
+        let body = comment_body("hello").unwrap();
+        assert_eq!(classify(&message(Some(SENDER), 0, raw_data(&body))), None);
+    }
+
+    #[test]
+    fn external_message() {
+        let body = comment_body("hello").unwrap();
+        assert_eq!(classify(&message(Some(""), 0, raw_data(&body))), None);
+        assert_eq!(classify(&message(None, 0, raw_data(&body))), None);
+    }
+
+    #[test]
+    fn event_from_transaction() {
+        let body = comment_body("hello").unwrap();
+        let mut transaction: RawTransaction = serde_json::from_value(serde_json::json!({
+            "@type": "raw.transaction",
+            "address": { "@type": "accountAddress", "account_address": WATCHED },
+            "utime": 1717600385,
+            "data": "",
+            "transaction_id": {
+                "@type": "internal.transactionId",
+                "lt": "47584224000001",
+                "hash": "8sOCHXuiGgEFJhDiHPvYUUCzMvUIlzmbfKUdZHtaQ9M="
+            },
+            "fee": "0",
+            "storage_fee": "0",
+            "other_fee": "0",
+            "in_msg": null,
+            "out_msgs": []
+        }))
+        .unwrap();
+        let watched: Address = WATCHED.parse().unwrap();
+        assert_eq!(
+            WatchEvent::from_transaction(watched, &transaction).unwrap(),
+            None
+        );
+
+        transaction.in_msg = Some(message(Some(SENDER), 1_000_000_000, raw_data(&body)));
+        let event = WatchEvent::from_transaction(watched, &transaction)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.lt, 47584224000001);
+        assert_eq!(
+            event.idempotency_key(),
+            "47584224000001:8sOCHXuiGgEFJhDiHPvYUUCzMvUIlzmbfKUdZHtaQ9M="
+        );
+
+        transaction.transaction_id.lt = "latest".to_string();
+        assert!(matches!(
+            WatchEvent::from_transaction(watched, &transaction),
+            Err(ToncenterError::ProcessingError(_))
+        ));
+    }
+}
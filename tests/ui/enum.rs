@@ -0,0 +1,9 @@
+use toncenter::tvm::FromStack;
+
+#[derive(FromStack)]
+enum State {
+    Active,
+    Frozen,
+}
+
+fn main() {}
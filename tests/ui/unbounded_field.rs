@@ -0,0 +1,12 @@
+use toncenter::tvm::{FromStack, StackEntry};
+
+#[derive(FromStack)]
+struct Wrapper<T> {
+    value: T,
+}
+
+struct NotOnStack;
+
+fn main() {
+    let _ = Wrapper::<NotOnStack>::from_stack(&[StackEntry::Null]);
+}
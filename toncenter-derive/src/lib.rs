@@ -0,0 +1,88 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields};
+
+/// Derive `FromStack` and `FromStackEntry` for a struct.
+///
+/// Fields are read from the stack in declaration order, so the first field maps to
+/// the first returned value. The struct can also be used as a field of another
+/// derived struct, in which case it is read from a nested tuple entry.
+///
+/// The generated code refers to the traits through absolute `::toncenter::` paths, so it
+/// compiles in crates depending on `toncenter` under that name. Inside `toncenter` itself
+/// this relies on the `extern crate self as toncenter;` declaration in its `lib.rs`, which
+/// must be kept for the derive to be usable there.
+///
+/// Every type parameter of the struct gets a `FromStackEntry` bound in the generated impls.
+#[proc_macro_derive(FromStack)]
+pub fn derive_from_stack(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as DeriveInput)).into()
+}
+
+fn expand(mut input: DeriveInput) -> proc_macro2::TokenStream {
+    let params: Vec<_> = input
+        .generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = input.generics.make_where_clause();
+    for param in params {
+        where_clause
+            .predicates
+            .push(parse_quote! { #param: ::toncenter::tvm::FromStackEntry });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return syn::Error::new_spanned(
+                &input.ident,
+                "FromStack can only be derived for structs",
+            )
+            .to_compile_error()
+        }
+    };
+
+    let body = match fields {
+        Fields::Named(fields) => {
+            let values = fields.named.iter().enumerate().map(|(index, field)| {
+                let ident = &field.ident;
+                quote! { #ident: ::toncenter::tvm::stack_field(stack, #index)? }
+            });
+            quote! { Self { #(#values),* } }
+        }
+        Fields::Unnamed(fields) => {
+            let values = fields.unnamed.iter().enumerate().map(|(index, _)| {
+                quote! { ::toncenter::tvm::stack_field(stack, #index)? }
+            });
+            quote! { Self(#(#values),*) }
+        }
+        Fields::Unit => quote! { Self },
+    };
+
+    let stack_ident = if fields.is_empty() {
+        quote! { _stack }
+    } else {
+        quote! { stack }
+    };
+    quote! {
+        impl #impl_generics ::toncenter::tvm::FromStack for #name #ty_generics #where_clause {
+            fn from_stack(
+                #stack_ident: &[::toncenter::tvm::StackEntry],
+            ) -> ::std::result::Result<Self, ::toncenter::error::StackError> {
+                ::std::result::Result::Ok(#body)
+            }
+        }
+
+        impl #impl_generics ::toncenter::tvm::FromStackEntry for #name #ty_generics #where_clause {
+            fn from_stack_entry(
+                entry: &::toncenter::tvm::StackEntry,
+            ) -> ::std::result::Result<Self, ::toncenter::error::StackError> {
+                ::toncenter::tvm::nested_from_stack(entry)
+            }
+        }
+    }
+}